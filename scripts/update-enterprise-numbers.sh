@@ -0,0 +1,31 @@
+#!/bin/sh
+# Скачивает полный реестр IANA Private Enterprise Numbers.
+# Файл по умолчанию - ./data/enterprise-numbers.txt (vendors.enterprise_numbers в настройках);
+# пока его нет, используется выборка vendors/enterprise-numbers.txt.
+# Реестр применяется после перезапуска сервера.
+#
+#   scripts/update-enterprise-numbers.sh [файл]
+set -eu
+
+URL="${IANA_PEN_URL:-https://www.iana.org/assignments/enterprise-numbers.txt}"
+OUT="${1:-./data/enterprise-numbers.txt}"
+TMP="$OUT.tmp"
+
+mkdir -p "$(dirname "$OUT")"
+trap 'rm -f "$TMP"' EXIT
+
+curl -fsSL --retry 3 -o "$TMP" "$URL"
+
+# Защита от страницы с ошибкой вместо реестра
+if ! head -n 5 "$TMP" | grep -q "PRIVATE ENTERPRISE NUMBERS"; then
+    echo "Не похоже на реестр IANA PEN: $URL" >&2
+    exit 1
+fi
+COUNT=$(grep -c '^[0-9][0-9]*$' "$TMP" || true)
+if [ "$COUNT" -lt 1000 ]; then
+    echo "В реестре слишком мало записей ($COUNT): $URL" >&2
+    exit 1
+fi
+
+mv "$TMP" "$OUT"
+echo "Реестр IANA PEN: $COUNT организаций, $OUT"
@@ -0,0 +1,144 @@
+use clap::{Parser, Subcommand, ValueEnum};
+
+/// Сбор данных по SNMP
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Файл настроек (YAML или TOML); по умолчанию SNMP_SETTINGS или ./settings.yaml
+    #[arg(long, short, global = true)]
+    pub config: Option<String>,
+
+    /// Переопределить настройку, можно несколько раз: --set connection.timeout=5
+    #[arg(long = "set", value_name = "KEY=VALUE", global = true, value_parser = parse_key_value)]
+    pub set: Vec<(String, String)>,
+
+    /// Адрес HTTP сервера (server.bind)
+    #[arg(long, global = true)]
+    pub bind: Option<String>,
+
+    /// Уровень логирования в формате RUST_LOG (logging.level)
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// Таймаут SNMP операций в секундах (connection.timeout)
+    #[arg(long, global = true)]
+    pub timeout: Option<u64>,
+
+    /// Community для SNMPv2c (auth.v2c.community)
+    #[arg(long, global = true)]
+    pub community: Option<String>,
+
+    /// Собирать таблицы (collection.collect_tables)
+    #[arg(long, global = true)]
+    pub collect_tables: Option<bool>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Запустить HTTP сервер (по умолчанию)
+    Serve,
+    /// Запустить HTTP сервер и периодический опрос устройств по расписанию
+    Daemon,
+    /// Инвентарь устройств: загрузка из файлов и просмотр
+    Inventory {
+        #[command(subcommand)]
+        action: InventoryCommand,
+    },
+    /// Опросить устройства инвентаря одновременно (лимиты - fleet в настройках)
+    /// и вывести результаты в JSON Lines по мере готовности
+    Collect {
+        /// Имена устройств; по умолчанию - все
+        names: Vec<String>,
+        /// Только устройства с этим тегом
+        #[arg(long)]
+        tag: Option<String>,
+        /// Добавить в таблицы сырой список varbind'ов
+        #[arg(long)]
+        varbinds: bool,
+    },
+    /// Проверить профили: OID, суффиксы скаляров, корни таблиц, дубликаты, пересечения
+    Lint {
+        /// Файлы или каталоги профилей; по умолчанию - каталог профилей из настроек
+        paths: Vec<String>,
+        /// Вывести результат в JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Показать итоговые настройки после всех слоев (файл, окружение, флаги)
+    Config {
+        /// Формат вывода
+        #[arg(long, value_enum, default_value_t = ConfigFormat::Yaml)]
+        format: ConfigFormat,
+        /// Показать, откуда взято каждое значение
+        #[arg(long)]
+        sources: bool,
+        /// Не скрывать пароли и community
+        #[arg(long)]
+        show_secrets: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+pub enum InventoryCommand {
+    /// Загрузить устройства из YAML или CSV (по расширению .csv)
+    Import {
+        #[arg(required = true)]
+        files: Vec<String>,
+        /// Удалить из инвентаря устройства, которых нет в файлах
+        #[arg(long)]
+        replace: bool,
+    },
+    /// Вывести устройства в YAML (формат файла для import)
+    List {
+        /// Только устройства с этим тегом
+        #[arg(long)]
+        tag: Option<String>,
+        /// Вывести в JSON
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl Cli {
+    /// Переопределения настроек из флагов: сначала отдельные флаги, затем --set
+    pub fn overrides(&self) -> Vec<(String, String)> {
+        let flags = [
+            ("server.bind", self.bind.clone()),
+            ("logging.level", self.log_level.clone()),
+            ("connection.timeout", self.timeout.map(|t| t.to_string())),
+            ("auth.v2c.community", self.community.clone()),
+            ("collection.collect_tables", self.collect_tables.map(|c| c.to_string())),
+        ];
+
+        flags
+            .into_iter()
+            .filter_map(|(key, value)| Some((key.to_string(), value?)))
+            .chain(
+                matches!(self.command, Some(Command::Daemon))
+                    .then(|| ("scheduler.enabled".to_string(), "true".to_string())),
+            )
+            .chain(self.set.iter().cloned())
+            .collect()
+    }
+}
+
+fn parse_key_value(text: &str) -> Result<(String, String), String> {
+    let (key, value) = text
+        .split_once('=')
+        .ok_or_else(|| format!("ожидается KEY=VALUE, получено '{}'", text))?;
+    let key = key.trim();
+    if key.is_empty() {
+        return Err(format!("пустой ключ в '{}'", text));
+    }
+    Ok((key.to_string(), value.to_string()))
+}
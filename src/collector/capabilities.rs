@@ -0,0 +1,279 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{LazyLock, Mutex};
+use snmp2::Oid;
+use tokio::time::{Duration, timeout};
+
+use crate::config::{AppConfig, Profile, Settings};
+use crate::snmp::{
+    DeviceInfo, SnmpClient, create_v2c_client, create_v3_client_auth_priv, parse_oid,
+};
+
+/// Таймаут одного запроса при проверке
+const PROBE_TIMEOUT: Duration = Duration::from_secs(3);
+/// Сколько OID проверяется одним GET
+const PROBE_BATCH: usize = 16;
+const SYS_OBJECT_ID: &str = "1.3.6.1.2.1.1.2.0";
+
+/// Что есть на устройстве: какие OID отвечают и по каким версиям SNMP
+#[derive(Debug, Clone, Serialize)]
+pub struct DeviceCapabilities {
+    pub target: String,
+    pub probed_at: DateTime<Utc>,
+    /// "SNMPv2c"/"SNMPv3" -> отвечает ли агент
+    pub versions: BTreeMap<String, bool>,
+    /// OID скаляра или корня поддерева -> есть ли на устройстве
+    pub oids: BTreeMap<String, bool>,
+    /// Устройство не ответило (таймаут или агент не отвечает по версии опроса):
+    /// OID не проверялись и до истечения ttl
+    /// не проверяются, условия when_exists не действуют
+    pub unreachable: bool,
+    /// Устройство, определенное перед проверкой: до истечения ttl не определяется заново
+    #[serde(skip)]
+    pub device: Option<DeviceInfo>,
+}
+
+impl DeviceCapabilities {
+    /// Есть ли OID на устройстве. Нет проверенного предка - значит нет и его;
+    /// None - OID не проверялся или проверка не удалась
+    pub fn exists(&self, oid: &str) -> Option<bool> {
+        if let Some(exists) = self.oids.get(oid) {
+            return Some(*exists);
+        }
+        let missing_parent = self.oids.iter().any(|(probed, exists)| {
+            !exists
+                && oid
+                    .strip_prefix(probed.as_str())
+                    .is_some_and(|rest| rest.starts_with('.'))
+        });
+        missing_parent.then_some(false)
+    }
+
+    /// OID, которых нет на устройстве
+    pub fn missing(&self) -> impl Iterator<Item = &String> {
+        self.oids
+            .iter()
+            .filter(|(_, exists)| !**exists)
+            .map(|(oid, _)| oid)
+    }
+}
+
+static CACHE: LazyLock<Mutex<HashMap<String, DeviceCapabilities>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Кэш проверок по адресу устройства, общий для всех опросов процесса
+pub struct CapabilityCache;
+
+impl CapabilityCache {
+    /// Результат проверки, если он не старше ttl секунд
+    pub fn get(target: &str, ttl: u64) -> Option<DeviceCapabilities> {
+        let cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        let capabilities = cache.get(target)?;
+        let age = Utc::now() - capabilities.probed_at;
+        (age.num_seconds() < ttl as i64).then(|| capabilities.clone())
+    }
+
+    pub fn store(capabilities: DeviceCapabilities) {
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        cache.insert(capabilities.target.clone(), capabilities);
+    }
+
+    /// Забыть устройство: следующий опрос проверит его заново
+    pub fn invalidate(target: &str) {
+        let mut cache = CACHE.lock().unwrap_or_else(|e| e.into_inner());
+        cache.remove(target);
+    }
+}
+
+/// Проверка устройства перед сбором: версии SNMP и OID профиля
+pub struct CapabilityProbe;
+
+impl CapabilityProbe {
+    /// Возможности устройства из кэша; OID профиля, которых в кэше нет, проверяются и дописываются.
+    /// Первый таймаут останавливает проверку: недоступное устройство не ждет каждый OID.
+    /// device запоминается вместе с новой проверкой
+    pub async fn capabilities(
+        client: &mut SnmpClient,
+        config: &AppConfig,
+        device: &DeviceInfo,
+    ) -> DeviceCapabilities {
+        let settings = &config.settings;
+        let target = client.target().to_string();
+
+        let mut capabilities =
+            match CapabilityCache::get(&target, settings.collection.capabilities_ttl) {
+                Some(capabilities) => capabilities,
+                None => {
+                    let (versions, answered) = Self::probe_versions(client, settings).await;
+                    DeviceCapabilities {
+                        versions,
+                        target,
+                        probed_at: Utc::now(),
+                        oids: BTreeMap::new(),
+                        unreachable: !answered,
+                        device: Some(device.clone()),
+                    }
+                }
+            };
+        if capabilities.unreachable {
+            CapabilityCache::store(capabilities.clone());
+            return capabilities;
+        }
+
+        let needed: Vec<String> =
+            Self::profile_oids(&config.profile, settings.should_collect_tables())
+                .into_iter()
+                .filter(|oid| !capabilities.oids.contains_key(oid))
+                .collect();
+        match Self::probe_oids(client, &needed).await {
+            Some(found) => {
+                if !found.is_empty() {
+                    tracing::debug!(
+                        "{}: проверено OID: {}, нет на устройстве: {}",
+                        capabilities.target,
+                        found.len(),
+                        found.values().filter(|exists| !**exists).count()
+                    );
+                }
+                capabilities.oids.extend(found);
+            }
+            None => {
+                tracing::debug!(
+                    "{}: таймаут при проверке OID, устройство считается недоступным",
+                    capabilities.target
+                );
+                capabilities.unreachable = true;
+            }
+        }
+
+        CapabilityCache::store(capabilities.clone());
+        capabilities
+    }
+
+    /// OID, которые понадобятся для сбора по профилю
+    fn profile_oids(profile: &Profile, with_tables: bool) -> BTreeSet<String> {
+        let scalars = profile.scalars.values().flat_map(|scalar| {
+            std::iter::once(scalar.oid.clone()).chain(scalar.when.when_exists.clone())
+        });
+        let tables = profile
+            .tables
+            .values()
+            .filter(|_| with_tables)
+            .flat_map(|table| {
+                std::iter::once(table.oid.clone()).chain(table.when.when_exists.clone())
+            });
+        let joins = profile
+            .joins
+            .values()
+            .filter(|_| with_tables)
+            .filter_map(|join| join.when.when_exists.clone());
+
+        scalars.chain(tables).chain(joins).collect()
+    }
+
+    /// Есть ли OID на устройстве: GET пачками, для не найденных GET - первый шаг обхода
+    /// (корни таблиц). OID с ошибкой в ответе не попадают в результат, проверятся
+    /// в следующий раз. None - таймаут, дальше не проверяем
+    async fn probe_oids(client: &mut SnmpClient, oids: &[String]) -> Option<BTreeMap<String, bool>> {
+        let mut found = BTreeMap::new();
+        let mut subtrees = Vec::new();
+
+        let parsed: Vec<(&String, Oid<'_>)> = oids
+            .iter()
+            .filter_map(|oid| Some((oid, parse_oid(oid).ok()?)))
+            .collect();
+        for batch in parsed.chunks(PROBE_BATCH) {
+            let request: Vec<&Oid<'_>> = batch.iter().map(|(_, oid)| oid).collect();
+            let values = match timeout(PROBE_TIMEOUT, client.get_many(&request)).await {
+                Ok(Ok(values)) => values,
+                Ok(Err(e)) => {
+                    tracing::debug!("{}: проверка OID не удалась: {:#}", client.target(), e);
+                    continue;
+                }
+                Err(_) => return None,
+            };
+            for ((oid, _), (_, value)) in batch.iter().zip(values) {
+                if Self::is_missing(&value) {
+                    subtrees.push(*oid);
+                } else {
+                    found.insert(oid.to_string(), true);
+                }
+            }
+        }
+
+        for oid_str in subtrees {
+            let Ok(oid) = parse_oid(oid_str) else {
+                continue;
+            };
+            match timeout(PROBE_TIMEOUT, client.walk_limited(&oid, Some(1), 1)).await {
+                Ok(Ok(walk)) => {
+                    found.insert(oid_str.clone(), !walk.items.is_empty());
+                }
+                Ok(Err(_)) => {}
+                Err(_) => return None,
+            }
+        }
+        Some(found)
+    }
+
+    fn is_missing(value: &str) -> bool {
+        matches!(
+            value,
+            "NO SUCH OBJECT" | "NO SUCH INSTANCE" | "END OF MIB VIEW"
+        )
+    }
+
+    /// Какие версии SNMP отвечают: текущая - через клиент опроса, другая - отдельной
+    /// сессией с учетными данными из настроек. Второе значение - ответил ли агент по
+    /// версии опроса: если нет, другая версия не проверяется
+    async fn probe_versions(
+        client: &mut SnmpClient,
+        settings: &Settings,
+    ) -> (BTreeMap<String, bool>, bool) {
+        let mut versions = BTreeMap::new();
+        let answers = Self::answers(client).await;
+        versions.insert(client.version().to_string(), answers);
+        if !answers {
+            return (versions, false);
+        }
+
+        let target = client.target().to_string();
+        let other = match client {
+            SnmpClient::V2c(_) if !settings.auth.v3.username.is_empty() => {
+                let create = create_v3_client_auth_priv(
+                    &target,
+                    settings.auth.v3.username.as_bytes(),
+                    settings.auth.v3.auth_password.as_bytes(),
+                    settings.get_auth_protocol(),
+                    settings.get_privacy_protocol(),
+                    settings.auth.v3.privacy_password.as_bytes(),
+                );
+                Some(("SNMPv3", timeout(PROBE_TIMEOUT, create).await))
+            }
+            SnmpClient::V3(_) => {
+                let create = create_v2c_client(&target, settings.auth.v2c.community.as_bytes());
+                Some(("SNMPv2c", timeout(PROBE_TIMEOUT, create).await))
+            }
+            _ => None,
+        };
+
+        if let Some((version, created)) = other {
+            let answers = match created {
+                Ok(Ok(mut other)) => Self::answers(&mut other).await,
+                _ => false,
+            };
+            versions.insert(version.to_string(), answers);
+        }
+
+        (versions, true)
+    }
+
+    /// Агент отвечает на GET sysObjectID.0
+    async fn answers(client: &mut SnmpClient) -> bool {
+        let Ok(oid) = parse_oid(SYS_OBJECT_ID) else {
+            return false;
+        };
+        matches!(timeout(PROBE_TIMEOUT, client.get(&oid)).await, Ok(Ok(_)))
+    }
+}
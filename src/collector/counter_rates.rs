@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+use tokio::time::{Duration, Instant, timeout};
+
+use super::types::{CounterRate, RateState, ScalarResult, TableResult, TableRow};
+use crate::config::AppConfig;
+use crate::snmp::{SnmpClient, parse_oid, value_to_string};
+
+const SYS_UP_TIME: &str = "1.3.6.1.2.1.1.3.0";
+/// Прошлый опрос старше этого - скорость не считается, как при первом опросе
+const MAX_SAMPLE_AGE: Duration = Duration::from_secs(6 * 3600);
+/// TimeTicks - 32 бита, sysUpTime переполняется через ~497 дней
+const TIMETICKS_MODULUS: u64 = 1 << 32;
+/// Допустимое расхождение sysUpTime с часами коллектора при переполнении, секунды
+const UPTIME_TOLERANCE: f64 = 5.0;
+
+/// Значение счетчика и его разрядность
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Sample {
+    value: u64,
+    bits: u8,
+}
+
+/// Значение счетчика с моментом опроса
+#[derive(Debug, Clone, Copy)]
+struct Stored {
+    sample: Sample,
+    taken_at: Instant,
+    /// sysUpTime.0 в сотых долях секунды
+    uptime: Option<u64>,
+}
+
+/// Прошлые значения счетчиков устройства. Части профиля могут опрашиваться в разное
+/// время (группы метрик), поэтому момент опроса хранится у каждого значения
+#[derive(Default)]
+struct DeviceSamples {
+    /// Последний sysUpTime и когда он получен, для сообщения о перезагрузке
+    uptime: Option<(u64, Instant)>,
+    /// OID скаляра или "таблица/колонка/индекс" -> значение
+    counters: HashMap<String, Stored>,
+    /// "таблица/индекс" -> время сброса счетчиков строки
+    discontinuity: HashMap<String, String>,
+}
+
+static SAMPLES: LazyLock<Mutex<HashMap<String, DeviceSamples>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+/// Скорости счетчиков по предыдущему опросу того же устройства
+pub struct CounterRates;
+
+impl CounterRates {
+    /// Считает скорости Counter32/Counter64 у скаляров и в строках таблиц и запоминает
+    /// значения для следующего опроса. Для счетчиков с выбранной разрядностью (HC) берется
+    /// выбранное значение. Вызывается до преобразований значений
+    pub async fn apply(
+        client: &mut SnmpClient,
+        scalars: &mut [ScalarResult],
+        tables: &mut [TableResult],
+        config: &AppConfig,
+    ) {
+        if !config.settings.collection.counter_rates {
+            return;
+        }
+
+        let uptime = match scalars.iter().find(|scalar| scalar.oid == SYS_UP_TIME) {
+            Some(scalar) => scalar.value.as_deref().and_then(Self::timeticks),
+            None => Self::fetch_uptime(client).await,
+        };
+        let now = Instant::now();
+        let target = client.target().to_string();
+
+        let fresh = |stored: &Stored| now.duration_since(stored.taken_at) <= MAX_SAMPLE_AGE;
+        let mut samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+        // Устройства, которые давно не опрашивались (удалены, сменили адрес), не копятся
+        samples.retain(|name, device| *name == target || device.counters.values().any(fresh));
+        let device = samples.entry(target.clone()).or_default();
+        device.counters.retain(|_, stored| fresh(stored));
+        if let (Some((before, taken_at)), Some(after)) = (device.uptime, uptime)
+            && Self::uptime_delta(before, after, now.duration_since(taken_at)).is_none()
+        {
+            tracing::info!("{}: sysUpTime уменьшился, счетчики сброшены", target);
+        }
+        if let Some(uptime) = uptime {
+            device.uptime = Some((uptime, now));
+        }
+
+        let stored = |sample| Stored {
+            sample,
+            taken_at: now,
+            uptime,
+        };
+
+        for scalar in scalars.iter_mut() {
+            let Some(sample) = scalar.value.as_deref().and_then(Self::counter) else {
+                continue;
+            };
+            let rate = Self::observe(
+                &mut device.counters,
+                scalar.oid.clone(),
+                stored(sample),
+                false,
+            );
+            scalar.rate = Some(rate);
+        }
+
+        let discontinuity = Self::discontinuity_columns(tables, config);
+        for table in tables.iter_mut().filter(|table| table.error.is_none()) {
+            let column = config
+                .profile
+                .tables
+                .get(&table.name)
+                .and_then(|table| table.discontinuity.as_ref());
+
+            for row in table.rows.iter_mut() {
+                let reset_time = column.and_then(|column| {
+                    row.values
+                        .get(column)
+                        .or_else(|| discontinuity.get(column)?.get(&row.index))
+                        .cloned()
+                });
+                let reset = match reset_time {
+                    Some(time) => device
+                        .discontinuity
+                        .insert(format!("{}/{}", table.name, row.index), time.clone())
+                        .is_some_and(|before| before != time),
+                    None => false,
+                };
+
+                for (column, sample) in Self::row_counters(row) {
+                    let key = format!("{}/{}/{}", table.name, column, row.index);
+                    let rate = Self::observe(&mut device.counters, key, stored(sample), reset);
+                    row.rates.insert(column, rate);
+                }
+            }
+        }
+    }
+
+    /// Забыть прошлый опрос устройства: следующая скорость будет как при первом опросе
+    pub fn forget(target: &str) {
+        let mut samples = SAMPLES.lock().unwrap_or_else(|e| e.into_inner());
+        samples.remove(target);
+    }
+
+    /// Запоминает значение и возвращает скорость относительно прошлого
+    fn observe(
+        counters: &mut HashMap<String, Stored>,
+        key: String,
+        current: Stored,
+        reset: bool,
+    ) -> CounterRate {
+        let before = counters.insert(key, current);
+        Self::rate(before, current, reset)
+    }
+
+    /// Скорость по двум значениям счетчика. Интервал - по sysUpTime агента,
+    /// он точнее часов коллектора
+    fn rate(before: Option<Stored>, after: Stored, reset: bool) -> CounterRate {
+        let Some(before) = before else {
+            return CounterRate {
+                per_second: None,
+                state: RateState::First,
+                interval: None,
+            };
+        };
+        let elapsed = after.taken_at.duration_since(before.taken_at);
+        let (interval, rebooted) = match (before.uptime, after.uptime) {
+            (Some(up_before), Some(up_after)) => {
+                match Self::uptime_delta(up_before, up_after, elapsed) {
+                    Some(delta) => (Some(delta as f64 / 100.0), false),
+                    None => (None, true),
+                }
+            }
+            _ => (Some(elapsed.as_secs_f64()), false),
+        };
+        let interval = interval.filter(|interval| *interval > 0.0);
+
+        let (before, after) = (before.sample, after.sample);
+        let state = if reset || rebooted || before.bits != after.bits {
+            // 32-битный счетчик сменился на HC или обратно - значения несравнимы
+            RateState::Reset
+        } else if after.value < before.value {
+            RateState::Wrapped
+        } else {
+            RateState::Ok
+        };
+
+        let delta = match state {
+            RateState::Ok => Some(after.value - before.value),
+            RateState::Wrapped => {
+                let modulus = 1u128 << after.bits;
+                let delta = modulus - before.value as u128 + after.value as u128;
+                u64::try_from(delta).ok()
+            }
+            _ => None,
+        };
+
+        CounterRate {
+            per_second: delta
+                .zip(interval)
+                .map(|(delta, interval)| delta as f64 / interval),
+            state: if delta.is_some() && interval.is_none() {
+                RateState::First
+            } else {
+                state
+            },
+            interval,
+        }
+    }
+
+    /// Сколько сотых секунды прошло по sysUpTime; None - агент перезагрузился.
+    /// Уменьшение sysUpTime считается переполнением TimeTicks, только если прошедшее
+    /// с ним время сходится с часами коллектора
+    fn uptime_delta(before: u64, after: u64, elapsed: Duration) -> Option<u64> {
+        if after >= before {
+            return Some(after - before);
+        }
+        let wrapped = TIMETICKS_MODULUS.checked_sub(before)? + after;
+        let elapsed = elapsed.as_secs_f64();
+        let tolerance = (elapsed * 0.1).max(UPTIME_TOLERANCE);
+        ((wrapped as f64 / 100.0 - elapsed).abs() <= tolerance).then_some(wrapped)
+    }
+
+    /// Счетчики строки: выбранный (HC) счетчик важнее значения колонки
+    fn row_counters(row: &TableRow) -> Vec<(String, Sample)> {
+        row.values
+            .iter()
+            .filter_map(|(column, raw)| {
+                let raw = row
+                    .counters
+                    .get(column)
+                    .map(|sample| &sample.value)
+                    .unwrap_or(raw);
+                Some((column.clone(), Self::counter(raw)?))
+            })
+            .collect()
+    }
+
+    /// Значения колонок discontinuity из всех таблиц: колонка -> индекс -> значение
+    fn discontinuity_columns(
+        tables: &[TableResult],
+        config: &AppConfig,
+    ) -> HashMap<String, HashMap<String, String>> {
+        let columns: Vec<&str> = config
+            .profile
+            .tables
+            .values()
+            .filter_map(|table| table.discontinuity.as_deref())
+            .collect();
+        if columns.is_empty() {
+            return HashMap::new();
+        }
+
+        let mut values: HashMap<String, HashMap<String, String>> = HashMap::new();
+        for table in tables {
+            for row in &table.rows {
+                for column in &columns {
+                    if let Some(value) = row.values.get(*column) {
+                        values
+                            .entry(column.to_string())
+                            .or_default()
+                            .insert(row.index.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        values
+    }
+
+    /// "COUNTER32: 123" -> значение и разрядность
+    fn counter(raw: &str) -> Option<Sample> {
+        let (kind, payload) = raw.split_once(": ")?;
+        let bits = match kind {
+            "COUNTER32" => 32,
+            "COUNTER64" => 64,
+            _ => return None,
+        };
+        Some(Sample {
+            value: payload.trim().parse().ok()?,
+            bits,
+        })
+    }
+
+    fn timeticks(raw: &str) -> Option<u64> {
+        raw.strip_prefix("TIMETICKS: ")?.trim().parse().ok()
+    }
+
+    async fn fetch_uptime(client: &mut SnmpClient) -> Option<u64> {
+        let oid = parse_oid(SYS_UP_TIME).ok()?;
+        match timeout(Duration::from_secs(3), client.get(&oid)).await {
+            Ok(Ok(value)) => Self::timeticks(&value_to_string(&value)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Пара значений с интервалом 10 секунд
+    fn pair(raw_before: &str, raw_after: &str, uptime: Option<(u64, u64)>) -> (Stored, Stored) {
+        let taken_at = Instant::now();
+        let before = Stored {
+            sample: CounterRates::counter(raw_before).unwrap(),
+            taken_at,
+            uptime: uptime.map(|(before, _)| before),
+        };
+        let after = Stored {
+            sample: CounterRates::counter(raw_after).unwrap(),
+            taken_at: taken_at + Duration::from_secs(10),
+            uptime: uptime.map(|(_, after)| after),
+        };
+        (before, after)
+    }
+
+    #[test]
+    fn parses_counters_only() {
+        assert_eq!(
+            CounterRates::counter("COUNTER64: 18446744073709551615"),
+            Some(Sample {
+                value: u64::MAX,
+                bits: 64
+            })
+        );
+        assert_eq!(CounterRates::counter("GAUGE32: 5"), None);
+        assert_eq!(CounterRates::counter("COUNTER32: x"), None);
+        assert_eq!(CounterRates::timeticks("TIMETICKS: 4200"), Some(4200));
+    }
+
+    #[test]
+    fn first_sample_has_no_rate() {
+        let (_, after) = pair("COUNTER32: 0", "COUNTER32: 100", None);
+
+        let rate = CounterRates::rate(None, after, false);
+
+        assert_eq!(rate.state, RateState::First);
+        assert_eq!(rate.per_second, None);
+    }
+
+    #[test]
+    fn plain_increase_uses_uptime_interval() {
+        let (before, after) = pair("COUNTER32: 1000", "COUNTER32: 3000", Some((500, 1500)));
+
+        let rate = CounterRates::rate(Some(before), after, false);
+
+        assert_eq!(rate.state, RateState::Ok);
+        assert_eq!(rate.interval, Some(10.0));
+        assert_eq!(rate.per_second, Some(200.0));
+    }
+
+    #[test]
+    fn counter32_wrap() {
+        let (before, after) = pair("COUNTER32: 4294967196", "COUNTER32: 900", None);
+
+        let rate = CounterRates::rate(Some(before), after, false);
+
+        assert_eq!(rate.state, RateState::Wrapped);
+        assert_eq!(rate.per_second, Some(100.0));
+    }
+
+    #[test]
+    fn counter64_wrap() {
+        let (before, after) = pair("COUNTER64: 18446744073709550616", "COUNTER64: 9000", None);
+
+        let rate = CounterRates::rate(Some(before), after, false);
+
+        assert_eq!(rate.state, RateState::Wrapped);
+        assert_eq!(rate.per_second, Some(1000.0));
+    }
+
+    #[test]
+    fn reboot_resets_instead_of_wrapping() {
+        let (before, after) = pair("COUNTER32: 5000", "COUNTER32: 100", Some((900_000, 300)));
+
+        let rate = CounterRates::rate(Some(before), after, false);
+
+        assert_eq!(rate.state, RateState::Reset);
+        assert_eq!(rate.per_second, None);
+    }
+
+    #[test]
+    fn discontinuity_resets() {
+        let (before, after) = pair("COUNTER32: 100", "COUNTER32: 200", Some((0, 1000)));
+
+        let rate = CounterRates::rate(Some(before), after, true);
+
+        assert_eq!(rate.state, RateState::Reset);
+        assert_eq!(rate.per_second, None);
+    }
+
+    #[test]
+    fn switching_to_hc_resets() {
+        let (before, after) = pair("COUNTER32: 100", "COUNTER64: 200", None);
+
+        let rate = CounterRates::rate(Some(before), after, false);
+
+        assert_eq!(rate.state, RateState::Reset);
+    }
+
+    #[test]
+    fn timeticks_wrap_matching_the_clock() {
+        let before = TIMETICKS_MODULUS - 400;
+        let elapsed = Duration::from_secs(10);
+
+        assert_eq!(CounterRates::uptime_delta(before, 600, elapsed), Some(1000));
+        assert_eq!(CounterRates::uptime_delta(100, 1100, elapsed), Some(1000));
+        // sysUpTime уменьшился, но до переполнения было далеко - перезагрузка
+        assert_eq!(CounterRates::uptime_delta(900_000, 600, elapsed), None);
+        // Переполнение, но прошло заметно больше, чем по часам коллектора
+        assert_eq!(CounterRates::uptime_delta(before, 10_000, elapsed), None);
+    }
+
+    #[test]
+    fn counter_wrap_across_timeticks_wrap() {
+        let uptime = (TIMETICKS_MODULUS - 400, 600);
+        let (before, after) = pair("COUNTER32: 4294967196", "COUNTER32: 900", Some(uptime));
+
+        let rate = CounterRates::rate(Some(before), after, false);
+
+        assert_eq!(rate.state, RateState::Wrapped);
+        assert_eq!(rate.interval, Some(10.0));
+        assert_eq!(rate.per_second, Some(100.0));
+    }
+}
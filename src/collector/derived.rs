@@ -0,0 +1,148 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::types::{DerivedResult, ScalarResult, TableResult, TableRow};
+use crate::config::{DerivedConfig, Expression};
+use crate::formatter::ValueRenderer;
+
+/// Вычисляет производные метрики профиля после сбора
+pub struct DerivedMetrics;
+
+impl DerivedMetrics {
+    /// Производные из скаляров. Ошибка одной метрики не мешает остальным
+    pub fn scalars(
+        scalars: &[ScalarResult],
+        derived: &HashMap<String, DerivedConfig>,
+    ) -> Vec<DerivedResult> {
+        if derived.is_empty() {
+            return Vec::new();
+        }
+        let Ok(order) = DerivedConfig::order(derived) else {
+            // Циклы отсекаются при загрузке профиля
+            return Vec::new();
+        };
+
+        let values = Self::scalar_values(scalars);
+        let mut computed: HashMap<&str, f64> = HashMap::new();
+        let mut results = Vec::with_capacity(order.len());
+
+        for name in order {
+            let config = &derived[name];
+            let lookup = |variable: &str| {
+                computed
+                    .get(variable)
+                    .or_else(|| values.get(variable))
+                    .copied()
+            };
+            let (value, error) = match config.expr.eval(&lookup) {
+                Ok(value) => (Some(value), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            if let Some(value) = value {
+                computed.insert(name, value);
+            }
+            results.push(DerivedResult {
+                name: name.to_string(),
+                expr: config.expr.to_string(),
+                value,
+                error,
+                meta: config.meta(),
+            });
+        }
+
+        results.sort_by(|a, b| a.name.cmp(&b.name));
+        results
+    }
+
+    /// Вычисляемые колонки таблиц: значение дописывается в каждую строку.
+    /// Строка, для которой выражение не вычисляется, остается без этой колонки
+    pub fn tables<'a>(
+        tables: &mut [TableResult],
+        derived: impl IntoIterator<Item = (&'a String, &'a BTreeMap<String, DerivedConfig>)>,
+        scalars: &[ScalarResult],
+    ) {
+        let values = Self::scalar_values(scalars);
+
+        for (table_name, derived) in derived {
+            if derived.is_empty() {
+                continue;
+            }
+            let Some(table) = tables.iter_mut().find(|table| &table.name == table_name) else {
+                continue;
+            };
+            if table.error.is_some() {
+                continue;
+            }
+            let Ok(order) = DerivedConfig::order(derived) else {
+                continue;
+            };
+
+            for name in &order {
+                let config = &derived[*name];
+                table.metrics.insert(name.to_string(), config.meta());
+                table.derived.insert(name.to_string(), config.expr.to_string());
+            }
+
+            let mut failed: BTreeMap<&str, (usize, String)> = BTreeMap::new();
+            for row in table.rows.iter_mut() {
+                for name in &order {
+                    let result = derived[*name].expr.eval(&|variable: &str| {
+                        Self::row_value(row, variable).or_else(|| values.get(variable).copied())
+                    });
+                    match result {
+                        Ok(value) => {
+                            row.values.insert(name.to_string(), value.to_string());
+                        }
+                        Err(e) => {
+                            let entry = failed.entry(name).or_insert((0, e.to_string()));
+                            entry.0 += 1;
+                        }
+                    }
+                }
+            }
+
+            for (name, (count, error)) in failed {
+                tracing::debug!(
+                    "Таблица '{}': '{}' не вычислено в {} строках: {}",
+                    table.name,
+                    name,
+                    count,
+                    error
+                );
+            }
+        }
+    }
+
+    /// Числовые значения собранных скаляров и скорости счетчиков ("rate(имя)")
+    fn scalar_values(scalars: &[ScalarResult]) -> HashMap<String, f64> {
+        scalars
+            .iter()
+            .flat_map(|scalar| {
+                let value = scalar
+                    .value
+                    .as_deref()
+                    .and_then(ValueRenderer::numeric)
+                    .map(|value| (scalar.name.clone(), value));
+                let rate = scalar
+                    .rate
+                    .as_ref()
+                    .and_then(|rate| rate.per_second)
+                    .map(|rate| (format!("rate({})", scalar.name), rate));
+                value.into_iter().chain(rate)
+            })
+            .collect()
+    }
+
+    /// Числовое значение колонки строки. Для счетчиков - выбранный (HC) счетчик,
+    /// для rate(колонка) - скорость счетчика
+    fn row_value(row: &TableRow, column: &str) -> Option<f64> {
+        if let Some(counter) = Expression::rate_of(column) {
+            return row.rates.get(counter)?.per_second;
+        }
+        let raw = row
+            .counters
+            .get(column)
+            .map(|sample| &sample.value)
+            .or_else(|| row.values.get(column))?;
+        ValueRenderer::numeric(raw)
+    }
+}
@@ -1,15 +1,34 @@
 use anyhow::Result;
 use tokio::time::{Duration, timeout};
 
-use crate::snmp::{DeviceDetector, SnmpClient, parse_oid, set_global_device_type};
+use crate::snmp::{
+    DeviceDetector, DeviceInfo, DeviceSignals, EntityInfo, SnmpClient, parse_oid,
+    value_to_string,
+};
 
-/// Модуль для работы с информацией об устройстве
-pub struct DeviceInfo;
+const SYS_OBJECT_ID: &str = "1.3.6.1.2.1.1.2.0";
+const SYS_DESCR: &str = "1.3.6.1.2.1.1.1.0";
 
-impl DeviceInfo {
+/// entPhysicalEntry (ENTITY-MIB) и нужные колонки
+const ENT_PHYSICAL_ENTRY: &str = "1.3.6.1.2.1.47.1.1.1.1";
+const ENT_PHYSICAL_CLASS: u32 = 5;
+const ENT_PHYSICAL_FIRMWARE_REV: u32 = 9;
+const ENT_PHYSICAL_SOFTWARE_REV: u32 = 10;
+const ENT_PHYSICAL_SERIAL_NUM: u32 = 11;
+const ENT_PHYSICAL_MFG_NAME: u32 = 12;
+const ENT_PHYSICAL_MODEL_NAME: u32 = 13;
+/// PhysicalClass chassis(3)
+const CLASS_CHASSIS: &str = "INTEGER: 3";
+/// Сколько компонентов entPhysicalTable просматривать в поисках шасси
+const ENTITY_SCAN_LIMIT: usize = 64;
+
+/// Сбор сигналов для определения устройства: sysObjectID, sysDescr, ENTITY-MIB
+pub struct DeviceProbe;
+
+impl DeviceProbe {
     /// Получает sysObjectID устройства
     pub async fn get_sys_object_id(client: &mut SnmpClient) -> Result<String> {
-        let sys_object_id_oid = parse_oid("1.3.6.1.2.1.1.2.0")?;
+        let sys_object_id_oid = parse_oid(SYS_OBJECT_ID)?;
         let timeout_duration = Duration::from_secs(3);
 
         match timeout(timeout_duration, client.get(&sys_object_id_oid)).await {
@@ -27,15 +46,73 @@ impl DeviceInfo {
         }
     }
 
-    /// Определяет тип устройства и устанавливает глобальный тип
-    pub async fn detect_and_set_device_type(client: &mut SnmpClient) {
-        // Определяем тип устройства для возможного использования в будущем
-        if let Ok(sys_object_id) = Self::get_sys_object_id(client).await {
-            let device_info = DeviceDetector::detect_device_type(&sys_object_id);
-            // Устанавливаем глобальный тип устройства для правильного разрешения OID
-            set_global_device_type(device_info.device_type);
-        } else {
-            set_global_device_type("generic".to_string());
-        }
+    /// Определяет устройство по всем сигналам. Результат относится только к этому опросу
+    pub async fn detect(client: &mut SnmpClient) -> DeviceInfo {
+        let signals = DeviceSignals {
+            sys_object_id: Self::get_sys_object_id(client).await.ok(),
+            sys_descr: Self::get_string(client, SYS_DESCR).await,
+            entity: Self::get_chassis(client).await,
+        };
+
+        let device_info = DeviceDetector::fingerprint(&signals);
+        tracing::debug!(
+            "Устройство: {} ({}, уверенность {})",
+            device_info.description,
+            device_info.device_type,
+            device_info.confidence
+        );
+        device_info
+    }
+
+    /// Шасси из entPhysicalTable: компонент класса chassis(3), иначе первый компонент
+    async fn get_chassis(client: &mut SnmpClient) -> Option<EntityInfo> {
+        let class_column = format!("{}.{}", ENT_PHYSICAL_ENTRY, ENT_PHYSICAL_CLASS);
+        let root = parse_oid(&class_column).ok()?;
+        let walk = timeout(
+            Duration::from_secs(3),
+            client.walk_limited(&root, Some(ENTITY_SCAN_LIMIT), 10),
+        )
+        .await
+        .ok()?
+        .ok()?;
+
+        let classes: Vec<(String, String)> = walk
+            .items
+            .into_iter()
+            .filter_map(|(oid, value)| {
+                let oid = oid.to_string();
+                let index = oid.strip_prefix(&class_column)?.strip_prefix('.')?;
+                Some((index.to_string(), value))
+            })
+            .collect();
+        let index = classes
+            .iter()
+            .find(|(_, class)| class == CLASS_CHASSIS)
+            .or(classes.first())
+            .map(|(index, _)| index.clone())?;
+
+        let column = |n: u32| format!("{}.{}.{}", ENT_PHYSICAL_ENTRY, n, index);
+        let entity = EntityInfo {
+            model: Self::get_string(client, &column(ENT_PHYSICAL_MODEL_NAME)).await,
+            serial_number: Self::get_string(client, &column(ENT_PHYSICAL_SERIAL_NUM)).await,
+            software_rev: Self::get_string(client, &column(ENT_PHYSICAL_SOFTWARE_REV)).await,
+            firmware_rev: Self::get_string(client, &column(ENT_PHYSICAL_FIRMWARE_REV)).await,
+            manufacturer: Self::get_string(client, &column(ENT_PHYSICAL_MFG_NAME)).await,
+        };
+        Some(entity)
+    }
+
+    /// Строковое значение OID; None при ошибке, таймауте, пустой строке или не-строке
+    async fn get_string(client: &mut SnmpClient, oid_str: &str) -> Option<String> {
+        let oid = parse_oid(oid_str).ok()?;
+        let value = match timeout(Duration::from_secs(3), client.get(&oid)).await {
+            Ok(Ok(value)) => value_to_string(&value),
+            _ => return None,
+        };
+
+        value
+            .strip_prefix("OCTET STRING: ")
+            .map(|text| text.trim().to_string())
+            .filter(|text| !text.is_empty())
     }
 }
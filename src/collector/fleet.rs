@@ -0,0 +1,303 @@
+use std::collections::HashMap;
+use std::net::IpAddr;
+use std::sync::Arc;
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinSet;
+use tokio::time::{Duration, Instant, timeout};
+
+use super::SnmpCollector;
+use super::types::{DevicePoll, FleetSummary, PollStatus};
+use crate::config::settings::FleetSettings;
+use crate::config::{ConfigSnapshot, reload};
+use crate::inventory::Device;
+
+/// Событие опроса нескольких устройств
+#[derive(Debug)]
+pub enum FleetEvent {
+    /// Устройство опрошено (успешно или нет), в порядке готовности
+    Device(Box<DevicePoll>),
+    /// Последнее событие: все устройства опрошены
+    Finished(FleetSummary),
+}
+
+/// Одновременный опрос нескольких устройств с ограничением числа опросов всего
+/// и в одной подсети (fleet в настройках)
+pub struct FleetCollector;
+
+impl FleetCollector {
+    /// Запускает опрос в фоне. Результаты приходят в канал по мере готовности устройств,
+    /// последним - итог. Если получатель закрыт, незавершенные опросы отменяются
+    pub fn spawn(devices: Vec<Device>) -> mpsc::Receiver<FleetEvent> {
+        let (sender, receiver) = mpsc::channel(64);
+        tokio::spawn(Self::run(devices, reload::global(), sender));
+        receiver
+    }
+
+    async fn run(
+        devices: Vec<Device>,
+        snapshot: Arc<ConfigSnapshot>,
+        sender: mpsc::Sender<FleetEvent>,
+    ) {
+        let limits = &snapshot.settings.fleet;
+        let started = Instant::now();
+        let mut summary = FleetSummary {
+            devices: devices.len(),
+            ..Default::default()
+        };
+
+        let mut tasks = Self::spawn_limited(devices, limits, |device| {
+            let snapshot = snapshot.clone();
+            async move { Self::poll(device, &snapshot).await }
+        });
+
+        while let Some(joined) = tasks.join_next().await {
+            let poll = match joined {
+                Ok(poll) => poll,
+                Err(e) => {
+                    tracing::error!("Опрос устройства прерван: {}", e);
+                    summary.failed += 1;
+                    continue;
+                }
+            };
+            match poll.status {
+                PollStatus::Ok => summary.ok += 1,
+                PollStatus::Error => summary.failed += 1,
+                PollStatus::Timeout => summary.timed_out += 1,
+            }
+            if sender
+                .send(FleetEvent::Device(Box::new(poll)))
+                .await
+                .is_err()
+            {
+                tracing::debug!("Результаты опроса никто не ждет, опрос отменен");
+                return;
+            }
+        }
+
+        summary.duration_ms = started.elapsed().as_millis() as u64;
+        tracing::info!(
+            "Опрошено устройств: {} (ok {}, ошибок {}, таймаутов {}) за {} мс",
+            summary.devices,
+            summary.ok,
+            summary.failed,
+            summary.timed_out,
+            summary.duration_ms
+        );
+        let _ = sender.send(FleetEvent::Finished(summary)).await;
+    }
+
+    /// Запускает work для каждого устройства, не больше limits.concurrency одновременно
+    /// и limits.per_subnet в одной подсети
+    fn spawn_limited<F, Fut>(
+        devices: Vec<Device>,
+        limits: &FleetSettings,
+        work: F,
+    ) -> JoinSet<Fut::Output>
+    where
+        F: Fn(Device) -> Fut,
+        Fut: Future + Send + 'static,
+        Fut::Output: Send + 'static,
+    {
+        let global = Arc::new(Semaphore::new(limits.concurrency));
+        let mut subnets: HashMap<String, Arc<Semaphore>> = HashMap::new();
+        let mut tasks = JoinSet::new();
+        for device in devices {
+            let subnet = subnets
+                .entry(Self::subnet(&device, limits))
+                .or_insert_with(|| Arc::new(Semaphore::new(limits.per_subnet)))
+                .clone();
+            let global = global.clone();
+            let work = work(device);
+            tasks.spawn(async move {
+                // Сначала подсеть: ждущие своей подсети не занимают общие места
+                let _subnet = subnet.acquire_owned().await;
+                let _global = global.acquire_owned().await;
+                work.await
+            });
+        }
+        tasks
+    }
+
+    /// Опрос одного устройства; время ожидания своей очереди в таймаут не входит
+    async fn poll(device: Device, snapshot: &ConfigSnapshot) -> DevicePoll {
+        let started_at = chrono::Utc::now();
+        let started = Instant::now();
+        let limit = Duration::from_secs(snapshot.settings.fleet.device_timeout);
+        let outcome = timeout(
+            limit,
+            Box::pin(SnmpCollector::collect_for(&device, snapshot, |_| {})),
+        )
+        .await;
+
+        let (status, result, error) = match outcome {
+            Ok(Ok(result)) => (PollStatus::Ok, Some(result), None),
+            Ok(Err(e)) => (PollStatus::Error, None, Some(format!("{:#}", e))),
+            Err(_) => (
+                PollStatus::Timeout,
+                None,
+                Some(format!("опрос не уложился в {} с", limit.as_secs())),
+            ),
+        };
+        DevicePoll {
+            target: device.target(),
+            device: device.name,
+            status,
+            started_at,
+            duration: started.elapsed(),
+            error,
+            result,
+        }
+    }
+
+    /// Подсеть адреса: "10.0.1.0/24". Для имени хоста - само имя
+    fn subnet(device: &Device, limits: &FleetSettings) -> String {
+        match device.address.trim().parse::<IpAddr>() {
+            Ok(IpAddr::V4(ip)) => {
+                let prefix = limits.subnet_prefix_v4.min(32);
+                let mask = u32::MAX.checked_shl(32 - prefix as u32).unwrap_or(0);
+                let network = std::net::Ipv4Addr::from(u32::from(ip) & mask);
+                format!("{}/{}", network, prefix)
+            }
+            Ok(IpAddr::V6(ip)) => {
+                let prefix = limits.subnet_prefix_v6.min(128);
+                let mask = u128::MAX.checked_shl(128 - prefix as u32).unwrap_or(0);
+                let network = std::net::Ipv6Addr::from(u128::from(ip) & mask);
+                format!("{}/{}", network, prefix)
+            }
+            Err(_) => device.address.trim().to_ascii_lowercase(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    fn device(address: &str) -> Device {
+        serde_yml::from_str(&format!("name: d\naddress: \"{}\"", address)).unwrap()
+    }
+
+    fn subnet(address: &str) -> String {
+        FleetCollector::subnet(&device(address), &FleetSettings::default())
+    }
+
+    #[test]
+    fn ipv4_subnets() {
+        assert_eq!(subnet("10.0.1.77"), "10.0.1.0/24");
+        assert_eq!(subnet(" 10.0.1.1 "), "10.0.1.0/24");
+        assert_ne!(subnet("10.0.2.1"), subnet("10.0.1.1"));
+
+        let limits = FleetSettings {
+            subnet_prefix_v4: 16,
+            ..Default::default()
+        };
+        assert_eq!(
+            FleetCollector::subnet(&device("10.0.200.1"), &limits),
+            "10.0.0.0/16"
+        );
+        let limits = FleetSettings {
+            subnet_prefix_v4: 0,
+            ..Default::default()
+        };
+        assert_eq!(
+            FleetCollector::subnet(&device("10.0.200.1"), &limits),
+            "0.0.0.0/0"
+        );
+    }
+
+    #[test]
+    fn ipv6_subnets() {
+        assert_eq!(subnet("2001:db8:1:2:3:4:5:6"), "2001:db8:1:2::/64");
+        assert_eq!(subnet("2001:db8:1:2::ff"), subnet("2001:db8:1:2:aa::1"));
+
+        let limits = FleetSettings {
+            subnet_prefix_v6: 128,
+            ..Default::default()
+        };
+        assert_eq!(
+            FleetCollector::subnet(&device("2001:db8::1"), &limits),
+            "2001:db8::1/128"
+        );
+    }
+
+    #[test]
+    fn hostnames_are_their_own_subnet() {
+        assert_eq!(subnet("Core-SW1.example.com"), "core-sw1.example.com");
+        assert_ne!(subnet("sw1"), subnet("sw2"));
+    }
+
+    /// Сколько опросов идет сейчас и сколько шло одновременно самое большее
+    #[derive(Default)]
+    struct Load {
+        now: usize,
+        peak: usize,
+    }
+
+    /// Наибольшее число одновременных опросов по подсетям, "" - всего
+    async fn peaks(addresses: &[&str], limits: FleetSettings) -> HashMap<String, usize> {
+        let loads: Arc<Mutex<HashMap<String, Load>>> = Arc::default();
+        let devices = addresses.iter().map(|address| device(address)).collect();
+
+        let mut tasks = FleetCollector::spawn_limited(devices, &limits, |device| {
+            let loads = loads.clone();
+            let keys = [String::new(), subnet(&device.address)];
+            async move {
+                for key in &keys {
+                    let mut loads = loads.lock().unwrap();
+                    let load = loads.entry(key.clone()).or_default();
+                    load.now += 1;
+                    load.peak = load.peak.max(load.now);
+                }
+                tokio::time::sleep(Duration::from_millis(20)).await;
+                for key in &keys {
+                    loads.lock().unwrap().get_mut(key).unwrap().now -= 1;
+                }
+            }
+        });
+        while tasks.join_next().await.is_some() {}
+
+        let loads = loads.lock().unwrap();
+        loads
+            .iter()
+            .map(|(key, load)| (key.clone(), load.peak))
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn limits_per_subnet_and_in_total() {
+        let addresses = [
+            "10.0.1.1", "10.0.1.2", "10.0.1.3", "10.0.1.4", "10.0.2.1", "10.0.2.2", "10.0.3.1",
+            "10.0.3.2",
+        ];
+        let limits = FleetSettings {
+            concurrency: 3,
+            per_subnet: 2,
+            ..Default::default()
+        };
+
+        let peaks = peaks(&addresses, limits).await;
+
+        assert_eq!(peaks[""], 3);
+        assert_eq!(peaks["10.0.1.0/24"], 2);
+        assert!(peaks["10.0.2.0/24"] <= 2 && peaks["10.0.3.0/24"] <= 2);
+    }
+
+    #[tokio::test]
+    async fn one_subnet_does_not_block_others() {
+        // 4 устройства в одной подсети ждут друг друга, остальные идут параллельно
+        let addresses = [
+            "10.0.1.1", "10.0.1.2", "10.0.1.3", "10.0.1.4", "10.0.2.1", "sw1",
+        ];
+        let limits = FleetSettings {
+            concurrency: 10,
+            per_subnet: 1,
+            ..Default::default()
+        };
+
+        let peaks = peaks(&addresses, limits).await;
+
+        assert_eq!(peaks[""], 3);
+        assert_eq!(peaks["10.0.1.0/24"], 1);
+    }
+}
@@ -0,0 +1,373 @@
+use std::collections::HashMap;
+use tokio::time::{Duration, timeout};
+
+use super::types::{CounterSample, TableResult};
+use crate::config::AppConfig;
+use crate::snmp::{SnmpClient, parse_oid};
+
+/// Пара счетчиков IF-MIB: 32-битный и его 64-битный аналог из ifXTable
+struct CounterPair {
+    /// Имя 32-битного счетчика, под ним отдается выбранное значение
+    name: &'static str,
+    low_oid: &'static str,
+    high_name: &'static str,
+    high_oid: &'static str,
+}
+
+const COUNTER_PAIRS: &[CounterPair] = &[
+    CounterPair {
+        name: "ifInOctets",
+        low_oid: "1.3.6.1.2.1.2.2.1.10",
+        high_name: "ifHCInOctets",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.6",
+    },
+    CounterPair {
+        name: "ifInUcastPkts",
+        low_oid: "1.3.6.1.2.1.2.2.1.11",
+        high_name: "ifHCInUcastPkts",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.7",
+    },
+    CounterPair {
+        name: "ifInMulticastPkts",
+        low_oid: "1.3.6.1.2.1.31.1.1.1.2",
+        high_name: "ifHCInMulticastPkts",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.8",
+    },
+    CounterPair {
+        name: "ifInBroadcastPkts",
+        low_oid: "1.3.6.1.2.1.31.1.1.1.3",
+        high_name: "ifHCInBroadcastPkts",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.9",
+    },
+    CounterPair {
+        name: "ifOutOctets",
+        low_oid: "1.3.6.1.2.1.2.2.1.16",
+        high_name: "ifHCOutOctets",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.10",
+    },
+    CounterPair {
+        name: "ifOutUcastPkts",
+        low_oid: "1.3.6.1.2.1.2.2.1.17",
+        high_name: "ifHCOutUcastPkts",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.11",
+    },
+    CounterPair {
+        name: "ifOutMulticastPkts",
+        low_oid: "1.3.6.1.2.1.31.1.1.1.4",
+        high_name: "ifHCOutMulticastPkts",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.12",
+    },
+    CounterPair {
+        name: "ifOutBroadcastPkts",
+        low_oid: "1.3.6.1.2.1.31.1.1.1.5",
+        high_name: "ifHCOutBroadcastPkts",
+        high_oid: "1.3.6.1.2.1.31.1.1.1.13",
+    },
+];
+
+/// Выбор разрядности счетчиков интерфейсов: HC (64 бита), если агент их отдает, иначе 32 бита
+pub struct HcCounters;
+
+impl HcCounters {
+    /// Заполняет `counters` в строках таблиц со счетчиками интерфейсов.
+    /// HC колонки берутся из уже собранных таблиц (ifXTable), а если их нет - дообходятся
+    pub async fn apply(client: &mut SnmpClient, tables: &mut [TableResult], config: &AppConfig) {
+        if !config.settings.collection.prefer_hc_counters {
+            for table in tables.iter_mut() {
+                Self::fill(table, &HashMap::new());
+            }
+            return;
+        }
+
+        // OID HC колонки -> (индекс строки -> значение)
+        let mut hc_values: HashMap<&'static str, HashMap<String, String>> = HashMap::new();
+        for table in tables.iter() {
+            for pair in COUNTER_PAIRS {
+                if let Some(values) = Self::column_values(table, pair.high_oid) {
+                    hc_values.entry(pair.high_oid).or_default().extend(values);
+                }
+            }
+        }
+
+        for (pair, limit) in Self::missing(tables, &hc_values) {
+            let values = Self::walk_column(client, pair, limit, config).await;
+            hc_values.insert(pair.high_oid, values);
+        }
+
+        for table in tables.iter_mut() {
+            Self::fill(table, &hc_values);
+        }
+    }
+
+    /// HC колонки, которых нет среди собранных таблиц, но есть 32-битная пара.
+    /// Лимит строк - от первой таблицы с 32-битной колонкой
+    fn missing(
+        tables: &[TableResult],
+        hc_values: &HashMap<&'static str, HashMap<String, String>>,
+    ) -> Vec<(&'static CounterPair, Option<usize>)> {
+        let mut missing: Vec<(&'static CounterPair, Option<usize>)> = Vec::new();
+        for table in tables.iter().filter(|table| table.error.is_none()) {
+            for pair in COUNTER_PAIRS {
+                let has_low = table.columns.iter().any(|c| c.oid == pair.low_oid);
+                let known = hc_values.contains_key(pair.high_oid)
+                    || missing.iter().any(|(p, _)| p.high_oid == pair.high_oid);
+                if has_low && !known {
+                    missing.push((pair, table.limited_to));
+                }
+            }
+        }
+        missing
+    }
+
+    /// Выбирает значение для каждой пары счетчиков в строках таблицы
+    fn fill(table: &mut TableResult, hc_values: &HashMap<&'static str, HashMap<String, String>>) {
+        let column_name = |oid: &str| {
+            table
+                .columns
+                .iter()
+                .find(|c| c.oid == oid)
+                .map(|c| c.name.clone())
+        };
+        let pairs: Vec<(&CounterPair, Option<String>)> = COUNTER_PAIRS
+            .iter()
+            .map(|pair| (pair, column_name(pair.low_oid)))
+            .filter(|(pair, low)| low.is_some() || column_name(pair.high_oid).is_some())
+            .collect();
+
+        for row in table.rows.iter_mut() {
+            for (pair, low_column) in &pairs {
+                let high = hc_values
+                    .get(pair.high_oid)
+                    .and_then(|values| values.get(&row.index));
+                let low = low_column
+                    .as_ref()
+                    .and_then(|column| row.values.get(column));
+
+                let sample = match (high, low) {
+                    (Some(value), _) => CounterSample {
+                        value: value.clone(),
+                        bits: 64,
+                        column: pair.high_name.to_string(),
+                    },
+                    (None, Some(value)) => CounterSample {
+                        value: value.clone(),
+                        bits: 32,
+                        column: pair.name.to_string(),
+                    },
+                    (None, None) => continue,
+                };
+                row.counters.insert(pair.name.to_string(), sample);
+            }
+        }
+    }
+
+    /// Значения колонки из собранной таблицы: индекс -> значение
+    fn column_values(table: &TableResult, column_oid: &str) -> Option<HashMap<String, String>> {
+        let column = table.columns.iter().find(|c| c.oid == column_oid)?;
+        Some(
+            table
+                .rows
+                .iter()
+                .filter_map(|row| {
+                    row.values
+                        .get(&column.name)
+                        .map(|value| (row.index.clone(), value.clone()))
+                })
+                .collect(),
+        )
+    }
+
+    /// Дообходит HC колонку. Пустой результат - агент HC счетчики не поддерживает
+    async fn walk_column(
+        client: &mut SnmpClient,
+        pair: &CounterPair,
+        limit: Option<usize>,
+        config: &AppConfig,
+    ) -> HashMap<String, String> {
+        let Ok(root_oid) = parse_oid(pair.high_oid) else {
+            return HashMap::new();
+        };
+        let timeout_duration = Duration::from_secs(config.get_timeout());
+        let walk = client.walk_limited(
+            &root_oid,
+            limit,
+            config.settings.collection.max_repetitions,
+        );
+
+        match timeout(timeout_duration, walk).await {
+            Ok(Ok(walk)) => {
+                let prefix = format!("{}.", pair.high_oid);
+                walk.items
+                    .into_iter()
+                    .filter_map(|(oid, value)| {
+                        let oid = oid.to_string();
+                        let index = oid.strip_prefix(&prefix)?.to_string();
+                        // noSuchObject/noSuchInstance - счетчика на этой строке нет
+                        (!value.starts_with("NO SUCH")).then_some((index, value))
+                    })
+                    .collect()
+            }
+            Ok(Err(e)) => {
+                tracing::debug!("{} недоступен, используем 32-битный счетчик: {}", pair.high_name, e);
+                HashMap::new()
+            }
+            Err(_) => {
+                tracing::debug!("{}: таймаут, используем 32-битный счетчик", pair.high_name);
+                HashMap::new()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::collector::types::{TableColumn, TableRow};
+
+    const IN_OCTETS: &str = "1.3.6.1.2.1.2.2.1.10";
+    const HC_IN_OCTETS: &str = "1.3.6.1.2.1.31.1.1.1.6";
+
+    /// Таблица с одной колонкой: индекс -> значение
+    fn table(name: &str, oid: &str, column: &str, rows: &[(&str, &str)]) -> TableResult {
+        TableResult {
+            name: name.to_string(),
+            oid: String::new(),
+            columns: vec![TableColumn {
+                oid: oid.to_string(),
+                name: column.to_string(),
+                value_count: rows.len(),
+            }],
+            rows: rows
+                .iter()
+                .map(|(index, value)| TableRow {
+                    index: index.to_string(),
+                    index_parts: Vec::new(),
+                    index_fields: None,
+                    values: BTreeMap::from([(column.to_string(), value.to_string())]),
+                    counters: BTreeMap::new(),
+                    rates: BTreeMap::new(),
+                })
+                .collect(),
+            varbinds: Vec::new(),
+            error: None,
+            limited_to: None,
+            truncated: false,
+            joined_from: Vec::new(),
+            metrics: BTreeMap::new(),
+            derived: BTreeMap::new(),
+        }
+    }
+
+    fn if_table() -> TableResult {
+        table(
+            "ifTable",
+            IN_OCTETS,
+            "ifInOctets",
+            &[("1", "COUNTER32: 10"), ("2", "COUNTER32: 20")],
+        )
+    }
+
+    /// (разрядность, колонка) выбранного ifInOctets по строкам
+    fn selected(table: &TableResult) -> Vec<Option<(u8, &str)>> {
+        table
+            .rows
+            .iter()
+            .map(|row| {
+                row.counters
+                    .get("ifInOctets")
+                    .map(|sample| (sample.bits, sample.column.as_str()))
+            })
+            .collect()
+    }
+
+    #[test]
+    fn hc_present_in_collected_table() {
+        let if_x_table = table(
+            "ifXTable",
+            HC_IN_OCTETS,
+            "ifHCInOctets",
+            &[("1", "COUNTER64: 100")],
+        );
+        let mut tables = vec![if_table(), if_x_table];
+        let hc_values = HashMap::from([(
+            HC_IN_OCTETS,
+            HcCounters::column_values(&tables[1], HC_IN_OCTETS).unwrap(),
+        )]);
+
+        assert!(HcCounters::missing(&tables, &hc_values).is_empty());
+
+        for table in tables.iter_mut() {
+            HcCounters::fill(table, &hc_values);
+        }
+        // Во второй строке HC счетчика нет - остается 32-битный
+        assert_eq!(
+            selected(&tables[0]),
+            [Some((64, "ifHCInOctets")), Some((32, "ifInOctets"))]
+        );
+        assert_eq!(
+            tables[0].rows[0].counters["ifInOctets"].value,
+            "COUNTER64: 100"
+        );
+        assert_eq!(selected(&tables[1]), [Some((64, "ifHCInOctets"))]);
+    }
+
+    #[test]
+    fn hc_missing_is_walked_once() {
+        let mut limited = if_table();
+        limited.limited_to = Some(50);
+        let mut failed = if_table();
+        failed.error = Some("timeout".to_string());
+        let tables = vec![failed, limited, if_table()];
+
+        let missing = HcCounters::missing(&tables, &HashMap::new());
+
+        let missing: Vec<_> = missing
+            .iter()
+            .map(|(pair, limit)| (pair.high_name, *limit))
+            .collect();
+        assert_eq!(missing, [("ifHCInOctets", Some(50))]);
+    }
+
+    #[test]
+    fn walked_hc_values_are_used() {
+        let mut table = if_table();
+        let walked = HashMap::from([("1".to_string(), "COUNTER64: 100".to_string())]);
+
+        HcCounters::fill(&mut table, &HashMap::from([(HC_IN_OCTETS, walked)]));
+
+        assert_eq!(
+            selected(&table),
+            [Some((64, "ifHCInOctets")), Some((32, "ifInOctets"))]
+        );
+    }
+
+    #[test]
+    fn only_32_bit_counters() {
+        // Агент HC не отдает: дообход вернул пустую колонку
+        let mut interfaces = if_table();
+        HcCounters::fill(
+            &mut interfaces,
+            &HashMap::from([(HC_IN_OCTETS, HashMap::new())]),
+        );
+
+        assert_eq!(
+            selected(&interfaces),
+            [Some((32, "ifInOctets")), Some((32, "ifInOctets"))]
+        );
+        assert_eq!(
+            interfaces.rows[1].counters["ifInOctets"].value,
+            "COUNTER32: 20"
+        );
+        // Таблица без счетчиков интерфейсов не трогается
+        let mut other = table(
+            "hrStorageTable",
+            "1.3.6.1.2.1.25.2.3.1.3",
+            "hrStorageDescr",
+            &[("1", "STRING: /")],
+        );
+        HcCounters::fill(&mut other, &HashMap::new());
+        assert!(other.rows[0].counters.is_empty());
+    }
+}
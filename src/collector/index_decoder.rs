@@ -0,0 +1,317 @@
+use serde::{Deserialize, Serialize};
+
+use crate::config::{IndexField, IndexType};
+
+/// Разобранный компонент индекса строки
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexComponent {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub kind: IndexType,
+    pub value: IndexValue,
+}
+
+/// Значение компонента индекса
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum IndexValue {
+    Number(u64),
+    Text(String),
+}
+
+/// Декодер индексов таблиц по описанию из профиля
+pub struct IndexDecoder;
+
+impl IndexDecoder {
+    /// Разбирает компоненты OID индекса по описанию полей.
+    /// Возвращает ошибку, если компонентов не хватает или остались лишние
+    pub fn decode(parts: &[u64], fields: &[IndexField]) -> Result<Vec<IndexComponent>, String> {
+        let mut components = Vec::with_capacity(fields.len());
+        let mut rest = parts;
+        // Последний InetAddressType, чтобы правильно показать следующий за ним InetAddress
+        let mut inet_type: Option<u64> = None;
+
+        for (pos, field) in fields.iter().enumerate() {
+            let is_last = pos + 1 == fields.len();
+
+            let (value, consumed) = match field.kind {
+                IndexType::Integer => (IndexValue::Number(Self::take(rest, 1, field)?[0]), 1),
+                IndexType::InetAddressType => {
+                    let code = Self::take(rest, 1, field)?[0];
+                    inet_type = Some(code);
+                    (IndexValue::Text(Self::inet_address_type_label(code)), 1)
+                }
+                IndexType::IpAddress => {
+                    let octets = Self::bytes(Self::take(rest, 4, field)?, field)?;
+                    (IndexValue::Text(Self::format_ipv4(&octets)), 4)
+                }
+                IndexType::MacAddress => {
+                    let octets = Self::bytes(Self::take(rest, 6, field)?, field)?;
+                    (IndexValue::Text(Self::format_mac(&octets)), 6)
+                }
+                IndexType::FixedString => {
+                    let length = field.length.ok_or_else(|| {
+                        format!("для '{}' (fixed_string) не указан length", field.name)
+                    })?;
+                    let octets = Self::bytes(Self::take(rest, length, field)?, field)?;
+                    (IndexValue::Text(Self::format_octets(&octets)), length)
+                }
+                IndexType::OctetString | IndexType::InetAddress => {
+                    let (arcs, consumed) = Self::take_variable(rest, field, is_last)?;
+                    let octets = Self::bytes(arcs, field)?;
+                    let text = if field.kind == IndexType::InetAddress {
+                        Self::format_inet_address(&octets, inet_type)
+                    } else {
+                        Self::format_octets(&octets)
+                    };
+                    (IndexValue::Text(text), consumed)
+                }
+                IndexType::ObjectIdentifier => {
+                    let (arcs, consumed) = Self::take_variable(rest, field, is_last)?;
+                    let oid = arcs
+                        .iter()
+                        .map(|arc| arc.to_string())
+                        .collect::<Vec<_>>()
+                        .join(".");
+                    (IndexValue::Text(oid), consumed)
+                }
+            };
+
+            components.push(IndexComponent {
+                name: field.name.clone(),
+                kind: field.kind,
+                value,
+            });
+            rest = &rest[consumed..];
+        }
+
+        if !rest.is_empty() {
+            return Err(format!(
+                "после разбора индекса остались лишние компоненты: {:?}",
+                rest
+            ));
+        }
+
+        Ok(components)
+    }
+
+    /// Берет ровно count компонентов
+    fn take<'a>(rest: &'a [u64], count: usize, field: &IndexField) -> Result<&'a [u64], String> {
+        rest.get(..count).ok_or_else(|| {
+            format!(
+                "для '{}' нужно {} компонентов индекса, осталось {}",
+                field.name,
+                count,
+                rest.len()
+            )
+        })
+    }
+
+    /// Берет компоненты переменной длины: с префиксом длины или IMPLIED (все оставшиеся)
+    fn take_variable<'a>(
+        rest: &'a [u64],
+        field: &IndexField,
+        is_last: bool,
+    ) -> Result<(&'a [u64], usize), String> {
+        if field.implied {
+            if !is_last {
+                return Err(format!(
+                    "IMPLIED допустим только для последнего компонента ('{}')",
+                    field.name
+                ));
+            }
+            return Ok((rest, rest.len()));
+        }
+
+        let length = Self::take(rest, 1, field)?[0] as usize;
+        let arcs = Self::take(&rest[1..], length, field)?;
+        Ok((arcs, length + 1))
+    }
+
+    /// Переводит компоненты OID в байты
+    fn bytes(arcs: &[u64], field: &IndexField) -> Result<Vec<u8>, String> {
+        arcs.iter()
+            .map(|arc| {
+                u8::try_from(*arc)
+                    .map_err(|_| format!("компонент {} в '{}' больше байта", arc, field.name))
+            })
+            .collect()
+    }
+
+    pub(crate) fn format_ipv4(octets: &[u8]) -> String {
+        octets
+            .iter()
+            .map(|b| b.to_string())
+            .collect::<Vec<_>>()
+            .join(".")
+    }
+
+    pub(crate) fn format_mac(octets: &[u8]) -> String {
+        octets
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+
+    /// Печатаемую строку отдаем как текст, остальное - hex
+    pub(crate) fn format_octets(octets: &[u8]) -> String {
+        if !octets.is_empty() && octets.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+            String::from_utf8_lossy(octets).to_string()
+        } else {
+            Self::format_mac(octets)
+        }
+    }
+
+    /// InetAddress: IPv4/IPv6 (с зоной для ipv4z/ipv6z) или DNS имя
+    pub(crate) fn format_inet_address(octets: &[u8], inet_type: Option<u64>) -> String {
+        match (inet_type, octets.len()) {
+            (Some(16), _) => String::from_utf8_lossy(octets).to_string(),
+            (_, 4) => Self::format_ipv4(octets),
+            (_, 8) => format!(
+                "{}%{}",
+                Self::format_ipv4(&octets[..4]),
+                u32::from_be_bytes([octets[4], octets[5], octets[6], octets[7]])
+            ),
+            (_, 16) => Self::format_ipv6(octets),
+            (_, 20) => format!(
+                "{}%{}",
+                Self::format_ipv6(&octets[..16]),
+                u32::from_be_bytes([octets[16], octets[17], octets[18], octets[19]])
+            ),
+            _ => Self::format_octets(octets),
+        }
+    }
+
+    fn format_ipv6(octets: &[u8]) -> String {
+        let mut segments = [0u16; 8];
+        for (i, segment) in segments.iter_mut().enumerate() {
+            *segment = u16::from_be_bytes([octets[i * 2], octets[i * 2 + 1]]);
+        }
+        std::net::Ipv6Addr::from(segments).to_string()
+    }
+
+    /// Метки InetAddressType из INET-ADDRESS-MIB
+    fn inet_address_type_label(code: u64) -> String {
+        let label = match code {
+            0 => "unknown",
+            1 => "ipv4",
+            2 => "ipv6",
+            3 => "ipv4z",
+            4 => "ipv6z",
+            16 => "dns",
+            _ => return code.to_string(),
+        };
+        format!("{}({})", label, code)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(name: &str, kind: IndexType) -> IndexField {
+        IndexField {
+            name: name.to_string(),
+            kind,
+            length: None,
+            implied: false,
+        }
+    }
+
+    fn text(component: &IndexComponent) -> &str {
+        match &component.value {
+            IndexValue::Text(text) => text,
+            IndexValue::Number(number) => panic!("ожидался текст, получено {}", number),
+        }
+    }
+
+    #[test]
+    fn implied_string_takes_the_rest() {
+        let mut name = field("name", IndexType::OctetString);
+        name.implied = true;
+        let fields = [field("id", IndexType::Integer), name];
+
+        let components = IndexDecoder::decode(&[7, 101, 116, 104, 48], &fields).unwrap();
+
+        assert_eq!(components[0].value, IndexValue::Number(7));
+        assert_eq!(text(&components[1]), "eth0");
+    }
+
+    #[test]
+    fn implied_is_only_allowed_last() {
+        let mut name = field("name", IndexType::OctetString);
+        name.implied = true;
+        let fields = [name, field("id", IndexType::Integer)];
+
+        assert!(IndexDecoder::decode(&[101, 116, 1], &fields).is_err());
+    }
+
+    #[test]
+    fn length_prefixed_string() {
+        let fields = [field("name", IndexType::OctetString)];
+
+        let components = IndexDecoder::decode(&[2, 0, 255], &fields).unwrap();
+
+        assert_eq!(text(&components[0]), "00:ff");
+        assert!(IndexDecoder::decode(&[3, 0, 255], &fields).is_err());
+    }
+
+    #[test]
+    fn fixed_string_uses_length() {
+        let mut mac = field("mac", IndexType::FixedString);
+        mac.length = Some(3);
+        let fields = [mac, field("vlan", IndexType::Integer)];
+
+        let components = IndexDecoder::decode(&[65, 66, 67, 10], &fields).unwrap();
+
+        assert_eq!(text(&components[0]), "ABC");
+        assert_eq!(components[1].value, IndexValue::Number(10));
+    }
+
+    #[test]
+    fn fixed_string_without_length_fails() {
+        let fields = [field("mac", IndexType::FixedString)];
+
+        assert!(IndexDecoder::decode(&[1, 2], &fields).is_err());
+    }
+
+    #[test]
+    fn ip_address_takes_four_components() {
+        let fields = [
+            field("ifIndex", IndexType::Integer),
+            field("addr", IndexType::IpAddress),
+        ];
+
+        let components = IndexDecoder::decode(&[3, 192, 168, 0, 1], &fields).unwrap();
+
+        assert_eq!(text(&components[1]), "192.168.0.1");
+    }
+
+    #[test]
+    fn ip_address_rejects_short_or_oversized_index() {
+        let fields = [field("addr", IndexType::IpAddress)];
+
+        assert!(IndexDecoder::decode(&[10, 0, 0], &fields).is_err());
+        assert!(IndexDecoder::decode(&[10, 0, 0, 256], &fields).is_err());
+        assert!(IndexDecoder::decode(&[10, 0, 0, 1, 5], &fields).is_err());
+    }
+
+    #[test]
+    fn inet_address_follows_its_type() {
+        let fields = [
+            field("type", IndexType::InetAddressType),
+            field("addr", IndexType::InetAddress),
+        ];
+
+        let v4 = IndexDecoder::decode(&[1, 4, 10, 0, 0, 1], &fields).unwrap();
+        assert_eq!(text(&v4[0]), "ipv4(1)");
+        assert_eq!(text(&v4[1]), "10.0.0.1");
+
+        let mut v6 = vec![2, 16, 32, 1, 13, 184];
+        v6.extend([0; 11]);
+        v6.push(1);
+        let v6 = IndexDecoder::decode(&v6, &fields).unwrap();
+        assert_eq!(text(&v6[1]), "2001:db8::1");
+    }
+}
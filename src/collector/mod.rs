@@ -1,45 +1,229 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use std::path::Path;
 
+mod capabilities;
+mod counter_rates;
+mod derived;
 mod device_info;
+mod fleet;
+mod hc_counters;
+mod index_decoder;
+mod profile_filter;
 mod scalar_collector;
 mod table_collector;
+mod table_join;
+mod table_pivot;
+mod table_schema;
 mod types;
+mod value_transform;
 
-use device_info::DeviceInfo;
+use capabilities::DeviceCapabilities;
+use derived::DerivedMetrics;
+use device_info::DeviceProbe;
+use hc_counters::HcCounters;
+use profile_filter::ProfileFilter;
 use scalar_collector::ScalarCollector;
 use table_collector::TableCollector;
-pub use types::{MonitoringResult, ScalarResult, TableResult};
+use table_join::TableJoiner;
+use value_transform::ValueTransformer;
+pub use capabilities::{CapabilityCache, CapabilityProbe};
+pub use counter_rates::CounterRates;
+pub use fleet::{FleetCollector, FleetEvent};
+pub use index_decoder::{IndexComponent, IndexDecoder};
+pub use types::{
+    CounterRate, CounterSample, DerivedResult, DevicePoll, FleetSummary, MonitoringResult,
+    PollStatus, ScalarResult, TableResult, TableRow,
+};
 
-use crate::config::AppConfig;
-use crate::snmp::SnmpClient;
+use crate::config::{AppConfig, ConfigSnapshot, Profile, Settings};
+use crate::inventory::Device;
+use crate::snmp::{DeviceDetector, DeviceInfo, DeviceSignals, SnmpClient};
 
 /// Основной коллектор для сбора данных
 pub struct SnmpCollector;
 
 impl SnmpCollector {
-    /// Собирает все данные с устройства
-    // TODO: Добавить pre-flight проверки (доступность устройства, поддерживаемые версии SNMP)
-    // TODO: Добавить сбор системных метрик (время выполнения, количество запросов, etc...)
-    pub async fn collect_all(
+    /// Определяет устройство и собирает данные по профилю, который для него вернул choose
+    pub async fn collect_with(
+        mut client: SnmpClient,
+        settings: &Settings,
+        choose: impl FnOnce(&DeviceInfo) -> Profile,
+        client_type: &str,
+    ) -> Result<MonitoringResult> {
+        let device = Self::identify(&mut client, settings).await;
+        let config = AppConfig {
+            profile: choose(&device),
+            settings: settings.clone(),
+        };
+        Self::collect_device(client, &config, device, client_type).await
+    }
+
+    /// Устройство из кэша проверок, пока он не устарел; иначе определяется заново.
+    /// Недоступное по кэшу устройство не опрашивается: каждый запрос ждал бы таймаут
+    async fn identify(client: &mut SnmpClient, settings: &Settings) -> DeviceInfo {
+        let collection = &settings.collection;
+        let cached = collection
+            .probe_capabilities
+            .then(|| CapabilityCache::get(client.target(), collection.capabilities_ttl))
+            .flatten();
+
+        match cached {
+            Some(DeviceCapabilities {
+                device: Some(device),
+                ..
+            }) => device,
+            Some(capabilities) if capabilities.unreachable => {
+                tracing::debug!(
+                    "{}: недоступно по кэшу проверок, устройство не определяется",
+                    capabilities.target
+                );
+                DeviceDetector::fingerprint(&DeviceSignals::default())
+            }
+            _ => DeviceProbe::detect(client).await,
+        }
+    }
+
+    /// Сбор с устройства инвентаря: по его профилям или по правилам выбора.
+    /// adjust правит профиль перед сбором (группы метрик планировщика)
+    pub async fn collect_for(
+        device: &Device,
+        snapshot: &ConfigSnapshot,
+        adjust: impl FnOnce(&mut Profile),
+    ) -> Result<MonitoringResult> {
+        let selector = snapshot.selector()?;
+        let fixed = match device.profiles.as_slice() {
+            [] => None,
+            // Пути приходят из API: профили и их extends/include - только из каталога профилей
+            paths => Some(
+                Profile::load_all_within(Path::new(&snapshot.settings.profiles.dir), paths)
+                    .context(format!("{}: профили", device.name))?,
+            ),
+        };
+
+        let client = device.connect(&snapshot.settings).await?;
+        let choose = |info: &DeviceInfo| {
+            let mut profile = fixed.unwrap_or_else(|| selector.select(info).clone());
+            adjust(&mut profile);
+            profile
+        };
+        Self::collect_with(client, &snapshot.settings, choose, device.client_type()).await
+    }
+
+    /// Сбор данных для уже определенного устройства. Записи профиля с невыполненными
+    /// условиями и отсутствующими на устройстве OID не опрашиваются
+    /// Этапы сбора запускаются в куче (Box::pin): вместе их future не помещается
+    /// в стек потока runtime в debug сборке
+    async fn collect_device(
         mut client: SnmpClient,
         config: &AppConfig,
+        device: DeviceInfo,
         client_type: &str,
     ) -> Result<MonitoringResult> {
-        DeviceInfo::detect_and_set_device_type(&mut client).await;
+        let capabilities = if config.settings.collection.probe_capabilities {
+            Some(Box::pin(CapabilityProbe::capabilities(&mut client, config, &device)).await)
+        } else {
+            None
+        };
+        let (profile, skipped) = ProfileFilter::apply(&config.profile, &device, capabilities.as_ref());
+        let config = &AppConfig {
+            profile,
+            settings: config.settings.clone(),
+        };
 
-        let scalars = ScalarCollector::collect_scalars(&mut client, config).await;
+        let mut scalars = Box::pin(ScalarCollector::collect_scalars(&mut client, config)).await;
 
         // Условный сбор таблиц
-        let tables = if config.settings.should_collect_tables() {
-            Some(TableCollector::collect_tables(&mut client, config).await)
+        let profile = &config.profile;
+        let mut tables = if config.settings.should_collect_tables() {
+            let mut tables = Box::pin(TableCollector::collect_tables(&mut client, config)).await;
+            Box::pin(HcCounters::apply(&mut client, &mut tables, config)).await;
+            Some(tables)
         } else {
             None
         };
 
+        // Скорости - по сырым значениям счетчиков, до преобразований
+        Box::pin(CounterRates::apply(
+            &mut client,
+            &mut scalars,
+            tables.as_deref_mut().unwrap_or_default(),
+            config,
+        ))
+        .await;
+        ValueTransformer::scalars(&mut scalars);
+
+        if let Some(tables) = tables.as_mut() {
+            ValueTransformer::tables(tables);
+            // Колонки таблиц вычисляются до объединений, чтобы попасть в их строки
+            let table_derived = profile.tables.iter().map(|(name, table)| (name, &table.derived));
+            DerivedMetrics::tables(tables, table_derived, &scalars);
+            TableJoiner::apply(tables, &profile.joins);
+            let join_derived = profile.joins.iter().map(|(name, join)| (name, &join.derived));
+            DerivedMetrics::tables(tables, join_derived, &scalars);
+        }
+        let derived = DerivedMetrics::scalars(&scalars, &config.profile.derived);
+
         Ok(MonitoringResult {
             client_type: client_type.to_string(),
+            device,
+            profile: config.profile.name.clone(),
             scalars,
             tables,
+            derived,
+            skipped,
+            capabilities,
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::collections::BTreeMap;
+    use tokio::time::{Duration, Instant};
+
+    use crate::snmp::create_v2c_client;
+
+    /// Запись кэша проверок для адреса, на котором нет агента
+    fn cached(target: &str, device: Option<&str>) {
+        CapabilityCache::store(DeviceCapabilities {
+            target: target.to_string(),
+            probed_at: Utc::now(),
+            versions: BTreeMap::new(),
+            oids: BTreeMap::new(),
+            unreachable: true,
+            device: device.map(|description| {
+                let mut device = DeviceDetector::fingerprint(&DeviceSignals::default());
+                device.description = description.to_string();
+                device
+            }),
+        });
+    }
+
+    #[tokio::test]
+    async fn cached_device_is_not_detected_again() {
+        let mut client = create_v2c_client("127.0.0.1:9", b"public").await.unwrap();
+        cached(client.target(), Some("core-sw1"));
+
+        let started = Instant::now();
+        let device = SnmpCollector::identify(&mut client, &Settings::default()).await;
+
+        assert_eq!(device.description, "core-sw1");
+        assert!(started.elapsed() < Duration::from_secs(1));
+        CapabilityCache::invalidate(client.target());
+    }
+
+    #[tokio::test]
+    async fn unreachable_device_is_not_probed() {
+        let mut client = create_v2c_client("127.0.0.2:9", b"public").await.unwrap();
+        cached(client.target(), None);
+
+        let started = Instant::now();
+        let device = SnmpCollector::identify(&mut client, &Settings::default()).await;
+
+        assert_eq!(device.confidence, 0.0);
+        assert!(started.elapsed() < Duration::from_secs(1));
+        CapabilityCache::invalidate(client.target());
+    }
+}
@@ -0,0 +1,104 @@
+use std::collections::BTreeSet;
+
+use super::capabilities::DeviceCapabilities;
+use super::types::SkippedEntry;
+use crate::config::{Condition, DerivedConfig, Profile};
+use crate::snmp::DeviceInfo;
+
+/// Отбирает записи профиля, которые имеет смысл собирать с устройства
+pub struct ProfileFilter;
+
+impl ProfileFilter {
+    /// Профиль без записей, чьи условия не выполнены или чьих OID нет на устройстве.
+    /// Вместе с записями убираются зависящие от них объединения и производные метрики
+    pub fn apply(
+        profile: &Profile,
+        device: &DeviceInfo,
+        capabilities: Option<&DeviceCapabilities>,
+    ) -> (Profile, Vec<SkippedEntry>) {
+        let exists = |oid: &str| capabilities.and_then(|caps| caps.exists(oid));
+        let check = |when: &Condition, oid: Option<&str>| {
+            when.check(device, exists).or_else(|| {
+                let oid = oid?;
+                (exists(oid) == Some(false)).then(|| format!("на устройстве нет {}", oid))
+            })
+        };
+
+        let mut filtered = profile.clone();
+        let mut skipped = Vec::new();
+        let mut skip = |kind: &str, name: &str, reason: String| {
+            skipped.push(SkippedEntry {
+                entry: format!("{}.{}", kind, name),
+                reason,
+            });
+        };
+
+        filtered.scalars.retain(
+            |name, scalar| match check(&scalar.when, Some(&scalar.oid)) {
+                Some(reason) => {
+                    skip("scalars", name, reason);
+                    false
+                }
+                None => true,
+            },
+        );
+
+        filtered
+            .tables
+            .retain(|name, table| match check(&table.when, Some(&table.oid)) {
+                Some(reason) => {
+                    skip("tables", name, reason);
+                    false
+                }
+                None => true,
+            });
+
+        // Объединение без основной таблицы пропускается, без дополнительных - собирается без них
+        let tables = &filtered.tables;
+        filtered.joins.retain(|name, join| {
+            let reason = check(&join.when, None).or_else(|| {
+                (!tables.contains_key(&join.base))
+                    .then(|| format!("таблица {} не собирается", join.base))
+            });
+            match reason {
+                Some(reason) => {
+                    skip("joins", name, reason);
+                    false
+                }
+                None => {
+                    join.augments.retain(|table| tables.contains_key(table));
+                    join.lookups
+                        .retain(|lookup| tables.contains_key(&lookup.table));
+                    true
+                }
+            }
+        });
+
+        // Производные по порядку вычисления: пропуск переходит на зависящие метрики
+        let mut dropped: BTreeSet<String> = BTreeSet::new();
+        if let Ok(order) = DerivedConfig::order(&profile.derived) {
+            for name in order {
+                let missing = profile.derived[name]
+                    .expr
+                    .variables()
+                    .into_iter()
+                    .find(|variable| {
+                        dropped.contains(*variable)
+                            || (profile.scalars.contains_key(*variable)
+                                && !filtered.scalars.contains_key(*variable))
+                    });
+                if let Some(variable) = missing {
+                    skip("derived", name, format!("{} не собирается", variable));
+                    dropped.insert(name.to_string());
+                }
+            }
+        }
+        filtered.derived.retain(|name, _| !dropped.contains(name));
+
+        skipped.sort_by(|a, b| a.entry.cmp(&b.entry));
+        for entry in &skipped {
+            tracing::debug!("Пропущено {}: {}", entry.entry, entry.reason);
+        }
+        (filtered, skipped)
+    }
+}
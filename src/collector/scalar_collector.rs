@@ -1,8 +1,8 @@
 use tokio::time::{Duration, timeout};
 
 use super::types::ScalarResult;
-use crate::config::AppConfig;
-use crate::snmp::{SnmpClient, parse_oid};
+use crate::config::{AppConfig, MetricMeta};
+use crate::snmp::{SnmpClient, parse_oid, value_to_string};
 
 /// Модуль для сбора скалярных SNMP значений
 pub struct ScalarCollector;
@@ -12,8 +12,9 @@ impl ScalarCollector {
     pub async fn collect_scalars(client: &mut SnmpClient, config: &AppConfig) -> Vec<ScalarResult> {
         let mut results = Vec::new();
 
-        for (name, oid_str) in &config.profile.scalars {
-            let result = Self::collect_single_scalar(client, name, oid_str).await;
+        for (name, scalar) in &config.profile.scalars {
+            let mut result = Self::collect_single_scalar(client, name, &scalar.oid).await;
+            result.meta = scalar.meta.clone();
             results.push(result);
         }
 
@@ -33,20 +34,26 @@ impl ScalarCollector {
                     Ok(Ok(value)) => ScalarResult {
                         name: name.to_string(),
                         oid: oid_str.to_string(),
-                        value: Some(format!("{:?}", value)),
+                        value: Some(value_to_string(&value)),
                         error: None,
+                        meta: MetricMeta::default(),
+                        rate: None,
                     },
                     Ok(Err(e)) => ScalarResult {
                         name: name.to_string(),
                         oid: oid_str.to_string(),
                         value: None,
                         error: Some(format!("SNMP ERROR: {}", e)),
+                        meta: MetricMeta::default(),
+                        rate: None,
                     },
                     Err(_) => ScalarResult {
                         name: name.to_string(),
                         oid: oid_str.to_string(),
                         value: None,
                         error: Some("TIMEOUT".to_string()),
+                        meta: MetricMeta::default(),
+                        rate: None,
                     },
                 }
             }
@@ -55,6 +62,8 @@ impl ScalarCollector {
                 oid: oid_str.to_string(),
                 value: None,
                 error: Some(format!("OID PARSE ERROR: {}", e)),
+                meta: MetricMeta::default(),
+                rate: None,
             },
         }
     }
@@ -1,7 +1,11 @@
+use std::collections::BTreeMap;
 use tokio::time::{Duration, timeout};
 
+use super::table_pivot::TablePivot;
+use super::table_schema::TableSchema;
 use super::types::TableResult;
-use crate::config::AppConfig;
+use crate::config::{AppConfig, TableConfig};
+use crate::mib;
 use crate::snmp::{SnmpClient, parse_oid};
 
 /// Модуль для сбора табличных SNMP данных
@@ -12,13 +16,8 @@ impl TableCollector {
     pub async fn collect_tables(client: &mut SnmpClient, config: &AppConfig) -> Vec<TableResult> {
         let mut results = Vec::new();
 
-        for (table_name, table_oid) in &config.profile.tables {
-            // Для ifTable берем больше записей
-            let max_items = if table_name == "ifTable" { 50 } else { 20 };
-
-            let result =
-                Self::collect_single_table(client, table_name, table_oid, config, Some(max_items))
-                    .await;
+        for (table_name, table) in &config.profile.tables {
+            let result = Self::collect_single_table(client, table_name, table, config).await;
 
             results.push(result);
         }
@@ -30,53 +29,73 @@ impl TableCollector {
     pub async fn collect_single_table(
         client: &mut SnmpClient,
         table_name: &str,
-        table_oid: &str,
+        table: &TableConfig,
         config: &AppConfig,
-        max_items: Option<usize>,
     ) -> TableResult {
+        // Параметры таблицы из профиля, иначе общие настройки
+        let collection = &config.settings.collection;
+        let limit = table
+            .max_rows
+            .unwrap_or(collection.max_table_rows)
+            .as_option();
+        let max_repetitions = table
+            .max_repetitions
+            .unwrap_or(collection.max_repetitions);
+        let timeout_secs = table.timeout.unwrap_or_else(|| config.get_timeout());
+
+        let table_oid = &table.oid;
+
         match parse_oid(table_oid) {
-            Ok(root_oid) => {
-                let timeout_duration = Duration::from_secs(config.get_timeout());
-                let limit = max_items.unwrap_or(50);
+            Ok(_) => {
+                let timeout_duration = Duration::from_secs(timeout_secs);
 
-                match timeout(timeout_duration, client.walk_limited(&root_oid, limit)).await {
-                    Ok(Ok(rows)) => {
-                        let formatted_rows: Vec<(String, String)> = rows
+                let schema = TableSchema::resolve(table, &mib::global());
+
+                match timeout(
+                    timeout_duration,
+                    client.walk_table(&schema.entry_oid, limit, max_repetitions),
+                )
+                .await
+                {
+                    Ok(Ok(walk)) => {
+                        let varbinds: Vec<(String, String)> = walk
+                            .items
                             .into_iter()
                             .map(|(oid, value)| (oid.to_string(), value))
                             .collect();
+                        let (columns, rows) = TablePivot::pivot(&schema, &varbinds);
 
                         TableResult {
                             name: table_name.to_string(),
                             oid: table_oid.to_string(),
-                            rows: formatted_rows,
+                            columns,
+                            rows,
+                            varbinds,
                             error: None,
-                            limited_to: Some(limit),
+                            limited_to: limit,
+                            truncated: walk.truncated,
+                            joined_from: Vec::new(),
+                            metrics: table.metrics.clone(),
+                            derived: BTreeMap::new(),
                         }
                     }
-                    Ok(Err(e)) => TableResult {
-                        name: table_name.to_string(),
-                        oid: table_oid.to_string(),
-                        rows: Vec::new(),
-                        error: Some(format!("SNMP ERROR: {}", e)),
-                        limited_to: Some(limit),
-                    },
-                    Err(_) => TableResult {
-                        name: table_name.to_string(),
-                        oid: table_oid.to_string(),
-                        rows: Vec::new(),
-                        error: Some("TIMEOUT".to_string()),
-                        limited_to: Some(limit),
-                    },
+                    Ok(Err(e)) => TableResult::failed(
+                        table_name,
+                        table_oid,
+                        format!("SNMP ERROR: {}", e),
+                        limit,
+                    ),
+                    Err(_) => {
+                        TableResult::failed(table_name, table_oid, "TIMEOUT".to_string(), limit)
+                    }
                 }
             }
-            Err(e) => TableResult {
-                name: table_name.to_string(),
-                oid: table_oid.to_string(),
-                rows: Vec::new(),
-                error: Some(format!("OID PARSE ERROR: {}", e)),
-                limited_to: max_items,
-            },
+            Err(e) => TableResult::failed(
+                table_name,
+                table_oid,
+                format!("OID PARSE ERROR: {}", e),
+                limit,
+            ),
         }
     }
 }
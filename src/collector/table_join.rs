@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+
+use super::index_decoder::IndexValue;
+use super::types::{TableColumn, TableResult, TableRow};
+use crate::config::{JoinConfig, Scale};
+
+/// Объединяет собранные таблицы по индексу (AUGMENTS) или по значению колонки
+pub struct TableJoiner;
+
+impl TableJoiner {
+    /// Добавляет к таблицам результаты объединений из профиля
+    pub fn apply(tables: &mut Vec<TableResult>, joins: &HashMap<String, JoinConfig>) {
+        let joined: Vec<TableResult> = joins
+            .iter()
+            .map(|(name, join)| Self::join(name, join, tables))
+            .collect();
+
+        tables.extend(joined);
+    }
+
+    /// Строит одно объединение
+    fn join(name: &str, join: &JoinConfig, tables: &[TableResult]) -> TableResult {
+        let find = |table_name: &str| tables.iter().find(|t| t.name == table_name);
+
+        let Some(base) = find(&join.base) else {
+            return TableResult::failed(
+                name,
+                "",
+                format!("JOIN ERROR: таблица '{}' не собрана", join.base),
+                None,
+            );
+        };
+        if let Some(error) = &base.error {
+            return TableResult::failed(
+                name,
+                &base.oid,
+                format!("JOIN ERROR: основная таблица '{}': {}", base.name, error),
+                base.limited_to,
+            );
+        }
+
+        let mut result = TableResult {
+            name: name.to_string(),
+            oid: base.oid.clone(),
+            columns: base.columns.clone(),
+            rows: base.rows.clone(),
+            varbinds: Vec::new(),
+            error: None,
+            limited_to: base.limited_to,
+            truncated: base.truncated,
+            joined_from: vec![base.name.clone()],
+            metrics: base.metrics.clone(),
+            derived: base.derived.clone(),
+        };
+        // Для inner join: нашлась ли пара для строки во всех таблицах
+        let mut matched = vec![true; result.rows.len()];
+
+        for table_name in &join.augments {
+            let Some(other) = find(table_name) else {
+                matched.fill(false);
+                continue;
+            };
+            Self::merge(&mut result, &mut matched, other, |row| Some(row.index.clone()));
+        }
+
+        for lookup in &join.lookups {
+            let Some(other) = find(&lookup.table) else {
+                matched.fill(false);
+                continue;
+            };
+            Self::merge(&mut result, &mut matched, other, |row| {
+                Self::lookup_key(row, &lookup.on)
+            });
+        }
+
+        if join.inner {
+            let mut flags = matched.into_iter();
+            result.rows.retain(|_| flags.next().unwrap_or(false));
+        }
+
+        result
+    }
+
+    /// Дописывает в строки результата колонки из другой таблицы.
+    /// key_of возвращает индекс строки в other для строки результата
+    fn merge(
+        result: &mut TableResult,
+        matched: &mut [bool],
+        other: &TableResult,
+        key_of: impl Fn(&TableRow) -> Option<String>,
+    ) {
+        result.joined_from.push(other.name.clone());
+        result.truncated |= other.truncated;
+
+        if other.error.is_some() {
+            tracing::warn!(
+                "Таблица '{}' не собрана, объединение '{}' без ее колонок",
+                other.name,
+                result.name
+            );
+            matched.fill(false);
+            return;
+        }
+
+        // Имена колонок и вычисляемых колонок, которые уже есть в результате,
+        // получают префикс таблицы
+        let taken = |name: &str| {
+            result.columns.iter().any(|c| c.name == name) || result.derived.contains_key(name)
+        };
+        let renames: HashMap<&str, String> = other
+            .columns
+            .iter()
+            .map(|column| column.name.as_str())
+            .chain(other.derived.keys().map(String::as_str))
+            .map(|name| {
+                let renamed = if taken(name) {
+                    format!("{}.{}", other.name, name)
+                } else {
+                    name.to_string()
+                };
+                (name, renamed)
+            })
+            .collect();
+        let rename = |name: &str| {
+            renames
+                .get(name)
+                .cloned()
+                .unwrap_or_else(|| name.to_string())
+        };
+
+        result
+            .columns
+            .extend(other.columns.iter().map(|column| TableColumn {
+                oid: column.oid.clone(),
+                name: rename(&column.name),
+                value_count: column.value_count,
+            }));
+
+        for (column, meta) in &other.metrics {
+            let mut meta = meta.clone();
+            if let Some(Scale::Column(scale)) = &meta.scale {
+                meta.scale = Some(Scale::Column(rename(scale)));
+            }
+            result.metrics.insert(rename(column), meta);
+        }
+        for (name, expr) in &other.derived {
+            result.derived.insert(rename(name), expr.clone());
+        }
+
+        let by_index: HashMap<&str, &TableRow> = other
+            .rows
+            .iter()
+            .map(|row| (row.index.as_str(), row))
+            .collect();
+
+        for (row, is_matched) in result.rows.iter_mut().zip(matched.iter_mut()) {
+            let other_row = key_of(row).and_then(|key| by_index.get(key.as_str()).copied());
+            match other_row {
+                Some(other_row) => {
+                    for (column, value) in &other_row.values {
+                        row.values.insert(rename(column), value.clone());
+                    }
+                    for (column, rate) in &other_row.rates {
+                        row.rates.insert(rename(column), rate.clone());
+                    }
+                    // 64-битный счетчик из другой таблицы важнее 32-битного
+                    for (name, sample) in &other_row.counters {
+                        let keep_own = row
+                            .counters
+                            .get(name)
+                            .is_some_and(|own| own.bits >= sample.bits);
+                        if !keep_own {
+                            row.counters.insert(name.clone(), sample.clone());
+                        }
+                    }
+                }
+                None => *is_matched = false,
+            }
+        }
+    }
+
+    /// Индекс для lookup: значение колонки или компонента индекса основной таблицы
+    fn lookup_key(row: &TableRow, on: &str) -> Option<String> {
+        if let Some(value) = row.values.get(on) {
+            return Some(Self::plain_value(value).to_string());
+        }
+
+        row.index_fields
+            .as_ref()?
+            .iter()
+            .find(|component| component.name == on)
+            .map(|component| match &component.value {
+                IndexValue::Number(n) => n.to_string(),
+                IndexValue::Text(text) => text.clone(),
+            })
+    }
+
+    /// Значение без префикса типа: "INTEGER: 5" -> "5"
+    fn plain_value(value: &str) -> &str {
+        value
+            .split_once(": ")
+            .map(|(_, payload)| payload)
+            .unwrap_or(value)
+            .trim()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::config::MetricMeta;
+
+    /// Собранная таблица: колонки и строки (индекс, значения)
+    fn table(name: &str, rows: &[(&str, &[(&str, &str)])]) -> TableResult {
+        let mut columns: Vec<TableColumn> = Vec::new();
+        for (_, values) in rows {
+            for (column, _) in values.iter() {
+                if !columns.iter().any(|c| c.name == *column) {
+                    columns.push(TableColumn {
+                        oid: format!("1.3.6.1.{}", columns.len() + 1),
+                        name: column.to_string(),
+                        value_count: 0,
+                    });
+                }
+            }
+        }
+
+        TableResult {
+            name: name.to_string(),
+            oid: "1.3.6.1".to_string(),
+            columns,
+            rows: rows
+                .iter()
+                .map(|(index, values)| TableRow {
+                    index: index.to_string(),
+                    index_parts: index.split('.').filter_map(|p| p.parse().ok()).collect(),
+                    index_fields: None,
+                    values: values
+                        .iter()
+                        .map(|(k, v)| (k.to_string(), v.to_string()))
+                        .collect(),
+                    counters: BTreeMap::new(),
+                    rates: BTreeMap::new(),
+                })
+                .collect(),
+            varbinds: Vec::new(),
+            error: None,
+            limited_to: None,
+            truncated: false,
+            joined_from: Vec::new(),
+            metrics: BTreeMap::new(),
+            derived: BTreeMap::new(),
+        }
+    }
+
+    fn join(yaml: &str) -> JoinConfig {
+        serde_yml::from_str(yaml).unwrap()
+    }
+
+    /// Результат объединения "j"
+    fn run(tables: Vec<TableResult>, yaml: &str) -> TableResult {
+        let mut tables = tables;
+        let joins = HashMap::from([("j".to_string(), join(yaml))]);
+        TableJoiner::apply(&mut tables, &joins);
+        tables.pop().unwrap()
+    }
+
+    fn interfaces() -> TableResult {
+        table(
+            "ifTable",
+            &[
+                ("1", &[("ifDescr", "STRING: lo")]),
+                ("2", &[("ifDescr", "STRING: eth0")]),
+            ],
+        )
+    }
+
+    #[test]
+    fn augments_by_index() {
+        let x = table("ifXTable", &[("2", &[("ifName", "STRING: eth0")])]);
+
+        let result = run(vec![interfaces(), x], "base: ifTable\naugments: [ifXTable]");
+
+        assert_eq!(result.joined_from, ["ifTable", "ifXTable"]);
+        assert_eq!(result.rows.len(), 2);
+        assert!(!result.rows[0].values.contains_key("ifName"));
+        assert_eq!(result.rows[1].values["ifName"], "STRING: eth0");
+        let columns: Vec<_> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(columns, ["ifDescr", "ifName"]);
+    }
+
+    #[test]
+    fn lookup_by_column_value() {
+        let arp = table(
+            "ipNetToMediaTable",
+            &[
+                ("2.10.0.0.1", &[("ipNetToMediaIfIndex", "INTEGER: 2")]),
+                ("7.10.0.0.9", &[("ipNetToMediaIfIndex", "INTEGER: 7")]),
+            ],
+        );
+        let yaml = "
+base: ipNetToMediaTable
+lookups:
+  - { table: ifTable, on: ipNetToMediaIfIndex }
+";
+
+        let result = run(vec![interfaces(), arp], yaml);
+
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0].values["ifDescr"], "STRING: eth0");
+        assert!(!result.rows[1].values.contains_key("ifDescr"));
+    }
+
+    #[test]
+    fn inner_join_drops_unmatched_rows() {
+        let x = table("ifXTable", &[("2", &[("ifName", "STRING: eth0")])]);
+
+        let result = run(
+            vec![interfaces(), x],
+            "base: ifTable\naugments: [ifXTable]\ninner: true",
+        );
+
+        let indexes: Vec<_> = result.rows.iter().map(|r| r.index.as_str()).collect();
+        assert_eq!(indexes, ["2"]);
+    }
+
+    #[test]
+    fn inner_join_with_missing_table_is_empty() {
+        let result = run(
+            vec![interfaces()],
+            "base: ifTable\naugments: [ifXTable]\ninner: true",
+        );
+
+        assert!(result.error.is_none());
+        assert!(result.rows.is_empty());
+    }
+
+    #[test]
+    fn colliding_columns_get_table_prefix() {
+        let mut other = table(
+            "other",
+            &[("1", &[("ifDescr", "STRING: x"), ("size", "INTEGER: 4")])],
+        );
+        other.metrics.insert(
+            "size".to_string(),
+            MetricMeta {
+                scale: Some(Scale::Column("ifDescr".to_string())),
+                ..Default::default()
+            },
+        );
+
+        let result = run(
+            vec![interfaces(), other],
+            "base: ifTable\naugments: [other]",
+        );
+
+        let columns: Vec<_> = result.columns.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(columns, ["ifDescr", "other.ifDescr", "size"]);
+        let row = &result.rows[0];
+        assert_eq!(row.values["ifDescr"], "STRING: lo");
+        assert_eq!(row.values["other.ifDescr"], "STRING: x");
+        assert_eq!(
+            result.metrics["size"].scale,
+            Some(Scale::Column("other.ifDescr".to_string()))
+        );
+    }
+
+    #[test]
+    fn colliding_derived_get_table_prefix() {
+        let mut base = interfaces();
+        base.derived.insert("load".to_string(), "a / 2".to_string());
+        base.rows[0]
+            .values
+            .insert("load".to_string(), "1".to_string());
+        let mut other = table("other", &[("1", &[("load", "5"), ("free", "3")])]);
+        // В other вычисляемые обе колонки, в колонках таблицы их нет
+        other.columns.clear();
+        other
+            .derived
+            .insert("load".to_string(), "b * 2".to_string());
+        other.derived.insert("free".to_string(), "c".to_string());
+        other
+            .metrics
+            .insert("load".to_string(), MetricMeta::default());
+
+        let result = run(vec![base, other], "base: ifTable\naugments: [other]");
+
+        assert_eq!(result.derived["load"], "a / 2");
+        assert_eq!(result.derived["other.load"], "b * 2");
+        assert_eq!(result.derived["free"], "c");
+        assert!(result.metrics.contains_key("other.load"));
+        assert!(!result.metrics.contains_key("load"));
+        let row = &result.rows[0];
+        assert_eq!(row.values["load"], "1");
+        assert_eq!(row.values["other.load"], "5");
+        assert_eq!(row.values["free"], "3");
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::{BTreeMap, HashMap};
+
+use super::index_decoder::{IndexComponent, IndexDecoder};
+use super::table_schema::TableSchema;
+use super::types::{TableColumn, TableRow};
+use crate::config::IndexField;
+
+/// Разворачивает плоский список varbind'ов таблицы в строки: индекс -> {колонка: значение}
+pub struct TablePivot;
+
+impl TablePivot {
+    /// Группирует varbind'ы по колонкам и индексам строк.
+    /// Колонки и индекс берутся из структуры таблицы (профиль/MIB)
+    pub fn pivot(
+        schema: &TableSchema,
+        varbinds: &[(String, String)],
+    ) -> (Vec<TableColumn>, Vec<TableRow>) {
+        let mut columns: Vec<TableColumn> = Vec::new();
+        let mut column_positions: HashMap<String, usize> = HashMap::new();
+        let mut rows: Vec<TableRow> = Vec::new();
+        let mut row_positions: HashMap<String, usize> = HashMap::new();
+
+        for (oid_str, value) in varbinds {
+            // Определяем колонку и индекс из OID
+            let Some(column) = schema.split(oid_str) else {
+                tracing::debug!("{} вне строк таблицы {}, пропускаем", oid_str, schema.entry_oid);
+                continue;
+            };
+            let index = column.index;
+
+            // Обновляем информацию о колонке
+            let column_pos = *column_positions
+                .entry(column.oid.clone())
+                .or_insert_with(|| {
+                    columns.push(TableColumn {
+                        oid: column.oid.clone(),
+                        name: column.name.clone(),
+                        value_count: 0,
+                    });
+                    columns.len() - 1
+                });
+            columns[column_pos].value_count += 1;
+
+            // Кладем значение в строку с нужным индексом
+            let row_pos = *row_positions.entry(index.to_string()).or_insert_with(|| {
+                let index_parts = Self::parse_index(index);
+                let index_fields = Self::decode_index(index, &index_parts, &schema.index);
+                rows.push(TableRow {
+                    index: index.to_string(),
+                    index_parts,
+                    index_fields,
+                    values: BTreeMap::new(),
+                    counters: BTreeMap::new(),
+                    rates: BTreeMap::new(),
+                });
+                rows.len() - 1
+            });
+            rows[row_pos].values.insert(column.name, value.clone());
+        }
+
+        // walk отдает данные по колонкам, строки сортируем по числовому индексу
+        rows.sort_by(|a, b| a.index_parts.cmp(&b.index_parts));
+
+        (columns, rows)
+    }
+
+    /// Раскладывает индекс на компоненты, если для таблицы задано описание индекса
+    fn decode_index(
+        index: &str,
+        index_parts: &[u64],
+        index_fields: &[IndexField],
+    ) -> Option<Vec<IndexComponent>> {
+        if index_fields.is_empty() {
+            return None;
+        }
+
+        match IndexDecoder::decode(index_parts, index_fields) {
+            Ok(components) => Some(components),
+            Err(e) => {
+                tracing::warn!("Не удалось разобрать индекс '{}': {}", index, e);
+                None
+            }
+        }
+    }
+
+    /// Разбирает индекс строки на числовые компоненты
+    fn parse_index(index: &str) -> Vec<u64> {
+        index
+            .split('.')
+            .filter_map(|part| part.parse::<u64>().ok())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::index_decoder::IndexValue;
+    use crate::config::Profile;
+    use crate::mib::MibTree;
+
+    /// Таблица 1.3.6.1.4.1.1 без MIB: Entry = 1.3.6.1.4.1.1.1
+    fn schema(extra: &str) -> TableSchema {
+        let yaml = format!(
+            "name: test\ntables:\n  t:\n    oid: 1.3.6.1.4.1.1\n    columns:\n      1: name\n      2: speed\n{}",
+            extra
+        );
+        let profile: Profile = serde_yml::from_str(&yaml).unwrap();
+        TableSchema::resolve(&profile.tables["t"], &MibTree::empty())
+    }
+
+    fn varbinds(list: &[(&str, &str)]) -> Vec<(String, String)> {
+        list.iter()
+            .map(|(oid, value)| (format!("1.3.6.1.4.1.1.1.{}", oid), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn pivots_columns_into_rows() {
+        // walk идет по колонкам, индекс 10 должен оказаться после 2
+        let varbinds = varbinds(&[
+            ("1.10", "STRING: c"),
+            ("1.2", "STRING: b"),
+            ("1.1", "STRING: a"),
+            ("2.10", "GAUGE32: 30"),
+            ("2.2", "GAUGE32: 20"),
+            ("2.1", "GAUGE32: 10"),
+        ]);
+
+        let (columns, rows) = TablePivot::pivot(&schema(""), &varbinds);
+
+        let columns: Vec<_> = columns
+            .iter()
+            .map(|c| (c.oid.as_str(), c.name.as_str(), c.value_count))
+            .collect();
+        assert_eq!(
+            columns,
+            [
+                ("1.3.6.1.4.1.1.1.1", "name", 3),
+                ("1.3.6.1.4.1.1.1.2", "speed", 3)
+            ]
+        );
+        let indexes: Vec<_> = rows.iter().map(|r| r.index.as_str()).collect();
+        assert_eq!(indexes, ["1", "2", "10"]);
+        assert_eq!(rows[2].values["name"], "STRING: c");
+        assert_eq!(rows[2].values["speed"], "GAUGE32: 30");
+        assert!(rows[0].index_fields.is_none());
+    }
+
+    #[test]
+    fn missing_columns_leave_gaps() {
+        let varbinds = varbinds(&[
+            ("1.1", "STRING: a"),
+            ("1.2", "STRING: b"),
+            ("2.2", "GAUGE32: 20"),
+            ("3.1", "INTEGER: 1"),
+        ]);
+
+        let (columns, rows) = TablePivot::pivot(&schema(""), &varbinds);
+
+        let counts: Vec<_> = columns
+            .iter()
+            .map(|c| (c.name.as_str(), c.value_count))
+            .collect();
+        assert_eq!(
+            counts,
+            [("name", 2), ("speed", 1), ("column_1_3_6_1_4_1_1_1_3", 1)]
+        );
+        assert_eq!(rows.len(), 2);
+        assert!(!rows[0].values.contains_key("speed"));
+        assert!(!rows[1].values.contains_key("column_1_3_6_1_4_1_1_1_3"));
+    }
+
+    #[test]
+    fn splits_composite_index() {
+        let index = "    index:
+      - name: id
+        type: integer
+      - name: address
+        type: ip_address
+";
+        let varbinds = varbinds(&[
+            ("1.7.10.0.0.1", "STRING: a"),
+            ("1.7.9.0.0.1", "STRING: b"),
+            // Неполный индекс: строка остается, компоненты не разбираются
+            ("1.8.10", "STRING: c"),
+        ]);
+
+        let (_, rows) = TablePivot::pivot(&schema(index), &varbinds);
+
+        let indexes: Vec<_> = rows.iter().map(|r| r.index.as_str()).collect();
+        assert_eq!(indexes, ["7.9.0.0.1", "7.10.0.0.1", "8.10"]);
+        assert_eq!(rows[1].index_parts, [7, 10, 0, 0, 1]);
+        let fields: Vec<_> = rows[1]
+            .index_fields
+            .as_ref()
+            .unwrap()
+            .iter()
+            .map(|c| (c.name.as_str(), c.value.clone()))
+            .collect();
+        assert_eq!(
+            fields,
+            [
+                ("id", IndexValue::Number(7)),
+                ("address", IndexValue::Text("10.0.0.1".to_string()))
+            ]
+        );
+        assert!(rows[2].index_fields.is_none());
+    }
+
+    #[test]
+    fn skips_varbinds_outside_rows() {
+        let varbinds = vec![
+            ("1.3.6.1.4.1.1.1.1.1".to_string(), "STRING: a".to_string()),
+            // Следующая таблица после конца walk и сам OID колонки без индекса
+            ("1.3.6.1.4.1.2.1.1.1".to_string(), "STRING: x".to_string()),
+            ("1.3.6.1.4.1.1.1.2".to_string(), "STRING: y".to_string()),
+        ];
+
+        let (columns, rows) = TablePivot::pivot(&schema(""), &varbinds);
+
+        assert_eq!(columns.len(), 1);
+        assert_eq!(rows.len(), 1);
+    }
+}
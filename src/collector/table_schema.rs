@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use crate::config::{IndexField, IndexType, TableConfig};
+use crate::mib::{BaseType, IndexPart, MibNode, MibTree, format_oid, parse_numeric_oid};
+
+/// Структура таблицы: OID строки (Entry), имена колонок и описание индекса.
+/// Берется из профиля, недостающее - из MIB, без MIB - по правилам SMI (Entry = Table.1)
+#[derive(Debug, Clone)]
+pub struct TableSchema {
+    /// OID Entry, колонки таблицы - Entry.N
+    pub entry_oid: String,
+    /// Номер колонки -> имя
+    columns: BTreeMap<u32, String>,
+    /// Описание индекса для разбора на компоненты (пустое - не разбираем)
+    pub index: Vec<IndexField>,
+}
+
+/// Колонка и индекс строки, выделенные из OID varbind'а
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnRef<'a> {
+    pub oid: String,
+    pub name: String,
+    pub index: &'a str,
+}
+
+impl TableSchema {
+    /// Собирает структуру таблицы из профиля и MIB
+    pub fn resolve(table: &TableConfig, tree: &MibTree) -> Self {
+        let table_oid = parse_numeric_oid(&table.oid).unwrap_or_default();
+        let entry_node = Self::entry_node(&table_oid, tree);
+
+        let entry_oid = match (&table.entry, entry_node) {
+            (Some(entry), _) => entry.trim_matches('.').to_string(),
+            (None, Some(node)) => node.oid_string(),
+            (None, None) => format!("{}.1", format_oid(&table_oid)),
+        };
+
+        // Имена колонок: сначала профиль, затем MIB
+        let mut columns = BTreeMap::new();
+        if let Some(entry) = parse_numeric_oid(&entry_oid) {
+            for node in tree.children(&entry) {
+                if let Some(column) = node.oid.last() {
+                    columns.insert(*column, node.name.clone());
+                }
+            }
+        }
+        columns.extend(table.columns.iter().map(|(n, name)| (*n, name.clone())));
+
+        let index = if !table.index.is_empty() {
+            table.index.clone()
+        } else {
+            parse_numeric_oid(&entry_oid)
+                .and_then(|entry| tree.node(&entry))
+                .map(|entry| Self::index_from_mib(entry, tree))
+                .unwrap_or_default()
+        };
+
+        Self {
+            entry_oid,
+            columns,
+            index,
+        }
+    }
+
+    /// Entry по MIB: сам OID профиля, если он указывает на строку, или строка внутри таблицы
+    fn entry_node<'a>(table_oid: &[u32], tree: &'a MibTree) -> Option<&'a MibNode> {
+        let node = tree.node(table_oid)?;
+        if node.is_row() {
+            return Some(node);
+        }
+        if node.is_table() {
+            return tree.children(table_oid).into_iter().find(|child| child.is_row());
+        }
+        None
+    }
+
+    /// Описание индекса из INDEX (или из INDEX расширяемой строки для AUGMENTS)
+    fn index_from_mib(entry: &MibNode, tree: &MibTree) -> Vec<IndexField> {
+        let parts: &[IndexPart] = match &entry.augments {
+            Some(augmented) => tree
+                .lookup(&format!("{}::{}", entry.module, augmented))
+                .or_else(|| tree.lookup(augmented))
+                .map(|node| node.index.as_slice())
+                .unwrap_or_default(),
+            None => &entry.index,
+        };
+
+        let fields: Option<Vec<IndexField>> = parts
+            .iter()
+            .map(|part| {
+                let node = tree
+                    .lookup(&format!("{}::{}", entry.module, part.name))
+                    .or_else(|| tree.lookup(&part.name))?;
+                Self::index_field(node, part.implied)
+            })
+            .collect();
+
+        fields.unwrap_or_else(|| {
+            tracing::debug!(
+                "Индекс {} не удалось вывести из MIB, индекс не разбирается",
+                entry.qualified_name()
+            );
+            Vec::new()
+        })
+    }
+
+    /// Тип компонента индекса по синтаксису объекта из MIB
+    fn index_field(node: &MibNode, implied: bool) -> Option<IndexField> {
+        let syntax = node.syntax.as_ref()?;
+        let mut length = None;
+
+        let kind = match syntax.base {
+            _ if syntax.is_tc("InetAddressType") => IndexType::InetAddressType,
+            _ if syntax.is_tc("InetAddress") => IndexType::InetAddress,
+            _ if syntax.is_tc("MacAddress") => IndexType::MacAddress,
+            BaseType::IpAddress => IndexType::IpAddress,
+            BaseType::Integer
+            | BaseType::Unsigned32
+            | BaseType::Gauge32
+            | BaseType::Counter32
+            | BaseType::TimeTicks => IndexType::Integer,
+            BaseType::ObjectIdentifier => IndexType::ObjectIdentifier,
+            BaseType::OctetString => match syntax.fixed_size() {
+                Some(size) => {
+                    length = Some(size as usize);
+                    IndexType::FixedString
+                }
+                None => IndexType::OctetString,
+            },
+            _ => return None,
+        };
+
+        Some(IndexField {
+            name: node.name.clone(),
+            kind,
+            length,
+            implied,
+        })
+    }
+
+    /// Раскладывает OID varbind'а на колонку и индекс: Entry.N.INDEX
+    pub fn split<'a>(&self, oid_str: &'a str) -> Option<ColumnRef<'a>> {
+        let rest = oid_str
+            .trim_start_matches('.')
+            .strip_prefix(&self.entry_oid)?
+            .strip_prefix('.')?;
+        let (column, index) = rest.split_once('.')?;
+        let column: u32 = column.parse().ok()?;
+        if index.is_empty() {
+            return None;
+        }
+
+        Some(ColumnRef {
+            oid: format!("{}.{}", self.entry_oid, column),
+            name: self.column_name(column),
+            index,
+        })
+    }
+
+    /// Имя колонки: из профиля или MIB, иначе по OID
+    pub fn column_name(&self, column: u32) -> String {
+        self.columns.get(&column).cloned().unwrap_or_else(|| {
+            format!("column_{}_{}", self.entry_oid.replace('.', "_"), column)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::Profile;
+    use crate::mib::parse_mib;
+
+    /// Таблица с INDEX из двух компонентов и расширяющая ее таблица с AUGMENTS
+    const TEST_MIB: &str = "
+TEST-MIB DEFINITIONS ::= BEGIN
+IMPORTS OBJECT-TYPE, Integer32, IpAddress FROM SNMPv2-SMI;
+
+testMib OBJECT IDENTIFIER ::= { iso 3 6 1 4 1 99999 }
+
+peerTable OBJECT-TYPE
+    SYNTAX SEQUENCE OF PeerEntry
+    MAX-ACCESS not-accessible
+    STATUS current
+    ::= { testMib 1 }
+
+peerEntry OBJECT-TYPE
+    SYNTAX PeerEntry
+    MAX-ACCESS not-accessible
+    STATUS current
+    INDEX { peerId, peerAddress }
+    ::= { peerTable 1 }
+
+PeerEntry ::= SEQUENCE { peerId Integer32, peerAddress IpAddress, peerState INTEGER }
+
+peerId OBJECT-TYPE
+    SYNTAX Integer32
+    MAX-ACCESS not-accessible
+    STATUS current
+    ::= { peerEntry 1 }
+
+peerAddress OBJECT-TYPE
+    SYNTAX IpAddress
+    MAX-ACCESS not-accessible
+    STATUS current
+    ::= { peerEntry 2 }
+
+peerState OBJECT-TYPE
+    SYNTAX INTEGER
+    MAX-ACCESS read-only
+    STATUS current
+    ::= { peerEntry 3 }
+
+peerStatsTable OBJECT-TYPE
+    SYNTAX SEQUENCE OF PeerStatsEntry
+    MAX-ACCESS not-accessible
+    STATUS current
+    ::= { testMib 2 }
+
+peerStatsEntry OBJECT-TYPE
+    SYNTAX PeerStatsEntry
+    MAX-ACCESS not-accessible
+    STATUS current
+    AUGMENTS { peerEntry }
+    ::= { peerStatsTable 1 }
+
+PeerStatsEntry ::= SEQUENCE { peerUpdates Integer32 }
+
+peerUpdates OBJECT-TYPE
+    SYNTAX Integer32
+    MAX-ACCESS read-only
+    STATUS current
+    ::= { peerStatsEntry 1 }
+
+END
+";
+
+    fn tree() -> MibTree {
+        let modules = parse_mib(TEST_MIB).unwrap();
+        let (tree, errors) = MibTree::build(modules.into_iter().map(|m| (m, None)).collect());
+        assert!(errors.is_empty(), "{:?}", errors);
+        tree
+    }
+
+    /// Таблица из YAML профиля
+    fn table(yaml: &str) -> TableConfig {
+        let profile: Profile =
+            serde_yml::from_str(&format!("name: test\ntables:\n  t:\n{}", yaml)).unwrap();
+        profile.tables["t"].clone()
+    }
+
+    #[test]
+    fn without_mib_entry_is_table_dot_one() {
+        let schema = TableSchema::resolve(&table("    oid: 1.3.6.1.4.1.1\n"), &MibTree::empty());
+
+        assert_eq!(schema.entry_oid, "1.3.6.1.4.1.1.1");
+        assert!(schema.index.is_empty());
+        assert_eq!(schema.column_name(3), "column_1_3_6_1_4_1_1_1_3");
+    }
+
+    #[test]
+    fn profile_overrides_entry_columns_and_index() {
+        let yaml = "    oid: 1.3.6.1.4.1.99999.1
+    entry: .1.3.6.1.4.1.99999.1.5
+    columns:
+      3: state
+    index:
+      - name: id
+        type: integer
+";
+        let schema = TableSchema::resolve(&table(yaml), &tree());
+
+        assert_eq!(schema.entry_oid, "1.3.6.1.4.1.99999.1.5");
+        assert_eq!(schema.column_name(3), "state");
+        assert_eq!(schema.index.len(), 1);
+        assert_eq!(schema.index[0].name, "id");
+    }
+
+    #[test]
+    fn entry_columns_and_index_from_mib() {
+        let tree = tree();
+
+        // OID таблицы и OID строки дают одну и ту же структуру
+        for oid in ["1.3.6.1.4.1.99999.1", "1.3.6.1.4.1.99999.1.1"] {
+            let schema = TableSchema::resolve(&table(&format!("    oid: {}\n", oid)), &tree);
+
+            assert_eq!(schema.entry_oid, "1.3.6.1.4.1.99999.1.1");
+            assert_eq!(schema.column_name(3), "peerState");
+            let index: Vec<_> = schema
+                .index
+                .iter()
+                .map(|f| (f.name.as_str(), f.kind))
+                .collect();
+            assert_eq!(
+                index,
+                [
+                    ("peerId", IndexType::Integer),
+                    ("peerAddress", IndexType::IpAddress)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn augments_takes_index_of_base_row() {
+        let schema = TableSchema::resolve(&table("    oid: 1.3.6.1.4.1.99999.2\n"), &tree());
+
+        assert_eq!(schema.entry_oid, "1.3.6.1.4.1.99999.2.1");
+        assert_eq!(schema.column_name(1), "peerUpdates");
+        let names: Vec<_> = schema.index.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["peerId", "peerAddress"]);
+    }
+
+    #[test]
+    fn splits_column_and_index() {
+        let schema = TableSchema::resolve(&table("    oid: 1.3.6.1.4.1.99999.1\n"), &tree());
+
+        assert_eq!(
+            schema.split(".1.3.6.1.4.1.99999.1.1.3.7.10.0.0.1"),
+            Some(ColumnRef {
+                oid: "1.3.6.1.4.1.99999.1.1.3".to_string(),
+                name: "peerState".to_string(),
+                index: "7.10.0.0.1",
+            })
+        );
+        // Без индекса, вне таблицы и на границе OID (Entry.1 vs Entry.10...)
+        assert_eq!(schema.split("1.3.6.1.4.1.99999.1.1.3"), None);
+        assert_eq!(schema.split("1.3.6.1.4.1.99999.2.1.1.7"), None);
+        assert_eq!(schema.split("1.3.6.1.4.1.99999.1.10.3.7"), None);
+    }
+}
@@ -1,3 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use super::capabilities::DeviceCapabilities;
+use super::index_decoder::IndexComponent;
+use crate::config::MetricMeta;
+use crate::snmp::DeviceInfo;
+
 /// Результат сбора скалярных значений
 #[derive(Debug, Clone)]
 pub struct ScalarResult {
@@ -5,6 +15,35 @@ pub struct ScalarResult {
     pub oid: String,
     pub value: Option<String>,
     pub error: Option<String>,
+    /// Описание метрики из профиля
+    pub meta: MetricMeta,
+    /// Скорость, если значение - счетчик
+    pub rate: Option<CounterRate>,
+}
+
+/// Скорость счетчика между двумя опросами устройства
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CounterRate {
+    /// Прирост в секунду; None - посчитать пока нельзя (см. state)
+    pub per_second: Option<f64>,
+    pub state: RateState,
+    /// Секунд между опросами: по sysUpTime, без него - по часам коллектора
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub interval: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RateState {
+    /// Обычный прирост
+    Ok,
+    /// Счетчик переполнился и пошел с нуля, прирост посчитан с учетом разрядности
+    Wrapped,
+    /// Первый опрос (или прошлый слишком давно): скорости еще нет
+    First,
+    /// Счетчики сброшены: перезагрузка (sysUpTime меньше прошлого), discontinuity time
+    /// или сменилась разрядность счетчика
+    Reset,
 }
 
 /// Результат сбора таблицы
@@ -12,15 +51,142 @@ pub struct ScalarResult {
 pub struct TableResult {
     pub name: String,
     pub oid: String,
-    pub rows: Vec<(String, String)>, // (OID, value)
+    /// Колонки в порядке появления при обходе
+    pub columns: Vec<TableColumn>,
+    /// Строки таблицы, развернутые по индексу
+    pub rows: Vec<TableRow>,
+    pub varbinds: Vec<(String, String)>, // (OID, value) - сырой результат walk
     pub error: Option<String>,
+    /// Лимит строк, с которым обходили таблицу (None - без ограничений)
     pub limited_to: Option<usize>,
+    /// true, если обход остановлен по лимиту и часть строк не собрана
+    pub truncated: bool,
+    /// Для объединенных таблиц - исходные таблицы (основная первой)
+    pub joined_from: Vec<String>,
+    /// Описания метрик колонок из профиля
+    pub metrics: BTreeMap<String, MetricMeta>,
+    /// Вычисляемые колонки: имя -> выражение. Значения - в строках рядом с колонками
+    pub derived: BTreeMap<String, String>,
+}
+
+impl TableResult {
+    /// Результат таблицы, которую не удалось собрать
+    pub fn failed(name: &str, oid: &str, error: String, limited_to: Option<usize>) -> Self {
+        Self {
+            name: name.to_string(),
+            oid: oid.to_string(),
+            columns: Vec::new(),
+            rows: Vec::new(),
+            varbinds: Vec::new(),
+            error: Some(error),
+            limited_to,
+            truncated: false,
+            joined_from: Vec::new(),
+            metrics: BTreeMap::new(),
+            derived: BTreeMap::new(),
+        }
+    }
+}
+
+/// Колонка таблицы
+#[derive(Debug, Clone)]
+pub struct TableColumn {
+    pub oid: String,
+    pub name: String,
+    pub value_count: usize,
+}
+
+/// Строка таблицы: индекс и значения колонок
+#[derive(Debug, Clone)]
+pub struct TableRow {
+    /// Индекс строки как суффикс OID, например "1" или "192.168.1.1"
+    pub index: String,
+    /// Компоненты индекса
+    pub index_parts: Vec<u64>,
+    /// Именованные типизированные компоненты индекса (если индекс описан в профиле)
+    pub index_fields: Option<Vec<IndexComponent>>,
+    /// Имя колонки -> значение
+    pub values: BTreeMap<String, String>,
+    /// Счетчики с выбранной разрядностью: имя 32-битного счетчика -> значение
+    pub counters: BTreeMap<String, CounterSample>,
+    /// Скорости счетчиков: имя колонки -> скорость
+    pub rates: BTreeMap<String, CounterRate>,
+}
+
+/// Значение счетчика с указанием, из какой колонки и какой разрядности оно взято
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CounterSample {
+    pub value: String,
+    /// 64 - HC счетчик из ifXTable, 32 - обычный счетчик
+    pub bits: u8,
+    /// Колонка, из которой взято значение, например "ifHCInOctets"
+    pub column: String,
 }
 
 /// Полный результат мониторинга устройства
 #[derive(Debug, Clone)]
 pub struct MonitoringResult {
     pub client_type: String,
+    /// Производитель, модель, ОС и версия, определенные при опросе
+    pub device: DeviceInfo,
+    /// Профиль, по которому собраны данные
+    pub profile: String,
     pub scalars: Vec<ScalarResult>,
     pub tables: Option<Vec<TableResult>>,
+    /// Производные метрики из скаляров
+    pub derived: Vec<DerivedResult>,
+    /// Записи профиля, которые не собирались: условие не выполнено или OID нет на устройстве
+    pub skipped: Vec<SkippedEntry>,
+    /// Результат проверки устройства перед сбором (если включена)
+    pub capabilities: Option<DeviceCapabilities>,
+}
+
+/// Пропущенная запись профиля
+#[derive(Debug, Clone)]
+pub struct SkippedEntry {
+    /// "scalars.имя", "tables.имя", "joins.имя" или "derived.имя"
+    pub entry: String,
+    pub reason: String,
+}
+
+/// Результат вычисления производной метрики
+#[derive(Debug, Clone)]
+pub struct DerivedResult {
+    pub name: String,
+    /// Выражение из профиля
+    pub expr: String,
+    pub value: Option<f64>,
+    /// Почему значение не вычислено: нет скаляра, деление на ноль
+    pub error: Option<String>,
+    pub meta: MetricMeta,
+}
+
+/// Итог опроса одного устройства из нескольких
+#[derive(Debug, Clone)]
+pub struct DevicePoll {
+    pub device: String,
+    pub target: String,
+    pub status: PollStatus,
+    pub started_at: DateTime<Utc>,
+    pub duration: Duration,
+    pub error: Option<String>,
+    pub result: Option<MonitoringResult>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PollStatus {
+    Ok,
+    Error,
+    Timeout,
+}
+
+/// Итог опроса нескольких устройств
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct FleetSummary {
+    pub devices: usize,
+    pub ok: usize,
+    pub failed: usize,
+    pub timed_out: usize,
+    pub duration_ms: u64,
 }
@@ -0,0 +1,72 @@
+use std::collections::BTreeMap;
+
+use super::types::{ScalarResult, TableResult};
+use crate::config::Transform;
+
+/// Применяет преобразования значений из профиля (transform у скаляров и metrics таблиц)
+pub struct ValueTransformer;
+
+impl ValueTransformer {
+    /// Скаляры: при ошибке значение заменяется ошибкой TRANSFORM ERROR
+    pub fn scalars(scalars: &mut [ScalarResult]) {
+        for scalar in scalars.iter_mut() {
+            if scalar.meta.transform.is_empty() {
+                continue;
+            }
+            let Some(raw) = scalar.value.as_deref() else {
+                continue;
+            };
+            match Transform::pipeline(&scalar.meta.transform, raw) {
+                Ok(value) => scalar.value = Some(value),
+                Err(e) => {
+                    scalar.value = None;
+                    scalar.error = Some(format!("TRANSFORM ERROR: {:#}", e));
+                }
+            }
+        }
+    }
+
+    /// Колонки таблиц: ячейка, которую не удалось преобразовать, убирается из строки
+    pub fn tables(tables: &mut [TableResult]) {
+        for table in tables.iter_mut() {
+            let steps: Vec<(&String, &Vec<Transform>)> = table
+                .metrics
+                .iter()
+                .filter(|(_, meta)| !meta.transform.is_empty())
+                .map(|(column, meta)| (column, &meta.transform))
+                .collect();
+            if steps.is_empty() {
+                continue;
+            }
+
+            let mut failed: BTreeMap<&str, (usize, String)> = BTreeMap::new();
+            for row in table.rows.iter_mut() {
+                for (column, transform) in &steps {
+                    let Some(raw) = row.values.get(*column) else {
+                        continue;
+                    };
+                    match Transform::pipeline(transform, raw) {
+                        Ok(value) => {
+                            row.values.insert(column.to_string(), value);
+                        }
+                        Err(e) => {
+                            row.values.remove(*column);
+                            let entry = failed.entry(column).or_insert((0, format!("{:#}", e)));
+                            entry.0 += 1;
+                        }
+                    }
+                }
+            }
+
+            for (column, (count, error)) in failed {
+                tracing::debug!(
+                    "Таблица '{}': '{}' не преобразовано в {} строках: {}",
+                    table.name,
+                    column,
+                    count,
+                    error
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,76 @@
+use serde::{Deserialize, Serialize};
+
+use super::profile::one_or_many;
+use super::transform::Pattern;
+use crate::snmp::DeviceInfo;
+
+/// Условия сбора записи профиля (скаляра, таблицы, объединения). Все заданные условия
+/// должны выполниться, иначе запись пропускается без запросов к устройству
+///
+/// ```yaml
+/// tables:
+///   cpmCPUTotalTable:
+///     oid: "1.3.6.1.4.1.9.9.109.1.1.1"
+///     when_exists: "1.3.6.1.4.1.9.9.109"        # поддерево есть на устройстве
+///     when_sysdescr_matches: "IOS|NX-OS"        # regex по sysDescr
+///     when_device_type: [cisco, cisco_switch]   # тип из реестра производителей
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    /// OID или имя из MIB: запись собирается, только если поддерево есть на устройстве
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when_exists: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when_sysdescr_matches: Option<Pattern>,
+    /// Один тип или список, без учета регистра
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub when_device_type: Vec<String>,
+}
+
+impl Condition {
+    /// Почему запись не собирается; None - условия выполнены.
+    /// exists - есть ли поддерево на устройстве, None - неизвестно (тогда условие считается выполненным)
+    pub fn check(
+        &self,
+        device: &DeviceInfo,
+        exists: impl Fn(&str) -> Option<bool>,
+    ) -> Option<String> {
+        if let Some(oid) = &self.when_exists
+            && exists(oid) == Some(false)
+        {
+            return Some(format!("when_exists: на устройстве нет {}", oid));
+        }
+
+        if let Some(pattern) = &self.when_sysdescr_matches {
+            let matched = device
+                .sys_descr
+                .as_deref()
+                .is_some_and(|descr| pattern.is_match(descr));
+            if !matched {
+                return Some(format!(
+                    "when_sysdescr_matches: sysDescr не совпадает с '{}'",
+                    pattern.as_str()
+                ));
+            }
+        }
+
+        if !self.when_device_type.is_empty()
+            && !self
+                .when_device_type
+                .iter()
+                .any(|kind| kind.eq_ignore_ascii_case(&device.device_type))
+        {
+            return Some(format!(
+                "when_device_type: тип устройства '{}' не из [{}]",
+                device.device_type,
+                self.when_device_type.join(", ")
+            ));
+        }
+
+        None
+    }
+}
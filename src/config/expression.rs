@@ -0,0 +1,484 @@
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+/// Выражение производной метрики.
+///
+/// Числа, имена (скаляры профиля, колонки той же строки, другие производные),
+/// `+ - * / %`, унарный минус, скобки и функции `min`, `max`, `abs`, `round(x[, знаков])`,
+/// `rate(счетчик)` - скорость счетчика в секунду с прошлого опроса.
+/// Других операций нет: выражение не может ничего, кроме арифметики над значениями
+///
+/// ```text
+/// hrStorageUsed / hrStorageSize * 100
+/// round(memAvailReal / memTotalReal * 100, 1)
+/// max(ifHCInOctets, ifInOctets) * 8
+/// rate(ifInOctets) * 8 / ifSpeedBits * 100
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Expression {
+    source: String,
+    root: Node,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Number(f64),
+    Variable(String),
+    /// rate(имя): lookup получает "rate(имя)"
+    Rate(String),
+    Negate(Box<Node>),
+    Binary(Operator, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Operator {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Function {
+    Min,
+    Max,
+    Abs,
+    Round,
+}
+
+impl Function {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "min" => Some(Function::Min),
+            "max" => Some(Function::Max),
+            "abs" => Some(Function::Abs),
+            "round" => Some(Function::Round),
+            _ => None,
+        }
+    }
+
+    /// Допустимое число аргументов (от, до)
+    fn arity(&self) -> (usize, usize) {
+        match self {
+            Function::Min | Function::Max => (1, usize::MAX),
+            Function::Abs => (1, 1),
+            Function::Round => (1, 2),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f64),
+    Ident(String),
+    Op(char),
+    Open,
+    Close,
+    Comma,
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self> {
+        let tokens = Self::tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            position: 0,
+        };
+        let root = parser.expression()?;
+        if let Some((position, token)) = parser.peek_at() {
+            bail!("позиция {}: лишнее '{}'", position, token_text(token));
+        }
+        Ok(Self {
+            source: source.trim().to_string(),
+            root,
+        })
+    }
+
+    /// Текст выражения
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Имена, на которые ссылается выражение
+    pub fn variables(&self) -> BTreeSet<&str> {
+        fn walk<'a>(node: &'a Node, out: &mut BTreeSet<&'a str>) {
+            match node {
+                Node::Number(_) => {}
+                Node::Variable(name) | Node::Rate(name) => {
+                    out.insert(name);
+                }
+                Node::Negate(inner) => walk(inner, out),
+                Node::Binary(_, left, right) => {
+                    walk(left, out);
+                    walk(right, out);
+                }
+                Node::Call(_, args) => args.iter().for_each(|arg| walk(arg, out)),
+            }
+        }
+
+        let mut out = BTreeSet::new();
+        walk(&self.root, &mut out);
+        out
+    }
+
+    /// Имя счетчика, если переменная - его скорость: "rate(ifInOctets)" -> "ifInOctets"
+    pub fn rate_of(variable: &str) -> Option<&str> {
+        variable.strip_prefix("rate(")?.strip_suffix(')')
+    }
+
+    /// Вычисляет выражение. lookup - значение по имени, None - значения нет
+    pub fn eval(&self, lookup: &dyn Fn(&str) -> Option<f64>) -> Result<f64> {
+        let value = Self::eval_node(&self.root, lookup)?;
+        if !value.is_finite() {
+            bail!("результат не число ({})", value);
+        }
+        Ok(value)
+    }
+
+    fn eval_node(node: &Node, lookup: &dyn Fn(&str) -> Option<f64>) -> Result<f64> {
+        Ok(match node {
+            Node::Number(value) => *value,
+            Node::Variable(name) => match lookup(name) {
+                Some(value) => value,
+                None => bail!("нет значения '{}'", name),
+            },
+            Node::Rate(name) => match lookup(&format!("rate({})", name)) {
+                Some(value) => value,
+                None => bail!("нет скорости '{}'", name),
+            },
+            Node::Negate(inner) => -Self::eval_node(inner, lookup)?,
+            Node::Binary(operator, left, right) => {
+                let left = Self::eval_node(left, lookup)?;
+                let right = Self::eval_node(right, lookup)?;
+                match operator {
+                    Operator::Add => left + right,
+                    Operator::Sub => left - right,
+                    Operator::Mul => left * right,
+                    Operator::Div | Operator::Rem if right == 0.0 => bail!("деление на ноль"),
+                    Operator::Div => left / right,
+                    Operator::Rem => left % right,
+                }
+            }
+            Node::Call(function, args) => {
+                let args = args
+                    .iter()
+                    .map(|arg| Self::eval_node(arg, lookup))
+                    .collect::<Result<Vec<_>>>()?;
+                match function {
+                    Function::Min => args.into_iter().fold(f64::INFINITY, f64::min),
+                    Function::Max => args.into_iter().fold(f64::NEG_INFINITY, f64::max),
+                    Function::Abs => args[0].abs(),
+                    Function::Round => {
+                        let digits = args.get(1).copied().unwrap_or(0.0).clamp(0.0, 15.0);
+                        let factor = 10f64.powi(digits as i32);
+                        (args[0] * factor).round() / factor
+                    }
+                }
+            }
+        })
+    }
+
+    fn tokenize(source: &str) -> Result<Vec<(usize, Token)>> {
+        let chars: Vec<char> = source.chars().collect();
+        let mut tokens = Vec::new();
+        let mut i = 0;
+
+        while i < chars.len() {
+            let c = chars[i];
+            let start = i + 1;
+            match c {
+                c if c.is_whitespace() => i += 1,
+                '+' | '-' | '*' | '/' | '%' => {
+                    tokens.push((start, Token::Op(c)));
+                    i += 1;
+                }
+                '(' => {
+                    tokens.push((start, Token::Open));
+                    i += 1;
+                }
+                ')' => {
+                    tokens.push((start, Token::Close));
+                    i += 1;
+                }
+                ',' => {
+                    tokens.push((start, Token::Comma));
+                    i += 1;
+                }
+                c if c.is_ascii_digit() || c == '.' => {
+                    let end = chars[i..]
+                        .iter()
+                        .position(|c| !(c.is_ascii_digit() || *c == '.'))
+                        .map_or(chars.len(), |offset| i + offset);
+                    let text: String = chars[i..end].iter().collect();
+                    let Ok(value) = text.parse() else {
+                        bail!("позиция {}: неверное число '{}'", start, text);
+                    };
+                    tokens.push((start, Token::Number(value)));
+                    i = end;
+                }
+                // Имена колонок объединенных таблиц бывают с точкой: ifXTable.ifName
+                c if c.is_alphabetic() || c == '_' => {
+                    let end = chars[i..]
+                        .iter()
+                        .position(|c| !(c.is_alphanumeric() || *c == '_' || *c == '.'))
+                        .map_or(chars.len(), |offset| i + offset);
+                    tokens.push((start, Token::Ident(chars[i..end].iter().collect())));
+                    i = end;
+                }
+                c => bail!("позиция {}: недопустимый символ '{}'", start, c),
+            }
+        }
+
+        Ok(tokens)
+    }
+}
+
+impl std::fmt::Display for Expression {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+impl TryFrom<String> for Expression {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        Self::parse(&source).map_err(|e| anyhow::anyhow!("выражение '{}': {}", source, e))
+    }
+}
+
+impl From<Expression> for String {
+    fn from(expression: Expression) -> Self {
+        expression.source
+    }
+}
+
+fn token_text(token: &Token) -> String {
+    match token {
+        Token::Number(value) => value.to_string(),
+        Token::Ident(name) => name.clone(),
+        Token::Op(op) => op.to_string(),
+        Token::Open => "(".to_string(),
+        Token::Close => ")".to_string(),
+        Token::Comma => ",".to_string(),
+    }
+}
+
+/// Рекурсивный спуск: expression = term {+|- term}, term = unary {*|/|% unary},
+/// unary = -unary | primary, primary = число | имя | имя(аргументы) | (expression)
+struct Parser<'a> {
+    tokens: &'a [(usize, Token)],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn peek_at(&self) -> Option<(usize, &Token)> {
+        self.tokens
+            .get(self.position)
+            .map(|(position, token)| (*position, token))
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.peek_at().map(|(_, token)| token)
+    }
+
+    fn next(&mut self) -> Option<(usize, Token)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expression(&mut self) -> Result<Node> {
+        let mut left = self.term()?;
+        while let Some(Token::Op(op @ ('+' | '-'))) = self.peek() {
+            let operator = if *op == '+' {
+                Operator::Add
+            } else {
+                Operator::Sub
+            };
+            self.position += 1;
+            left = Node::Binary(operator, Box::new(left), Box::new(self.term()?));
+        }
+        Ok(left)
+    }
+
+    fn term(&mut self) -> Result<Node> {
+        let mut left = self.unary()?;
+        while let Some(Token::Op(op @ ('*' | '/' | '%'))) = self.peek() {
+            let operator = match op {
+                '*' => Operator::Mul,
+                '/' => Operator::Div,
+                _ => Operator::Rem,
+            };
+            self.position += 1;
+            left = Node::Binary(operator, Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+
+    fn unary(&mut self) -> Result<Node> {
+        if let Some(Token::Op('-')) = self.peek() {
+            self.position += 1;
+            return Ok(Node::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Node> {
+        match self.next() {
+            Some((_, Token::Number(value))) => Ok(Node::Number(value)),
+            Some((position, Token::Ident(name))) => {
+                if self.peek() != Some(&Token::Open) {
+                    return Ok(Node::Variable(name));
+                }
+                if name == "rate" {
+                    self.position += 1;
+                    return match self.arguments()?.as_slice() {
+                        [Node::Variable(counter)] => Ok(Node::Rate(counter.clone())),
+                        _ => bail!("позиция {}: rate - от одного имени счетчика", position),
+                    };
+                }
+                let Some(function) = Function::from_name(&name) else {
+                    bail!("позиция {}: неизвестная функция '{}'", position, name);
+                };
+                self.position += 1;
+                let args = self.arguments()?;
+                let (min, max) = function.arity();
+                if args.len() < min || args.len() > max {
+                    bail!(
+                        "позиция {}: '{}' - неверное число аргументов ({})",
+                        position,
+                        name,
+                        args.len()
+                    );
+                }
+                Ok(Node::Call(function, args))
+            }
+            Some((_, Token::Open)) => {
+                let inner = self.expression()?;
+                match self.next() {
+                    Some((_, Token::Close)) => Ok(inner),
+                    Some((position, token)) => {
+                        bail!(
+                            "позиция {}: ожидалась ')', получено '{}'",
+                            position,
+                            token_text(&token)
+                        )
+                    }
+                    None => bail!("не закрыта скобка"),
+                }
+            }
+            Some((position, token)) => {
+                bail!(
+                    "позиция {}: ожидалось число или имя, получено '{}'",
+                    position,
+                    token_text(&token)
+                )
+            }
+            None => bail!("выражение оборвано"),
+        }
+    }
+
+    /// Аргументы функции после '('
+    fn arguments(&mut self) -> Result<Vec<Node>> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::Close) {
+            self.position += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.expression()?);
+            match self.next() {
+                Some((_, Token::Comma)) => continue,
+                Some((_, Token::Close)) => return Ok(args),
+                Some((position, token)) => {
+                    bail!(
+                        "позиция {}: ожидалась ',' или ')', получено '{}'",
+                        position,
+                        token_text(&token)
+                    )
+                }
+                None => bail!("не закрыта скобка у функции"),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, values: &[(&str, f64)]) -> Result<f64> {
+        let lookup = |name: &str| {
+            values
+                .iter()
+                .find(|(known, _)| *known == name)
+                .map(|(_, value)| *value)
+        };
+        Expression::parse(source)?.eval(&lookup)
+    }
+
+    #[test]
+    fn precedence_and_associativity() {
+        assert_eq!(eval("2 + 3 * 4", &[]).unwrap(), 14.0);
+        assert_eq!(eval("(2 + 3) * 4", &[]).unwrap(), 20.0);
+        assert_eq!(eval("10 - 4 - 3", &[]).unwrap(), 3.0);
+        assert_eq!(eval("100 / 10 / 5", &[]).unwrap(), 2.0);
+        assert_eq!(eval("7 % 4 * 2", &[]).unwrap(), 6.0);
+        assert_eq!(eval("-2 * -3 + 1", &[]).unwrap(), 7.0);
+    }
+
+    #[test]
+    fn variables_and_functions() {
+        let values = [("used", 25.0), ("size", 200.0), ("rate(octets)", 1000.0)];
+
+        assert_eq!(eval("used / size * 100", &values).unwrap(), 12.5);
+        assert_eq!(eval("round(used / 3, 2)", &values).unwrap(), 8.33);
+        assert_eq!(eval("max(used, size, 1)", &values).unwrap(), 200.0);
+        assert_eq!(eval("rate(octets) * 8", &values).unwrap(), 8000.0);
+    }
+
+    #[test]
+    fn division_by_zero_fails() {
+        let values = [("zero", 0.0)];
+
+        assert!(eval("1 / 0", &[]).is_err());
+        assert!(eval("5 % zero", &values).is_err());
+        assert!(eval("1 / (2 - 2)", &[]).is_err());
+    }
+
+    #[test]
+    fn unknown_identifiers() {
+        let err = eval("missing + 1", &[]).unwrap_err();
+        assert!(err.to_string().contains("missing"), "{}", err);
+
+        assert!(eval("rate(octets)", &[("octets", 1.0)]).is_err());
+        assert!(Expression::parse("sqrt(4)").is_err());
+    }
+
+    #[test]
+    fn variables_lists_every_reference() {
+        let expression = Expression::parse("rate(in) + max(a, b) - a").unwrap();
+
+        let variables: Vec<_> = expression.variables().into_iter().collect();
+        assert_eq!(variables, ["a", "b", "in"]);
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for source in [
+            "1 +",
+            "(1 + 2",
+            "1 2",
+            "a $ b",
+            "round(1, 2, 3)",
+            "rate(a + b)",
+        ] {
+            assert!(Expression::parse(source).is_err(), "{}", source);
+        }
+    }
+}
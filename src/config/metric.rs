@@ -0,0 +1,113 @@
+use serde::{Deserialize, Serialize};
+
+use super::transform::Transform;
+use crate::mib::{BaseType, MibNode};
+
+/// Вид метрики
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MetricKind {
+    /// Текущее значение: загрузка, температура, размер
+    Gauge,
+    /// Монотонный счетчик, полезна скорость изменения
+    Counter,
+    /// Текст или идентификатор, используется как метка
+    Info,
+    /// Число из перечисления (INTEGER { up(1), down(2) })
+    Enum,
+}
+
+impl MetricKind {
+    /// Вид по синтаксису из MIB
+    pub fn from_mib(node: &MibNode) -> Option<Self> {
+        let syntax = node.syntax.as_ref()?;
+        if !syntax.enums.is_empty() {
+            return Some(MetricKind::Enum);
+        }
+        match syntax.base {
+            BaseType::Counter32 | BaseType::Counter64 => Some(MetricKind::Counter),
+            BaseType::Gauge32 | BaseType::Unsigned32 | BaseType::Integer | BaseType::TimeTicks => {
+                Some(MetricKind::Gauge)
+            }
+            BaseType::OctetString
+            | BaseType::ObjectIdentifier
+            | BaseType::IpAddress
+            | BaseType::Bits => Some(MetricKind::Info),
+            _ => None,
+        }
+    }
+}
+
+/// Множитель значения: число (0.1 для десятых долей градуса) или колонка той же строки
+/// (hrStorageAllocationUnits для hrStorageSize)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Scale {
+    Factor(f64),
+    Column(String),
+}
+
+/// Описание метрики в профиле. Все поля необязательные: kind и unit без явного
+/// значения берутся из MIB (SYNTAX и UNITS)
+///
+/// ```yaml
+/// scalars:
+///   sysUpTime:
+///     oid: "SNMPv2-MIB::sysUpTime.0"
+///     kind: gauge
+///     unit: s
+///     scale: 0.01
+/// tables:
+///   hrStorageTable:
+///     oid: "HOST-RESOURCES-MIB::hrStorageTable"
+///     metrics:
+///       hrStorageSize: { unit: bytes, scale: hrStorageAllocationUnits }
+///       hrStorageUsed: { unit: bytes, scale: hrStorageAllocationUnits }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MetricMeta {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<MetricKind>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scale: Option<Scale>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Преобразования значения при сборе (см. Transform)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub transform: Vec<Transform>,
+}
+
+impl MetricMeta {
+    /// Нет ничего для вывода метрики (преобразования не в счет)
+    pub fn is_empty(&self) -> bool {
+        self.kind.is_none()
+            && self.unit.is_none()
+            && self.scale.is_none()
+            && self.description.is_none()
+    }
+
+    /// Последний шаг преобразования - number: вид из MIB (строка) уже не подходит
+    fn is_converted_to_number(&self) -> bool {
+        matches!(self.transform.last(), Some(Transform::Number))
+    }
+
+    /// Описание с подстановкой kind и unit из MIB, если в профиле их нет
+    pub fn with_mib(&self, node: Option<&MibNode>) -> Self {
+        Self {
+            kind: self
+                .kind
+                .or_else(|| self.is_converted_to_number().then_some(MetricKind::Gauge))
+                .or_else(|| node.and_then(MetricKind::from_mib)),
+            unit: self
+                .unit
+                .clone()
+                .or_else(|| node.and_then(|node| node.units.clone())),
+            scale: self.scale.clone(),
+            description: self.description.clone(),
+            transform: self.transform.clone(),
+        }
+    }
+}
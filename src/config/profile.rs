@@ -1,26 +1,762 @@
 use anyhow::{Context, Result};
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
 
+use super::condition::Condition;
+use super::expression::Expression;
+use super::metric::{MetricKind, MetricMeta};
+use super::profile_loader::ProfileLoader;
+use crate::mib::{MibTree, format_oid};
+
+/// Профиль устройства.
+///
+/// Профиль может наследовать другие профили и подключать фрагменты, а затем
+/// переопределять (тот же ключ) или удалять унаследованные записи:
+/// ```yaml
+/// extends: generic-endpoint          # или список
+/// include: [if-mib, host-resources]  # фрагменты из profiles/fragments
+/// remove: [scalars.sysLocation, tables.hrDeviceTable]
+/// scalars:
+///   sysName: "SNMPv2-MIB::sysName.0"
+///   sysUpTime: { oid: "SNMPv2-MIB::sysUpTime.0", unit: s, scale: 0.01 }
+/// derived:
+///   memUsedPercent: { expr: "(memTotalReal - memAvailReal) / memTotalReal * 100", unit: percent }
+/// ```
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Profile {
-    pub name: String, // Название профиля, например "generic-endpoint" или "printer"
-    pub scalars: HashMap<String, String>, // Хранит скалярные OID — одиночные значения, которые опрашиваются через SNMP
-    pub tables: HashMap<String, String>, // Тоже словарь, но хранит корневые OID таблиц для SNMP WALK
+    #[serde(default)]
+    pub name: String, // Название профиля, например "generic-endpoint" или "printer"; по умолчанию имя файла
+    #[serde(default, deserialize_with = "one_or_many", skip_serializing_if = "Vec::is_empty")]
+    pub extends: Vec<String>, // Родительские профили, применяются по порядку
+    #[serde(default, deserialize_with = "one_or_many", skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>, // Фрагменты, применяются после родителей
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub remove: Vec<String>, // Удаляемые унаследованные записи: "scalars.sysLocation", "tables.hrDeviceTable"
+    #[serde(default)]
+    pub scalars: HashMap<String, ScalarConfig>, // Хранит скалярные OID — одиночные значения, которые опрашиваются через SNMP
+    #[serde(default)]
+    pub tables: HashMap<String, TableConfig>, // Корневые OID таблиц для SNMP WALK и параметры обхода
+    #[serde(default)]
+    pub joins: HashMap<String, JoinConfig>, // Объединения таблиц в один набор строк
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub derived: HashMap<String, DerivedConfig>, // Значения, вычисляемые из скаляров
+    /// Откуда взята каждая запись собранного профиля: "scalars.sysName" -> "profiles/fragments/system.yaml"
+    #[serde(skip_deserializing, skip_serializing_if = "BTreeMap::is_empty")]
+    pub provenance: BTreeMap<String, String>,
+}
+
+/// Значение YAML, которое может быть строкой или списком строк
+pub(crate) fn one_or_many<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        One(String),
+        Many(Vec<String>),
+    }
+
+    Ok(match Raw::deserialize(deserializer)? {
+        Raw::One(value) => vec![value],
+        Raw::Many(values) => values,
+    })
+}
+
+/// Объединение собранных таблиц в один набор строк.
+///
+/// ```yaml
+/// joins:
+///   interfaces:
+///     base: ifTable
+///     augments: [ifXTable]          # тот же индекс (AUGMENTS)
+///   arp:
+///     base: ipNetToMediaTable
+///     lookups:
+///       - { table: ifTable, on: ipNetToMediaIfIndex }  # значение колонки = индекс ifTable
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JoinConfig {
+    /// Основная таблица, ее строки задают результат
+    pub base: String,
+    /// Таблицы с тем же индексом, что и у основной (AUGMENTS)
+    #[serde(default)]
+    pub augments: Vec<String>,
+    /// Таблицы, индекс строки которых берется из колонки основной таблицы
+    #[serde(default)]
+    pub lookups: Vec<LookupJoin>,
+    /// true - оставить только строки, для которых нашлись пары во всех таблицах
+    #[serde(default)]
+    pub inner: bool,
+    /// Условия сбора (см. Condition)
+    #[serde(flatten)]
+    pub when: Condition,
+    /// Значения, вычисляемые из колонок строки объединения
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub derived: BTreeMap<String, DerivedConfig>,
+}
+
+/// Присоединение таблицы по значению колонки основной таблицы
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LookupJoin {
+    /// Присоединяемая таблица
+    pub table: String,
+    /// Колонка или компонент индекса основной таблицы, значение которой - индекс в `table`
+    pub on: String,
+}
+
+impl JoinConfig {
+    /// Все таблицы, участвующие в объединении
+    pub fn table_names(&self) -> impl Iterator<Item = &String> {
+        std::iter::once(&self.base)
+            .chain(self.augments.iter())
+            .chain(self.lookups.iter().map(|lookup| &lookup.table))
+    }
+}
+
+/// Скаляр в профиле: OID строкой или объект с OID и описанием метрики (см. MetricMeta)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "ScalarConfigRaw")]
+pub struct ScalarConfig {
+    pub oid: String,
+    #[serde(flatten)]
+    pub when: Condition,
+    #[serde(flatten)]
+    pub meta: MetricMeta,
+}
+
+/// Объект разбирается отдельно в try_from: ошибка внутри untagged enum
+/// свелась бы к "data did not match any variant"
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ScalarConfigRaw {
+    Oid(String),
+    Full(serde_yml::Value),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ScalarConfigFull {
+    oid: String,
+    #[serde(flatten)]
+    when: Condition,
+    #[serde(flatten)]
+    meta: MetricMeta,
+}
+
+impl TryFrom<ScalarConfigRaw> for ScalarConfig {
+    type Error = serde_yml::Error;
+
+    fn try_from(raw: ScalarConfigRaw) -> Result<Self, Self::Error> {
+        Ok(match raw {
+            ScalarConfigRaw::Oid(oid) => Self {
+                oid,
+                when: Condition::default(),
+                meta: MetricMeta::default(),
+            },
+            ScalarConfigRaw::Full(value) => {
+                let ScalarConfigFull { oid, when, meta } = serde_yml::from_value(value)?;
+                Self { oid, when, meta }
+            }
+        })
+    }
+}
+
+/// Производная метрика: выражение (см. Expression) и описание метрики.
+/// Можно указать просто выражение строкой
+///
+/// ```yaml
+/// derived:
+///   usedPercent: { expr: "hrStorageUsed / hrStorageSize * 100", unit: percent }
+///   usedBytes: "hrStorageUsed * hrStorageAllocationUnits"
+/// ```
+///
+/// В выражение подставляются значения как есть, без scale из описания метрик.
+/// Вид по умолчанию - gauge
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "DerivedConfigRaw")]
+pub struct DerivedConfig {
+    pub expr: Expression,
+    #[serde(flatten)]
+    pub meta: MetricMeta,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum DerivedConfigRaw {
+    Expr(String),
+    Full(serde_yml::Value),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct DerivedConfigFull {
+    expr: String,
+    #[serde(flatten)]
+    meta: MetricMeta,
+}
+
+impl TryFrom<DerivedConfigRaw> for DerivedConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: DerivedConfigRaw) -> Result<Self> {
+        let (expr, meta) = match raw {
+            DerivedConfigRaw::Expr(expr) => (expr, MetricMeta::default()),
+            DerivedConfigRaw::Full(value) => {
+                let DerivedConfigFull { expr, meta } = serde_yml::from_value(value)?;
+                (expr, meta)
+            }
+        };
+        Ok(Self {
+            expr: Expression::try_from(expr)?,
+            meta,
+        })
+    }
+}
+
+impl DerivedConfig {
+    /// Описание метрики; без kind - gauge
+    pub fn meta(&self) -> MetricMeta {
+        let mut meta = self.meta.clone();
+        meta.kind.get_or_insert(MetricKind::Gauge);
+        meta
+    }
+
+    /// Порядок вычисления: сначала те, от которых зависят другие. Цикл - ошибка
+    pub fn order<'a>(
+        derived: impl IntoIterator<Item = (&'a String, &'a DerivedConfig)>,
+    ) -> Result<Vec<&'a str>> {
+        let mut pending: BTreeMap<&str, BTreeSet<&str>> = derived
+            .into_iter()
+            .map(|(name, config)| (name.as_str(), config.expr.variables()))
+            .collect();
+        let names: BTreeSet<&str> = pending.keys().copied().collect();
+        let mut order = Vec::with_capacity(pending.len());
+
+        while !pending.is_empty() {
+            let ready: Vec<&str> = pending
+                .iter()
+                .filter(|(_, variables)| {
+                    variables
+                        .iter()
+                        .all(|variable| !names.contains(variable) || order.contains(variable))
+                })
+                .map(|(name, _)| *name)
+                .collect();
+            if ready.is_empty() {
+                let cycle: Vec<&str> = pending.keys().copied().collect();
+                anyhow::bail!("циклическая зависимость производных метрик: {}", cycle.join(", "));
+            }
+            for name in ready {
+                pending.remove(name);
+                order.push(name);
+            }
+        }
+
+        Ok(order)
+    }
+}
+
+/// Описание таблицы в профиле.
+///
+/// В YAML можно указать просто OID строкой (числовой или имя из MIB), либо объект с параметрами обхода:
+/// ```yaml
+/// tables:
+///   ifTable: "IF-MIB::ifTable"
+///   hrStorageTable:
+///     oid: "1.3.6.1.2.1.25.2.3"
+///     max_rows: unlimited
+///     timeout: 20
+///     max_repetitions: 25
+///   ipNetToMediaTable:
+///     oid: "1.3.6.1.2.1.4.22"
+///     index:
+///       - { name: ipNetToMediaIfIndex, type: integer }
+///       - { name: ipNetToMediaNetAddress, type: ip_address }
+///   vendorTable:                    # таблица без MIB
+///     oid: "1.3.6.1.4.1.9999.1.2"
+///     entry: "1.3.6.1.4.1.9999.1.2.1"
+///     columns: { 1: name, 2: temperature }
+///     metrics:                      # описание колонок как метрик
+///       temperature: { kind: gauge, unit: celsius, scale: 0.1 }
+///     derived:                      # вычисляемые колонки (см. DerivedConfig)
+///       temperatureF: { expr: "temperature * 0.18 + 32", unit: fahrenheit }
+/// ```
+///
+/// Без `entry`, `columns` и `index` структура таблицы берется из MIB (INDEX, имена колонок),
+/// а если таблицы нет в MIB - Entry считается Table.1
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "TableConfigRaw")]
+pub struct TableConfig {
+    /// Корневой OID таблицы
+    pub oid: String,
+    /// Максимум строк (индексов) в каждой колонке, None - берется из настроек
+    pub max_rows: Option<RowLimit>,
+    /// Таймаут обхода таблицы (секунды), None - берется из настроек
+    pub timeout: Option<u64>,
+    /// max-repetitions для GETBULK, None - берется из настроек
+    pub max_repetitions: Option<u32>,
+    /// OID строки таблицы (Entry), None - из MIB или Table.1
+    pub entry: Option<String>,
+    /// Номер колонки -> имя, дополняет/переопределяет имена из MIB
+    pub columns: BTreeMap<u32, String>,
+    /// Описание индекса таблицы для разбора на именованные компоненты, пустое - из MIB
+    pub index: Vec<IndexField>,
+    /// Имя колонки -> описание метрики
+    pub metrics: BTreeMap<String, MetricMeta>,
+    /// Вычисляемые колонки: выражения над колонками строки и скалярами
+    pub derived: BTreeMap<String, DerivedConfig>,
+    /// Колонка со временем последнего сброса счетчиков строки (ifCounterDiscontinuityTime).
+    /// Ее нет в строке - берется из строки с тем же индексом другой собранной таблицы
+    pub discontinuity: Option<String>,
+    /// Условия сбора (см. Condition)
+    #[serde(flatten)]
+    pub when: Condition,
+}
+
+/// Компонент индекса таблицы
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexField {
+    /// Имя компонента, например "ipNetToMediaNetAddress"
+    pub name: String,
+    /// Тип компонента
+    #[serde(rename = "type")]
+    pub kind: IndexType,
+    /// Длина для fixed_string
+    #[serde(default)]
+    pub length: Option<usize>,
+    /// IMPLIED - строка/OID без префикса длины (только для последнего компонента)
+    #[serde(default)]
+    pub implied: bool,
+}
+
+/// Тип компонента индекса
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IndexType {
+    /// INTEGER / Unsigned32 - один компонент OID
+    Integer,
+    /// IpAddress - 4 компонента
+    IpAddress,
+    /// MacAddress / PhysAddress фиксированной длины - 6 компонентов
+    MacAddress,
+    /// OCTET STRING переменной длины с префиксом длины
+    OctetString,
+    /// OCTET STRING фиксированной длины (length)
+    FixedString,
+    /// OBJECT IDENTIFIER с префиксом длины
+    ObjectIdentifier,
+    /// InetAddressType - один компонент (ipv4(1), ipv6(2), ...)
+    InetAddressType,
+    /// InetAddress - OCTET STRING с префиксом длины, IPv4/IPv6 по длине
+    InetAddress,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum TableConfigRaw {
+    Oid(String),
+    Full(serde_yml::Value),
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TableConfigFull {
+    oid: String,
+    #[serde(default)]
+    max_rows: Option<RowLimit>,
+    #[serde(default)]
+    timeout: Option<u64>,
+    #[serde(default)]
+    max_repetitions: Option<u32>,
+    #[serde(default)]
+    entry: Option<String>,
+    #[serde(default)]
+    columns: BTreeMap<u32, String>,
+    #[serde(default)]
+    index: Vec<IndexField>,
+    #[serde(default)]
+    metrics: BTreeMap<String, MetricMeta>,
+    #[serde(default)]
+    derived: BTreeMap<String, DerivedConfig>,
+    #[serde(default)]
+    discontinuity: Option<String>,
+    #[serde(flatten)]
+    when: Condition,
+}
+
+impl TryFrom<TableConfigRaw> for TableConfig {
+    type Error = serde_yml::Error;
+
+    fn try_from(raw: TableConfigRaw) -> Result<Self, Self::Error> {
+        Ok(match raw {
+            TableConfigRaw::Oid(oid) => Self {
+                oid,
+                max_rows: None,
+                timeout: None,
+                max_repetitions: None,
+                entry: None,
+                columns: BTreeMap::new(),
+                index: Vec::new(),
+                metrics: BTreeMap::new(),
+                derived: BTreeMap::new(),
+                discontinuity: None,
+                when: Condition::default(),
+            },
+            TableConfigRaw::Full(value) => {
+                let TableConfigFull {
+                    oid,
+                    max_rows,
+                    timeout,
+                    max_repetitions,
+                    entry,
+                    columns,
+                    index,
+                    metrics,
+                    derived,
+                    discontinuity,
+                    when,
+                } = serde_yml::from_value(value)?;
+                Self {
+                    oid,
+                    max_rows,
+                    timeout,
+                    max_repetitions,
+                    entry,
+                    columns,
+                    index,
+                    metrics,
+                    derived,
+                    discontinuity,
+                    when,
+                }
+            }
+        })
+    }
+}
+
+/// Ограничение на количество строк при обходе таблицы.
+/// В YAML задается числом или строкой "unlimited"
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowLimit {
+    Limited(usize),
+    Unlimited,
+}
+
+impl RowLimit {
+    /// Лимит в виде Option: None - без ограничений
+    pub fn as_option(&self) -> Option<usize> {
+        match self {
+            RowLimit::Limited(n) => Some(*n),
+            RowLimit::Unlimited => None,
+        }
+    }
+}
+
+impl Serialize for RowLimit {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            RowLimit::Limited(n) => serializer.serialize_u64(*n as u64),
+            RowLimit::Unlimited => serializer.serialize_str("unlimited"),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for RowLimit {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            Count(usize),
+            Keyword(String),
+        }
+
+        match Raw::deserialize(deserializer)? {
+            Raw::Count(n) => Ok(RowLimit::Limited(n)),
+            Raw::Keyword(s) if s == "unlimited" => Ok(RowLimit::Unlimited),
+            Raw::Keyword(s) => Err(serde::de::Error::custom(format!(
+                "max_rows должен быть числом или \"unlimited\", получено '{}'",
+                s
+            ))),
+        }
+    }
 }
 
 impl Profile {
-    pub fn load(path: &str) -> Result<Self> {
-        let content = std::fs::read_to_string(path)
-            .context(format!("Не удалось прочитать файл: {}", path))?;
+    /// Собирает несколько профилей в один: записи следующих переопределяют записи предыдущих.
+    /// Имя результата - имена профилей через "+"
+    pub fn load_all(paths: &[impl AsRef<str>]) -> Result<Self> {
+        Self::load_with(ProfileLoader::new(), paths)
+    }
+
+    /// То же, но сами профили и их extends/include должны быть внутри каталога root
+    /// (профили устройств из инвентаря задаются через API)
+    pub fn load_all_within(root: &Path, paths: &[impl AsRef<str>]) -> Result<Self> {
+        Self::load_with(ProfileLoader::within(root)?, paths)
+    }
 
-        let profile: Profile =
-            serde_yml::from_str(&content).context("Не удалось распарсить YAML")?;
+    /// Ошибка, если файла профиля нет или он вне каталога root
+    pub fn check_within(root: &Path, path: &str) -> Result<()> {
+        ProfileLoader::within(root)?.inside(Path::new(path))
+    }
+
+    fn load_with(mut loader: ProfileLoader, paths: &[impl AsRef<str>]) -> Result<Self> {
+        let mut merged: Option<Profile> = None;
 
-        if profile.scalars.is_empty() && profile.tables.is_empty() {
-            anyhow::bail!("Профиль '{}' пустой", profile.name);
+        for path in paths {
+            let path = Path::new(path.as_ref());
+            loader.inside(path)?;
+            let profile = loader.resolve(path)?;
+            merged = Some(match merged {
+                None => profile,
+                Some(mut merged) => {
+                    merged.name = format!("{}+{}", merged.name, profile.name);
+                    merged.merge(profile);
+                    merged
+                }
+            });
         }
 
+        let profile = merged.ok_or_else(|| anyhow::anyhow!("Не задан ни один профиль"))?;
+        profile.validate()?;
         Ok(profile)
     }
+
+    /// Пустой профиль с именем
+    pub fn empty(name: String) -> Self {
+        Self {
+            name,
+            extends: Vec::new(),
+            include: Vec::new(),
+            remove: Vec::new(),
+            scalars: HashMap::new(),
+            tables: HashMap::new(),
+            joins: HashMap::new(),
+            derived: HashMap::new(),
+            provenance: BTreeMap::new(),
+        }
+    }
+
+    /// Проверяет собранный профиль: не пустой, объединения ссылаются на существующие таблицы
+    pub fn validate(&self) -> Result<()> {
+        if self.scalars.is_empty() && self.tables.is_empty() {
+            anyhow::bail!("Профиль '{}' пустой", self.name);
+        }
+
+        for (join_name, join) in &self.joins {
+            if let Some(unknown) = join
+                .table_names()
+                .find(|table| !self.tables.contains_key(*table))
+            {
+                anyhow::bail!(
+                    "Объединение '{}' ссылается на таблицу '{}', которой нет в профиле",
+                    join_name,
+                    unknown
+                );
+            }
+        }
+
+        let unknown = self.derived.iter().find_map(|(name, derived)| {
+            derived
+                .expr
+                .variables()
+                .into_iter()
+                .find(|variable| {
+                    !self.scalars.contains_key(*variable) && !self.derived.contains_key(*variable)
+                })
+                .map(|variable| (name, variable))
+        });
+        if let Some((name, variable)) = unknown {
+            anyhow::bail!(
+                "Производная метрика '{}' ссылается на '{}', которого нет среди скаляров",
+                name,
+                variable
+            );
+        }
+
+        DerivedConfig::order(&self.derived).context(format!("Профиль '{}'", self.name))?;
+        for (name, table) in &self.tables {
+            DerivedConfig::order(&table.derived).context(format!("Таблица '{}'", name))?;
+        }
+        for (name, join) in &self.joins {
+            DerivedConfig::order(&join.derived).context(format!("Объединение '{}'", name))?;
+        }
+
+        Ok(())
+    }
+
+    /// Добавляет записи другого профиля поверх своих, одинаковые ключи переопределяются
+    pub fn merge(&mut self, other: Profile) {
+        self.scalars.extend(other.scalars);
+        self.tables.extend(other.tables);
+        self.joins.extend(other.joins);
+        self.derived.extend(other.derived);
+        self.provenance.extend(other.provenance);
+    }
+
+    /// Отмечает источником всех записей без источника файл source
+    pub fn mark_source(&mut self, source: &str) {
+        let keys = self
+            .scalars
+            .keys()
+            .map(|key| format!("scalars.{}", key))
+            .chain(self.tables.keys().map(|key| format!("tables.{}", key)))
+            .chain(self.joins.keys().map(|key| format!("joins.{}", key)))
+            .chain(self.derived.keys().map(|key| format!("derived.{}", key)))
+            .collect::<Vec<_>>();
+        for key in keys {
+            self.provenance
+                .entry(key)
+                .or_insert_with(|| source.to_string());
+        }
+    }
+
+    /// Удаляет записи вида "scalars.sysLocation". Запись должна существовать
+    pub fn remove_entries(&mut self, entries: &[String]) -> Result<()> {
+        for entry in entries {
+            let removed = match entry.split_once('.') {
+                Some(("scalars", key)) => self.scalars.remove(key).is_some(),
+                Some(("tables", key)) => self.tables.remove(key).is_some(),
+                Some(("joins", key)) => self.joins.remove(key).is_some(),
+                Some(("derived", key)) => self.derived.remove(key).is_some(),
+                _ => anyhow::bail!(
+                    "remove: '{}' - ожидается scalars.<имя>, tables.<имя>, joins.<имя> или derived.<имя>",
+                    entry
+                ),
+            };
+            if !removed {
+                anyhow::bail!("remove: '{}' нет среди унаследованных записей", entry);
+            }
+            self.provenance.remove(entry);
+        }
+        Ok(())
+    }
+
+    /// Есть ли запись вида "scalars.sysLocation" в профиле
+    pub fn has_entry(&self, entry: &str) -> bool {
+        match entry.split_once('.') {
+            Some(("scalars", key)) => self.scalars.contains_key(key),
+            Some(("tables", key)) => self.tables.contains_key(key),
+            Some(("joins", key)) => self.joins.contains_key(key),
+            Some(("derived", key)) => self.derived.contains_key(key),
+            _ => false,
+        }
+    }
+
+    /// Оставляет записи, для которых keep("scalars.имя", "tables.имя", ...) вернул true
+    pub fn retain_entries(&mut self, keep: impl Fn(&str) -> bool) {
+        self.scalars.retain(|name, _| keep(&format!("scalars.{}", name)));
+        self.tables.retain(|name, _| keep(&format!("tables.{}", name)));
+        self.joins.retain(|name, _| keep(&format!("joins.{}", name)));
+        self.derived.retain(|name, _| keep(&format!("derived.{}", name)));
+    }
+
+    /// Заменяет имена из MIB (IF-MIB::ifDescr, sysUpTime.0) на числовые OID.
+    /// source - текст YAML, нужен только для номеров строк в ошибках
+    pub fn resolve_oids(&mut self, source: &str, tree: &MibTree) -> Result<()> {
+        let mut errors = Vec::new();
+
+        let mut resolve = |section: &str, key: &str, oid: &mut String| {
+            match tree.parse_name(oid) {
+                Ok(numeric) => *oid = format_oid(&numeric),
+                Err(e) => {
+                    let location = match Self::line_of(source, key, oid) {
+                        Some(line) => format!("строка {}", line),
+                        None => "строка ?".to_string(),
+                    };
+                    errors.push(format!("{}: {}.{}: {}", location, section, key, e));
+                }
+            }
+        };
+
+        for (name, scalar) in self.scalars.iter_mut() {
+            resolve("scalars", name, &mut scalar.oid);
+            if let Some(oid) = scalar.when.when_exists.as_mut() {
+                resolve("scalars", name, oid);
+            }
+        }
+        for (name, table) in self.tables.iter_mut() {
+            resolve("tables", name, &mut table.oid);
+            if let Some(entry) = table.entry.as_mut() {
+                resolve("tables", name, entry);
+            }
+            if let Some(oid) = table.when.when_exists.as_mut() {
+                resolve("tables", name, oid);
+            }
+        }
+        for (name, join) in self.joins.iter_mut() {
+            if let Some(oid) = join.when.when_exists.as_mut() {
+                resolve("joins", name, oid);
+            }
+        }
+
+        if !errors.is_empty() {
+            errors.sort();
+            anyhow::bail!("Не удалось разрешить OID:\n  {}", errors.join("\n  "));
+        }
+
+        Ok(())
+    }
+
+    /// Номер строки YAML со значением: сначала строка вида "key: value", затем любая с value
+    pub(crate) fn line_of(source: &str, key: &str, value: &str) -> Option<usize> {
+        let lines: Vec<&str> = source
+            .lines()
+            .map(|line| line.split(" #").next().unwrap_or(line))
+            .collect();
+        let is_key = |line: &str| line.trim_start().starts_with(&format!("{}:", key));
+
+        lines
+            .iter()
+            .position(|line| is_key(line) && line.contains(value))
+            .or_else(|| {
+                // oid: внутри объекта таблицы - ищем после строки с ключом
+                let start = lines.iter().position(|line| is_key(line))?;
+                lines[start..]
+                    .iter()
+                    .position(|line| line.contains(value))
+                    .map(|offset| start + offset)
+            })
+            .or_else(|| lines.iter().position(|line| line.contains(value)))
+            .map(|index| index + 1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unknown_table_key_is_rejected_with_line() {
+        let yaml = "
+name: test
+tables:
+  ifTable:
+    oid: 1.3.6.1.2.1.2.2
+    max_row: 10
+";
+
+        let err = serde_yml::from_str::<Profile>(yaml)
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("max_row"), "{}", err);
+        assert!(err.contains("line 4"), "{}", err);
+    }
+
+    #[test]
+    fn table_options_are_read() {
+        let yaml = "
+name: test
+tables:
+  ifTable:
+    oid: 1.3.6.1.2.1.2.2
+    max_rows: unlimited
+    timeout: 20
+";
+
+        let profile = serde_yml::from_str::<Profile>(yaml).unwrap();
+
+        let table = &profile.tables["ifTable"];
+        assert_eq!(table.max_rows, Some(RowLimit::Unlimited));
+        assert_eq!(table.timeout, Some(20));
+    }
 }
@@ -0,0 +1,803 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use super::metric::Scale;
+use super::profile::{DerivedConfig, Profile, TableConfig};
+use super::profile_loader::ProfileLoader;
+use crate::mib::{self, MibTree, NodeRole, format_oid, parse_numeric_oid};
+
+/// Ключи верхнего уровня, которые понимает профиль
+const KNOWN_KEYS: &[&str] = &[
+    "name", "extends", "include", "remove", "scalars", "tables", "joins", "derived",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// Замечание к профилю
+#[derive(Debug, Clone, Serialize)]
+pub struct LintIssue {
+    pub line: Option<usize>,
+    pub severity: Severity,
+    /// Вид замечания: bad_oid, unknown_mib_name, missing_instance, scalar_is_table,
+    /// table_is_column, bad_metric, unknown_column, duplicate_oid, overlapping_tables,
+    /// unknown_key, yaml, compose
+    pub code: &'static str,
+    /// Запись профиля: "scalars.sysName", "tables.ifTable"
+    pub entry: Option<String>,
+    pub message: String,
+}
+
+/// Результат проверки одного файла профиля
+#[derive(Debug, Clone, Serialize)]
+pub struct LintReport {
+    pub file: String,
+    pub issues: Vec<LintIssue>,
+}
+
+impl LintReport {
+    pub fn has_errors(&self) -> bool {
+        self.issues
+            .iter()
+            .any(|issue| issue.severity == Severity::Error)
+    }
+}
+
+impl std::fmt::Display for LintReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.issues.is_empty() {
+            return writeln!(f, "{}: ok", self.file);
+        }
+        for issue in &self.issues {
+            let line = issue.line.map(|line| format!(":{}", line)).unwrap_or_default();
+            let severity = match issue.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            let entry = issue
+                .entry
+                .as_ref()
+                .map(|entry| format!(" {}:", entry))
+                .unwrap_or_default();
+            writeln!(
+                f,
+                "{}{}: {}[{}]{} {}",
+                self.file, line, severity, issue.code, entry, issue.message
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Проверка профилей: синтаксис и разрешение OID, суффиксы скаляров, корни таблиц,
+/// дубликаты и пересечения. Замечания привязаны к строкам файла
+pub struct ProfileLinter;
+
+impl ProfileLinter {
+    /// Проверяет файл профиля
+    pub fn lint_file(path: &str) -> LintReport {
+        match std::fs::read_to_string(path) {
+            Ok(content) => Self::lint_source(path, &content),
+            Err(e) => LintReport {
+                file: path.to_string(),
+                issues: vec![Self::issue(
+                    None,
+                    Severity::Error,
+                    "yaml",
+                    None,
+                    format!("Не удалось прочитать файл: {}", e),
+                )],
+            },
+        }
+    }
+
+    /// Проверяет текст профиля. path - где профиль лежит (или будет лежать), от него
+    /// ищутся extends/include
+    pub fn lint_source(path: &str, content: &str) -> LintReport {
+        Self::lint(path, content, ProfileLoader::new())
+    }
+
+    /// То же для присланного текста: extends/include только внутри root
+    pub fn lint_source_within(root: &Path, path: &str, content: &str) -> LintReport {
+        match ProfileLoader::within(root) {
+            Ok(loader) => Self::lint(path, content, loader),
+            Err(e) => LintReport {
+                file: path.to_string(),
+                issues: vec![Self::issue(
+                    None,
+                    Severity::Error,
+                    "compose",
+                    None,
+                    format!("{:#}", e),
+                )],
+            },
+        }
+    }
+
+    fn lint(path: &str, content: &str, mut loader: ProfileLoader) -> LintReport {
+        let tree = mib::global();
+        let mut issues = Vec::new();
+
+        let raw: Profile = match serde_yml::from_str(content) {
+            Ok(raw) => raw,
+            Err(e) => {
+                issues.push(Self::issue(
+                    e.location().map(|location| location.line()),
+                    Severity::Error,
+                    "yaml",
+                    None,
+                    e.to_string(),
+                ));
+                return LintReport {
+                    file: path.to_string(),
+                    issues,
+                };
+            }
+        };
+
+        Self::check_keys(content, &mut issues);
+
+        for (name, scalar) in &raw.scalars {
+            let line = Profile::line_of(content, name, &scalar.oid);
+            let entry = format!("scalars.{}", name);
+            if let Some(numeric) = Self::check_oid(&scalar.oid, &tree, line, &entry, &mut issues) {
+                Self::check_scalar(&numeric, &tree, line, &entry, &mut issues);
+            }
+            if let Some(Scale::Column(column)) = &scalar.meta.scale {
+                issues.push(Self::issue(
+                    line,
+                    Severity::Error,
+                    "bad_metric",
+                    Some(entry),
+                    format!(
+                        "scale: '{}' - множитель из колонки возможен только в таблице, у скаляра - число",
+                        column
+                    ),
+                ));
+            }
+        }
+        for (name, table) in &raw.tables {
+            let line = Profile::line_of(content, name, &table.oid);
+            let entry = format!("tables.{}", name);
+            if let Some(numeric) = Self::check_oid(&table.oid, &tree, line, &entry, &mut issues) {
+                Self::check_table(&numeric, &tree, line, &entry, &mut issues);
+            }
+            if let Some(entry_oid) = &table.entry {
+                let line = Profile::line_of(content, name, entry_oid);
+                Self::check_oid(entry_oid, &tree, line, &entry, &mut issues);
+            }
+            Self::check_metrics(table, &tree, content, &entry, &mut issues);
+        }
+
+        // when_exists - любой узел дерева, без требований к скаляру или таблице
+        let conditions = raw
+            .scalars
+            .iter()
+            .map(|(name, scalar)| ("scalars", name, &scalar.when))
+            .chain(raw.tables.iter().map(|(name, table)| ("tables", name, &table.when)))
+            .chain(raw.joins.iter().map(|(name, join)| ("joins", name, &join.when)));
+        for (section, name, when) in conditions {
+            if let Some(oid) = &when.when_exists {
+                let line = Profile::line_of(content, name, oid);
+                let entry = format!("{}.{}", section, name);
+                Self::check_oid(oid, &tree, line, &entry, &mut issues);
+            }
+        }
+
+        // Дубликаты и пересечения - по собранному профилю, если собственные записи в порядке
+        if !issues.iter().any(|issue| issue.severity == Severity::Error) {
+            match loader.resolve_source(Path::new(path), content) {
+                Ok(profile) => {
+                    if let Err(e) = profile.validate() {
+                        issues.push(Self::issue(
+                            None,
+                            Severity::Error,
+                            "compose",
+                            None,
+                            format!("{:#}", e),
+                        ));
+                    }
+                    Self::check_duplicates(&profile, path, content, &mut issues);
+                    Self::check_overlaps(&profile, path, content, &mut issues);
+                    Self::check_derived(&profile, &tree, path, content, &mut issues);
+                }
+                Err(e) => issues.push(Self::issue(
+                    None,
+                    Severity::Error,
+                    "compose",
+                    None,
+                    format!("{:#}", e),
+                )),
+            }
+        }
+
+        issues.sort_by_key(|issue| (issue.line.unwrap_or(0), issue.severity));
+        LintReport {
+            file: path.to_string(),
+            issues,
+        }
+    }
+
+    /// Проверяет файлы и каталоги; для каталогов - все профили в них, кроме exclude
+    pub fn lint_paths(paths: &[PathBuf], exclude: &[PathBuf]) -> Vec<LintReport> {
+        paths
+            .iter()
+            .flat_map(|path| {
+                if path.is_dir() {
+                    Self::profile_files(path, exclude)
+                } else {
+                    vec![path.clone()]
+                }
+            })
+            .map(|path| Self::lint_file(&path.display().to_string()))
+            .collect()
+    }
+
+    /// Файлы профилей в каталоге (рекурсивно), кроме файлов из exclude
+    pub fn profile_files(dir: &Path, exclude: &[PathBuf]) -> Vec<PathBuf> {
+        let exclude: Vec<PathBuf> = exclude
+            .iter()
+            .filter_map(|path| path.canonicalize().ok())
+            .collect();
+        let mut files = Vec::new();
+        let mut dirs = vec![dir.to_path_buf()];
+
+        while let Some(dir) = dirs.pop() {
+            let Ok(entries) = std::fs::read_dir(&dir) else {
+                continue;
+            };
+            for path in entries.flatten().map(|entry| entry.path()) {
+                if path.is_dir() {
+                    dirs.push(path);
+                } else if matches!(
+                    path.extension().and_then(|ext| ext.to_str()),
+                    Some("yaml" | "yml")
+                ) && !path
+                    .canonicalize()
+                    .is_ok_and(|canonical| exclude.contains(&canonical))
+                {
+                    files.push(path);
+                }
+            }
+        }
+
+        files.sort();
+        files
+    }
+
+    fn issue(
+        line: Option<usize>,
+        severity: Severity,
+        code: &'static str,
+        entry: Option<String>,
+        message: String,
+    ) -> LintIssue {
+        LintIssue {
+            line,
+            severity,
+            code,
+            entry,
+            message,
+        }
+    }
+
+    /// Опечатки в ключах верхнего уровня молча игнорируются serde - предупреждаем
+    fn check_keys(content: &str, issues: &mut Vec<LintIssue>) {
+        let Ok(serde_yml::Value::Mapping(mapping)) = serde_yml::from_str(content) else {
+            return;
+        };
+        for key in mapping.keys().filter_map(|key| key.as_str()) {
+            if !KNOWN_KEYS.contains(&key) {
+                issues.push(Self::issue(
+                    Profile::line_of(content, key, ""),
+                    Severity::Warning,
+                    "unknown_key",
+                    None,
+                    format!(
+                        "неизвестный ключ '{}' (ожидается один из: {})",
+                        key,
+                        KNOWN_KEYS.join(", ")
+                    ),
+                ));
+            }
+        }
+    }
+
+    /// Синтаксис числового OID или разрешение имени MIB
+    fn check_oid(
+        text: &str,
+        tree: &MibTree,
+        line: Option<usize>,
+        entry: &str,
+        issues: &mut Vec<LintIssue>,
+    ) -> Option<Vec<u32>> {
+        let text = text.trim();
+        let mut report = |code, message: String| {
+            issues.push(Self::issue(
+                line,
+                Severity::Error,
+                code,
+                Some(entry.to_string()),
+                message,
+            ));
+            None
+        };
+
+        let looks_numeric = text
+            .chars()
+            .all(|c| c.is_ascii_digit() || c == '.' || c.is_whitespace());
+        if looks_numeric {
+            return match parse_numeric_oid(text) {
+                Some(oid) => match Self::oid_syntax_error(&oid) {
+                    Some(error) => report("bad_oid", format!("'{}': {}", text, error)),
+                    None => Some(oid),
+                },
+                None => report(
+                    "bad_oid",
+                    format!("'{}' - не OID (пустой компонент или лишние точки)", text),
+                ),
+            };
+        }
+
+        let (name, suffix) = MibTree::split_name(text);
+        if tree.lookup(name).is_none() {
+            return report("unknown_mib_name", format!("неизвестное имя MIB '{}'", name));
+        }
+        if !suffix.is_empty() && parse_numeric_oid(suffix).is_none() {
+            return report(
+                "bad_oid",
+                format!("'{}': суффикс '{}' должен быть числовым", text, suffix),
+            );
+        }
+        tree.parse_name(text).ok()
+    }
+
+    /// Правила BER для первых двух компонентов OID
+    fn oid_syntax_error(oid: &[u32]) -> Option<String> {
+        match oid {
+            [] | [_] => Some("OID должен содержать хотя бы два компонента".to_string()),
+            [first, ..] if *first > 2 => {
+                Some(format!("первый компонент {} (допустимо 0, 1 или 2)", first))
+            }
+            [first, second, ..] if *first < 2 && *second >= 40 => Some(format!(
+                "второй компонент {} при первом {} (допустимо 0..39)",
+                second, first
+            )),
+            _ => None,
+        }
+    }
+
+    /// Скаляр должен указывать на экземпляр: sysName.0, а не sysName и не таблицу
+    fn check_scalar(
+        oid: &[u32],
+        tree: &MibTree,
+        line: Option<usize>,
+        entry: &str,
+        issues: &mut Vec<LintIssue>,
+    ) {
+        let Some(resolved) = tree.resolve(oid) else {
+            return;
+        };
+        let node = resolved.node;
+        let (severity, code, message) = match (tree.role(node), resolved.suffix.is_empty()) {
+            (NodeRole::Scalar, true) => (
+                Severity::Warning,
+                "missing_instance",
+                format!("нет суффикса экземпляра: нужен {}.0", node.qualified_name()),
+            ),
+            (NodeRole::Column, true) => (
+                Severity::Warning,
+                "missing_instance",
+                format!(
+                    "{} - колонка таблицы, для скаляра нужен индекс строки ({}.<индекс>)",
+                    node.qualified_name(),
+                    node.name
+                ),
+            ),
+            (NodeRole::Table | NodeRole::Row, _) => (
+                Severity::Error,
+                "scalar_is_table",
+                format!(
+                    "{} - таблица, ее нужно описать в tables",
+                    node.qualified_name()
+                ),
+            ),
+            _ => return,
+        };
+        issues.push(Self::issue(line, severity, code, Some(entry.to_string()), message));
+    }
+
+    /// Корень таблицы не должен быть колонкой или скаляром
+    fn check_table(
+        oid: &[u32],
+        tree: &MibTree,
+        line: Option<usize>,
+        entry: &str,
+        issues: &mut Vec<LintIssue>,
+    ) {
+        let Some(resolved) = tree.resolve(oid) else {
+            return;
+        };
+        let node = resolved.node;
+        let message = match tree.role(node) {
+            NodeRole::Column => {
+                let table = tree
+                    .parent(&node.oid)
+                    .and_then(|row| tree.parent(&row.oid))
+                    .map(|table| format!(", таблица - {}", table.qualified_name()))
+                    .unwrap_or_default();
+                format!("{} - колонка, а не таблица{}", resolved, table)
+            }
+            NodeRole::Scalar => format!("{} - скаляр, а не таблица", resolved),
+            _ => return,
+        };
+        issues.push(Self::issue(
+            line,
+            Severity::Error,
+            "table_is_column",
+            Some(entry.to_string()),
+            message,
+        ));
+    }
+
+    /// metrics таблицы ссылаются на ее колонки (если колонки известны из MIB или columns)
+    fn check_metrics(
+        table: &TableConfig,
+        tree: &MibTree,
+        content: &str,
+        entry: &str,
+        issues: &mut Vec<LintIssue>,
+    ) {
+        let columns = Self::table_columns(table, tree);
+        if columns.is_empty() {
+            return;
+        }
+
+        for (column, meta) in &table.metrics {
+            let line = Profile::line_of(content, column, "");
+            let mut unknown = |name: &str, what: &str| {
+                issues.push(Self::issue(
+                    line,
+                    Severity::Warning,
+                    "unknown_column",
+                    Some(format!("{}.metrics.{}", entry, column)),
+                    format!("{} '{}' - нет такой колонки в таблице", what, name),
+                ));
+            };
+            if !columns.contains(column) {
+                unknown(column, "метрика");
+            }
+            if let Some(Scale::Column(scale)) = &meta.scale
+                && !columns.contains(scale)
+            {
+                unknown(scale, "scale");
+            }
+        }
+    }
+
+    /// Имена колонок таблицы: из профиля и из MIB
+    fn table_columns(table: &TableConfig, tree: &MibTree) -> Vec<String> {
+        let mut columns: Vec<String> = table.columns.values().cloned().collect();
+        if let Some(oid) = tree.parse_name(&table.oid).ok()
+            && let Some(node) = tree.node(&oid)
+            && tree.role(node) == NodeRole::Table
+        {
+            for row in tree.children(&node.oid) {
+                columns.extend(tree.children(&row.oid).iter().map(|column| column.name.clone()));
+            }
+        }
+        columns
+    }
+
+    /// Имена в выражениях вычисляемых колонок, которых нет ни среди колонок таблицы
+    /// (объединения), ни среди скаляров. Проверяются записи из этого файла
+    fn check_derived(
+        profile: &Profile,
+        tree: &MibTree,
+        path: &str,
+        content: &str,
+        issues: &mut Vec<LintIssue>,
+    ) {
+        // Колонки таблицы вместе с вычисляемыми; пусто - таблицы нет в MIB, проверять не с чем
+        let known_columns = |name: &str| -> Vec<String> {
+            let Some(table) = profile.tables.get(name) else {
+                return Vec::new();
+            };
+            let mut columns = Self::table_columns(table, tree);
+            if !columns.is_empty() {
+                columns.extend(table.derived.keys().cloned());
+            }
+            columns
+        };
+
+        let mut targets: Vec<(String, Vec<String>, &BTreeMap<String, DerivedConfig>)> = Vec::new();
+        for (name, table) in &profile.tables {
+            targets.push((format!("tables.{}", name), known_columns(name), &table.derived));
+        }
+        for (name, join) in &profile.joins {
+            let mut columns = Vec::new();
+            for table in join.table_names() {
+                let own = known_columns(table);
+                if own.is_empty() {
+                    columns.clear();
+                    break;
+                }
+                columns.extend(own.iter().map(|column| format!("{}.{}", table, column)));
+                columns.extend(own);
+            }
+            if !columns.is_empty() {
+                columns.extend(join.derived.keys().cloned());
+            }
+            targets.push((format!("joins.{}", name), columns, &join.derived));
+        }
+
+        for (entry, columns, derived) in targets {
+            if columns.is_empty() || !Self::is_own(profile, &entry, path) {
+                continue;
+            }
+            for (name, config) in derived {
+                for variable in config.expr.variables() {
+                    if columns.iter().any(|column| column == variable)
+                        || profile.scalars.contains_key(variable)
+                    {
+                        continue;
+                    }
+                    issues.push(Self::issue(
+                        Profile::line_of(content, name, ""),
+                        Severity::Warning,
+                        "unknown_column",
+                        Some(format!("{}.derived.{}", entry, name)),
+                        format!("'{}' - нет такой колонки в таблице и такого скаляра", variable),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// Одинаковые OID под разными именами. Отмечается запись из проверяемого файла
+    fn check_duplicates(
+        profile: &Profile,
+        path: &str,
+        content: &str,
+        issues: &mut Vec<LintIssue>,
+    ) {
+        // Скаляр с transform - намеренно другое представление того же OID
+        let entries = profile
+            .scalars
+            .iter()
+            .filter(|(_, scalar)| scalar.meta.transform.is_empty())
+            .map(|(name, scalar)| (format!("scalars.{}", name), name, &scalar.oid))
+            .chain(
+                profile
+                    .tables
+                    .iter()
+                    .map(|(name, table)| (format!("tables.{}", name), name, &table.oid)),
+            );
+
+        let mut by_oid: BTreeMap<&str, Vec<(String, &String)>> = BTreeMap::new();
+        for (entry, name, oid) in entries {
+            by_oid.entry(oid.as_str()).or_default().push((entry, name));
+        }
+
+        for (oid, mut group) in by_oid.into_iter().filter(|(_, group)| group.len() > 1) {
+            group.sort();
+            let (first, _) = &group[0];
+            for (entry, name) in &group[1..] {
+                if !Self::is_own(profile, entry, path) {
+                    continue;
+                }
+                issues.push(Self::issue(
+                    Profile::line_of(content, name, ""),
+                    Severity::Warning,
+                    "duplicate_oid",
+                    Some(entry.clone()),
+                    format!("OID {} уже собирается как {}", oid, first),
+                ));
+            }
+        }
+    }
+
+    /// Таблица внутри другой таблицы: поддерево будет обойдено дважды
+    fn check_overlaps(profile: &Profile, path: &str, content: &str, issues: &mut Vec<LintIssue>) {
+        let tables: Vec<(&String, Vec<u32>)> = profile
+            .tables
+            .iter()
+            .filter_map(|(name, table)| Some((name, parse_numeric_oid(&table.oid)?)))
+            .collect();
+
+        for (inner_name, inner) in &tables {
+            for (outer_name, outer) in &tables {
+                if inner.len() <= outer.len() || !inner.starts_with(outer) {
+                    continue;
+                }
+                let inner_entry = format!("tables.{}", inner_name);
+                let outer_entry = format!("tables.{}", outer_name);
+                if !Self::is_own(profile, &inner_entry, path)
+                    && !Self::is_own(profile, &outer_entry, path)
+                {
+                    continue;
+                }
+                issues.push(Self::issue(
+                    Profile::line_of(content, inner_name, ""),
+                    Severity::Warning,
+                    "overlapping_tables",
+                    Some(inner_entry),
+                    format!(
+                        "{} лежит внутри {} ({}), данные будут собраны дважды",
+                        format_oid(inner),
+                        outer_entry,
+                        format_oid(outer)
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn is_own(profile: &Profile, entry: &str, path: &str) -> bool {
+        profile
+            .provenance
+            .get(entry)
+            .is_some_and(|source| source == path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mib::parse_mib;
+
+    /// Скаляр testName и таблица peerTable с колонками peerState, peerSpeed
+    const TEST_MIB: &str = "
+TEST-MIB DEFINITIONS ::= BEGIN
+IMPORTS OBJECT-TYPE, Integer32 FROM SNMPv2-SMI;
+
+testMib OBJECT IDENTIFIER ::= { iso 3 6 1 4 1 99999 }
+
+testName OBJECT-TYPE
+    SYNTAX OCTET STRING
+    MAX-ACCESS read-only
+    STATUS current
+    ::= { testMib 1 }
+
+peerTable OBJECT-TYPE
+    SYNTAX SEQUENCE OF PeerEntry
+    MAX-ACCESS not-accessible
+    STATUS current
+    ::= { testMib 2 }
+
+peerEntry OBJECT-TYPE
+    SYNTAX PeerEntry
+    MAX-ACCESS not-accessible
+    STATUS current
+    INDEX { peerState }
+    ::= { peerTable 1 }
+
+PeerEntry ::= SEQUENCE { peerState INTEGER, peerSpeed Integer32 }
+
+peerState OBJECT-TYPE
+    SYNTAX INTEGER
+    MAX-ACCESS read-only
+    STATUS current
+    ::= { peerEntry 1 }
+
+peerSpeed OBJECT-TYPE
+    SYNTAX Integer32
+    MAX-ACCESS read-only
+    STATUS current
+    ::= { peerEntry 2 }
+
+END
+";
+
+    /// Замечания к профилю: (строка, код). Имена MIB разрешаются и при сборке профиля,
+    /// поэтому тестовое дерево ставится глобальным
+    fn lint(content: &str) -> Vec<(Option<usize>, &'static str)> {
+        let modules = parse_mib(TEST_MIB).unwrap();
+        let (tree, errors) = MibTree::build(modules.into_iter().map(|m| (m, None)).collect());
+        assert!(errors.is_empty(), "{:?}", errors);
+        mib::install(tree);
+
+        let report = ProfileLinter::lint_source("test.yaml", content);
+        report
+            .issues
+            .iter()
+            .map(|issue| (issue.line, issue.code))
+            .collect()
+    }
+
+    #[test]
+    fn valid_profile_has_no_issues() {
+        let content = "
+scalars:
+  name: TEST-MIB::testName.0
+tables:
+  peers:
+    oid: TEST-MIB::peerTable
+    metrics:
+      peerSpeed: { unit: bps }
+    derived:
+      bits: { expr: peerSpeed * 8 }
+";
+        assert_eq!(lint(content), []);
+    }
+
+    #[test]
+    fn yaml_and_keys() {
+        assert_eq!(lint("scalars: [\n"), [(Some(1), "yaml")]);
+        assert_eq!(
+            lint("scalars:\n  name: 1.3.6.1.4.1.1.0\nderive:\n  x: { expr: name }\n"),
+            [(Some(3), "unknown_key")]
+        );
+    }
+
+    #[test]
+    fn oids() {
+        assert_eq!(lint("scalars:\n  name: 3.6.1.0\n"), [(Some(2), "bad_oid")]);
+        assert_eq!(
+            lint("scalars:\n  name: NOPE-MIB::nope.0\n"),
+            [(Some(2), "unknown_mib_name")]
+        );
+        assert_eq!(
+            lint("scalars:\n  name: TEST-MIB::testName\n"),
+            [(Some(2), "missing_instance")]
+        );
+        assert_eq!(
+            lint("scalars:\n  name: TEST-MIB::peerTable\n"),
+            [(Some(2), "scalar_is_table")]
+        );
+        assert_eq!(
+            lint("tables:\n  peers: TEST-MIB::peerState\n"),
+            [(Some(2), "table_is_column")]
+        );
+    }
+
+    #[test]
+    fn metrics_and_derived() {
+        let content = "
+scalars:
+  name: { oid: TEST-MIB::testName.0, scale: peerSpeed }
+";
+        assert_eq!(lint(content), [(Some(3), "bad_metric")]);
+
+        let content = "
+tables:
+  peers:
+    oid: TEST-MIB::peerTable
+    metrics:
+      peerSped: { unit: bps }
+    derived:
+      bits: { expr: peerSpeed * limit }
+";
+        assert_eq!(
+            lint(content),
+            [(Some(6), "unknown_column"), (Some(8), "unknown_column")]
+        );
+    }
+
+    #[test]
+    fn duplicates_and_overlaps() {
+        let content = "
+scalars:
+  first: 1.3.6.1.4.1.1.0
+  second: 1.3.6.1.4.1.1.0
+";
+        assert_eq!(lint(content), [(Some(4), "duplicate_oid")]);
+
+        let content = "
+tables:
+  outer: 1.3.6.1.4.1.1
+  inner: 1.3.6.1.4.1.1.5
+";
+        assert_eq!(lint(content), [(Some(4), "overlapping_tables")]);
+    }
+
+    #[test]
+    fn compose() {
+        assert_eq!(lint("extends: missing-parent\n"), [(None, "compose")]);
+    }
+}
@@ -0,0 +1,342 @@
+use anyhow::{Context, Result, bail};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use super::profile::Profile;
+use crate::mib;
+
+/// Каталог фрагментов относительно профиля, который их подключает
+const FRAGMENTS_DIR: &str = "fragments";
+
+/// Сборка профиля по графу extends/include: поиск файлов, циклы, слияние и источники записей
+pub(crate) struct ProfileLoader {
+    /// Цепочка файлов, которые сейчас собираются, для поиска циклов
+    stack: Vec<PathBuf>,
+    /// Уже собранные файлы: общий родитель собирается один раз
+    resolved: HashMap<PathBuf, Profile>,
+    /// Каталог, за пределы которого extends/include не выходят (профили из API)
+    root: Option<PathBuf>,
+}
+
+impl ProfileLoader {
+    pub fn new() -> Self {
+        Self {
+            stack: Vec::new(),
+            resolved: HashMap::new(),
+            root: None,
+        }
+    }
+
+    /// Загрузчик, которому доступны только файлы внутри root
+    pub fn within(root: &Path) -> Result<Self> {
+        let root = root
+            .canonicalize()
+            .context(format!("Каталог профилей не найден: {}", root.display()))?;
+        Ok(Self {
+            root: Some(root),
+            ..Self::new()
+        })
+    }
+
+    /// Профиль со всеми родителями и фрагментами, без проверки полноты
+    pub fn resolve(&mut self, path: &Path) -> Result<Profile> {
+        let content = std::fs::read_to_string(path)
+            .context(format!("Не удалось прочитать файл: {}", path.display()))?;
+        self.resolve_source(path, &content)
+    }
+
+    /// То же для текста профиля, которого может не быть на диске (проверка из API).
+    /// path нужен для поиска extends/include и в сообщениях
+    pub fn resolve_source(&mut self, path: &Path, content: &str) -> Result<Profile> {
+        let key = Self::key(path)?;
+
+        if let Some(position) = self.stack.iter().position(|file| *file == key) {
+            let cycle = self.stack[position..]
+                .iter()
+                .chain(std::iter::once(&key))
+                .map(|file| file.display().to_string())
+                .collect::<Vec<_>>()
+                .join(" -> ");
+            bail!("Цикл в наследовании профилей: {}", cycle);
+        }
+        if let Some(profile) = self.resolved.get(&key) {
+            return Ok(profile.clone());
+        }
+
+        let own = Self::parse(path, content)?;
+        self.stack.push(key.clone());
+        let result = self.compose(path, own);
+        self.stack.pop();
+
+        let profile = result?;
+        self.resolved.insert(key, profile.clone());
+        Ok(profile)
+    }
+
+    /// Абсолютный путь файла для поиска циклов; файла может еще не быть
+    fn key(path: &Path) -> Result<PathBuf> {
+        if let Ok(key) = path.canonicalize() {
+            return Ok(key);
+        }
+        let dir = match path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        let dir = dir
+            .canonicalize()
+            .context(format!("Каталог профиля не найден: {}", dir.display()))?;
+        Ok(dir.join(path.file_name().unwrap_or_default()))
+    }
+
+    /// Разбирает текст профиля и разрешает в нем имена MIB (номера строк - по этому тексту)
+    fn parse(path: &Path, content: &str) -> Result<Profile> {
+        let mut profile: Profile = serde_yml::from_str(content)
+            .context(format!("Не удалось распарсить YAML {}", path.display()))?;
+
+        if profile.name.is_empty() {
+            profile.name = path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+
+        profile
+            .resolve_oids(content, &mib::global())
+            .context(format!("Профиль {}", path.display()))?;
+
+        Ok(profile)
+    }
+
+    /// Родители по порядку, затем фрагменты, затем remove и собственные записи файла
+    fn compose(&mut self, path: &Path, mut own: Profile) -> Result<Profile> {
+        let source = path.display().to_string();
+        let dir = path.parent().unwrap_or(Path::new("."));
+        let mut merged = Profile::empty(own.name.clone());
+
+        let references = own.extends.iter().chain(own.include.iter());
+        for reference in references {
+            let parent_path = self
+                .locate(dir, reference)
+                .with_context(|| format!("Профиль {}", source))?;
+            let parent = self
+                .resolve(&parent_path)
+                .with_context(|| format!("Профиль {}: '{}'", source, reference))?;
+            merged.merge(parent);
+        }
+
+        merged
+            .remove_entries(&own.remove)
+            .context(format!("Профиль {}", source))?;
+
+        own.provenance.clear();
+        own.mark_source(&source);
+        merged.merge(own);
+        Ok(merged)
+    }
+
+    /// Файл для extends/include: путь относительно профиля, затем с .yaml, затем fragments/<имя>.yaml
+    fn locate(&self, dir: &Path, reference: &str) -> Result<PathBuf> {
+        if self.root.is_some() {
+            let path = Path::new(reference);
+            let forbidden = path.is_absolute()
+                || path
+                    .components()
+                    .any(|component| matches!(component, std::path::Component::ParentDir));
+            if forbidden {
+                bail!(
+                    "'{}' должен быть относительным и внутри каталога профилей",
+                    reference
+                );
+            }
+            let found = Self::find(dir, reference)?;
+            self.inside(&found)
+                .map_err(|_| anyhow::anyhow!("'{}' вне каталога профилей", reference))?;
+            return Ok(found);
+        }
+        Self::find(dir, reference)
+    }
+
+    /// Ошибка, если файл вне root. Ссылки (symlink) тоже не должны уводить из каталога
+    pub fn inside(&self, path: &Path) -> Result<()> {
+        let Some(root) = &self.root else {
+            return Ok(());
+        };
+        let canonical = path
+            .canonicalize()
+            .context(format!("Профиль не найден: {}", path.display()))?;
+        if !canonical.starts_with(root) {
+            bail!(
+                "'{}' вне каталога профилей {}",
+                path.display(),
+                root.display()
+            );
+        }
+        Ok(())
+    }
+
+    fn find(dir: &Path, reference: &str) -> Result<PathBuf> {
+        let candidates = [
+            dir.join(reference),
+            dir.join(format!("{}.yaml", reference)),
+            dir.join(FRAGMENTS_DIR).join(format!("{}.yaml", reference)),
+        ];
+
+        candidates
+            .iter()
+            .find(|candidate| candidate.is_file())
+            .cloned()
+            .with_context(|| {
+                format!(
+                    "'{}' не найден (искали {})",
+                    reference,
+                    candidates
+                        .iter()
+                        .map(|candidate| candidate.display().to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                )
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Временный каталог с файлами профилей, удаляется вместе со значением
+    struct Profiles(PathBuf);
+
+    impl Drop for Profiles {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn profiles(name: &str, files: &[(&str, &str)]) -> Profiles {
+        let dir =
+            std::env::temp_dir().join(format!("profile-loader-{}-{}", std::process::id(), name));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(dir.join(FRAGMENTS_DIR)).unwrap();
+        for (file, content) in files {
+            std::fs::write(dir.join(file), content).unwrap();
+        }
+        Profiles(dir.canonicalize().unwrap())
+    }
+
+    fn resolve(dir: &Path, file: &str) -> Result<Profile> {
+        ProfileLoader::new().resolve(&dir.join(file))
+    }
+
+    #[test]
+    fn two_profiles_extending_each_other() {
+        let files = profiles(
+            "cycle",
+            &[("a.yaml", "extends: b\n"), ("b.yaml", "extends: a\n")],
+        );
+        let dir = &files.0;
+
+        let err = format!("{:#}", resolve(dir, "a.yaml").unwrap_err());
+
+        let a = dir.join("a.yaml").display().to_string();
+        let b = dir.join("b.yaml").display().to_string();
+        assert!(
+            err.contains(&format!(
+                "Цикл в наследовании профилей: {} -> {} -> {}",
+                a, b, a
+            )),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn self_include() {
+        let files = profiles(
+            "self",
+            &[
+                ("device.yaml", "include: loop\n"),
+                ("fragments/loop.yaml", "include: loop\n"),
+            ],
+        );
+        let dir = &files.0;
+
+        let err = format!("{:#}", resolve(dir, "device.yaml").unwrap_err());
+
+        let fragment = dir.join("fragments/loop.yaml").display().to_string();
+        assert!(
+            err.contains(&format!("{} -> {}", fragment, fragment)),
+            "{}",
+            err
+        );
+    }
+
+    #[test]
+    fn shared_parent_is_not_a_cycle() {
+        let files = profiles(
+            "diamond",
+            &[
+                ("base.yaml", "scalars:\n  sysName: 1.3.6.1.2.1.1.5.0\n"),
+                ("left.yaml", "extends: base\n"),
+                ("right.yaml", "extends: base\nremove: [scalars.sysName]\n"),
+                ("device.yaml", "extends: [left, right]\n"),
+            ],
+        );
+        let dir = &files.0;
+
+        let profile = resolve(dir, "device.yaml").unwrap();
+
+        assert_eq!(profile.name, "device");
+        // right убрал скаляр только у себя, left его унаследовал
+        assert!(profile.has_entry("scalars.sysName"));
+        assert_eq!(
+            profile.provenance["scalars.sysName"],
+            dir.join("base.yaml").display().to_string()
+        );
+    }
+
+    #[test]
+    fn removing_missing_entry() {
+        let files = profiles(
+            "remove",
+            &[
+                ("base.yaml", "scalars:\n  sysName: 1.3.6.1.2.1.1.5.0\n"),
+                (
+                    "device.yaml",
+                    "extends: base\nremove: [scalars.sysLocation]\n",
+                ),
+                ("typo.yaml", "extends: base\nremove: [scalar.sysName]\n"),
+            ],
+        );
+        let dir = &files.0;
+
+        let err = format!("{:#}", resolve(dir, "device.yaml").unwrap_err());
+        assert!(
+            err.contains("remove: 'scalars.sysLocation' нет среди унаследованных записей"),
+            "{}",
+            err
+        );
+        let err = format!("{:#}", resolve(dir, "typo.yaml").unwrap_err());
+        assert!(err.contains("ожидается scalars.<имя>"), "{}", err);
+    }
+
+    #[test]
+    fn within_root_rejects_outside_references() {
+        let files = profiles(
+            "root",
+            &[
+                ("up.yaml", "extends: ../base\n"),
+                ("absolute.yaml", "extends: /etc/hostname\n"),
+            ],
+        );
+        let dir = &files.0;
+        let outside = dir.parent().unwrap().join("outside.yaml");
+
+        let mut loader = ProfileLoader::within(dir).unwrap();
+        for file in ["up.yaml", "absolute.yaml"] {
+            let err = format!("{:#}", loader.resolve(&dir.join(file)).unwrap_err());
+            assert!(err.contains("внутри каталога профилей"), "{}", err);
+        }
+        assert!(loader.inside(&dir.join("up.yaml")).is_ok());
+        assert!(loader.inside(&outside).is_err());
+    }
+}
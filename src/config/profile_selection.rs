@@ -0,0 +1,131 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use super::profile::{Profile, one_or_many};
+use crate::snmp::DeviceInfo;
+
+/// Правило выбора профиля. Все заданные условия должны совпасть (без учета регистра),
+/// правило без условий совпадает с любым устройством
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SelectionRule {
+    pub device_type: Option<String>,
+    pub vendor: Option<String>,
+    pub os: Option<String>,
+    /// Путь к профилю или список профилей, которые собираются в один
+    #[serde(deserialize_with = "one_or_many")]
+    pub profile: Vec<String>,
+}
+
+impl SelectionRule {
+    fn matches(&self, device: &DeviceInfo) -> bool {
+        let check = |expected: &Option<String>, actual: Option<&str>| match expected {
+            Some(expected) => actual.is_some_and(|actual| actual.eq_ignore_ascii_case(expected)),
+            None => true,
+        };
+
+        check(&self.device_type, Some(&device.device_type))
+            && check(&self.vendor, device.vendor.as_deref())
+            && check(&self.os, device.os.as_deref())
+    }
+}
+
+/// Файл правил выбора профилей.
+///
+/// ```yaml
+/// rules:
+///   - device_type: cisco
+///     profile: profiles/cisco-ios.yaml
+///   - vendor: MikroTik
+///     profile: [profiles/generic-endpoint.yaml, profiles/mikrotik.yaml]
+/// fallback: profiles/generic-endpoint.yaml
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SelectionFile {
+    #[serde(default)]
+    rules: Vec<SelectionRule>,
+    /// Профиль для устройств, под которые не подошло ни одно правило
+    #[serde(deserialize_with = "one_or_many")]
+    fallback: Vec<String>,
+}
+
+/// Выбор профиля по определенному устройству: первое совпавшее правило, иначе fallback.
+/// Профили загружаются один раз при создании
+#[derive(Debug, Clone)]
+pub struct ProfileSelector {
+    rules: Vec<SelectionRule>,
+    fallback: Vec<String>,
+    /// Собранные профили по ключу "путь+путь"
+    profiles: HashMap<String, Profile>,
+}
+
+impl ProfileSelector {
+    /// Загружает правила и все профили, на которые они ссылаются
+    pub fn load(path: &str) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .context(format!("Не удалось прочитать файл: {}", path))?;
+        let file: SelectionFile = serde_yml::from_str(&content)
+            .context(format!("Не удалось распарсить правила выбора профилей {}", path))?;
+
+        let mut profiles = HashMap::new();
+        let mut errors = Vec::new();
+        let selections = file
+            .rules
+            .iter()
+            .map(|rule| &rule.profile)
+            .chain(std::iter::once(&file.fallback));
+        for paths in selections {
+            let key = Self::key(paths);
+            if profiles.contains_key(&key) {
+                continue;
+            }
+            match Profile::load_all(paths) {
+                Ok(profile) => {
+                    profiles.insert(key, profile);
+                }
+                Err(e) => errors.push(format!("{:#}", e)),
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!(
+                "Правила выбора профилей {}: не удалось загрузить профили:\n  {}",
+                path,
+                errors.join("\n  ")
+            );
+        }
+
+        Ok(Self {
+            rules: file.rules,
+            fallback: file.fallback,
+            profiles,
+        })
+    }
+
+    fn key(paths: &[String]) -> String {
+        paths.join("+")
+    }
+
+    /// Пути к профилям для устройства
+    pub fn select_paths(&self, device: &DeviceInfo) -> &[String] {
+        self.rules
+            .iter()
+            .find(|rule| rule.matches(device))
+            .map(|rule| rule.profile.as_slice())
+            .unwrap_or(&self.fallback)
+    }
+
+    /// Собранный профиль для устройства
+    pub fn select(&self, device: &DeviceInfo) -> &Profile {
+        let key = Self::key(self.select_paths(device));
+        tracing::debug!(
+            "Профиль для {} ({}): {}",
+            device.description,
+            device.device_type,
+            key
+        );
+        &self.profiles[&key]
+    }
+}
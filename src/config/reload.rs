@@ -0,0 +1,256 @@
+use anyhow::{Context, Result, anyhow};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, LazyLock, RwLock};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+use super::profile_selection::ProfileSelector;
+use super::settings::Settings;
+use super::settings_layers::SettingsLayers;
+
+/// Сколько ждать после первого события: редактор пишет файл в несколько приемов
+const DEBOUNCE: Duration = Duration::from_millis(300);
+
+/// Настройки и профили, с которыми работает опрос. Опрос берет снимок целиком в начале
+/// и работает с ним до конца, даже если за это время конфигурация перезагрузилась
+#[derive(Debug)]
+pub struct ConfigSnapshot {
+    /// Номер загрузки: 0 - при старте, дальше +1 на каждую успешную перезагрузку
+    pub version: u64,
+    pub settings: Settings,
+    /// None, если при старте профили не загрузились; появится после исправления файлов
+    selector: Option<ProfileSelector>,
+}
+
+impl ConfigSnapshot {
+    /// Загружает и проверяет настройки и все профили из правил выбора
+    pub fn load(layers: &SettingsLayers) -> Result<Self> {
+        let settings = layers.load()?;
+        let selector = ProfileSelector::load(&settings.profiles.selection)?;
+        Ok(Self {
+            version: 0,
+            settings,
+            selector: Some(selector),
+        })
+    }
+
+    /// Выбор профилей
+    pub fn selector(&self) -> Result<&ProfileSelector> {
+        self.selector
+            .as_ref()
+            .ok_or_else(|| anyhow!("Профили не загружены, исправьте файлы профилей"))
+    }
+}
+
+static GLOBAL_CONFIG: LazyLock<RwLock<Arc<ConfigSnapshot>>> = LazyLock::new(|| {
+    RwLock::new(Arc::new(ConfigSnapshot {
+        version: 0,
+        settings: Settings::default(),
+        selector: None,
+    }))
+});
+
+/// Текущий снимок конфигурации
+pub fn global() -> Arc<ConfigSnapshot> {
+    GLOBAL_CONFIG.read().unwrap().clone()
+}
+
+/// Заменяет снимок; опросы со старым снимком его не заметят
+fn install(mut snapshot: ConfigSnapshot) -> Arc<ConfigSnapshot> {
+    let mut current = GLOBAL_CONFIG.write().unwrap();
+    snapshot.version = current.version + 1;
+    let snapshot = Arc::new(snapshot);
+    *current = snapshot.clone();
+    snapshot
+}
+
+/// Загрузка при старте. Если файлы с ошибкой, сервер все равно запускается:
+/// с настройками по умолчанию (или из файла, если он в порядке) и без профилей
+pub fn load_and_install(layers: &SettingsLayers) {
+    match ConfigSnapshot::load(layers) {
+        Ok(snapshot) => {
+            tracing::info!(
+                "Конфигурация загружена: {}, профили по {}",
+                layers.path,
+                snapshot.settings.profiles.selection
+            );
+            *GLOBAL_CONFIG.write().unwrap() = Arc::new(snapshot);
+        }
+        Err(e) => {
+            tracing::error!("Конфигурация не загружена: {:#}", e);
+            let settings = layers.load().unwrap_or_default();
+            *GLOBAL_CONFIG.write().unwrap() = Arc::new(ConfigSnapshot {
+                version: 0,
+                settings,
+                selector: None,
+            });
+        }
+    }
+}
+
+/// Перезагружает конфигурацию. При ошибке остается прежний снимок
+pub fn reload(layers: &SettingsLayers) -> Result<Arc<ConfigSnapshot>> {
+    let snapshot = ConfigSnapshot::load(layers)
+        .context("Перезагрузка отменена, остается прежняя конфигурация")?;
+
+    let previous = global();
+    if previous.settings.mib.dirs != snapshot.settings.mib.dirs
+        || previous.settings.vendors.registry != snapshot.settings.vendors.registry
+        || previous.settings.vendors.enterprise_numbers
+            != snapshot.settings.vendors.enterprise_numbers
+        || previous.settings.vendors.enterprise_numbers_fallback
+            != snapshot.settings.vendors.enterprise_numbers_fallback
+    {
+        tracing::warn!("Каталоги MIB и реестр производителей применяются только после перезапуска");
+    }
+    if previous.settings.server.bind != snapshot.settings.server.bind
+        || previous.settings.logging.level != snapshot.settings.logging.level
+    {
+        tracing::warn!("server.bind и logging.level применяются только после перезапуска");
+    }
+
+    let snapshot = install(snapshot);
+    tracing::info!("Конфигурация перезагружена (версия {})", snapshot.version);
+    Ok(snapshot)
+}
+
+/// Перезагрузка при изменении файла настроек, каталога профилей или файла правил,
+/// а также по SIGHUP
+pub struct ConfigWatcher;
+
+impl ConfigWatcher {
+    /// Запускает слежение в фоне. Работает до завершения процесса
+    pub fn spawn(layers: SettingsLayers) {
+        tokio::spawn(async move {
+            if let Err(e) = Self::run(layers).await {
+                tracing::error!("Слежение за конфигурацией остановлено: {:#}", e);
+            }
+        });
+    }
+
+    async fn run(layers: SettingsLayers) -> Result<()> {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let fs_tx = tx.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            match event {
+                Ok(event) if !event.kind.is_access() => {
+                    let _ = fs_tx.send(event.paths);
+                }
+                Ok(_) => {}
+                Err(e) => tracing::warn!("Ошибка слежения за файлами: {}", e),
+            }
+        })
+        .context("Не удалось запустить слежение за файлами")?;
+
+        let mut watched = WatchSet::default();
+        watched.update(&mut watcher, &layers.path, &global().settings);
+
+        #[cfg(unix)]
+        {
+            use tokio::signal::unix::{SignalKind, signal};
+            let mut hangup =
+                signal(SignalKind::hangup()).context("Не удалось подписаться на SIGHUP")?;
+            let signal_tx = tx.clone();
+            tokio::spawn(async move {
+                while hangup.recv().await.is_some() {
+                    tracing::info!("SIGHUP: перезагрузка конфигурации");
+                    // Пустой список путей - перезагрузка без проверки файлов
+                    let _ = signal_tx.send(Vec::new());
+                }
+            });
+        }
+        drop(tx);
+
+        while let Some(paths) = rx.recv().await {
+            let mut relevant = paths.is_empty() || watched.is_relevant(&paths);
+
+            tokio::time::sleep(DEBOUNCE).await;
+            while let Ok(paths) = rx.try_recv() {
+                relevant |= paths.is_empty() || watched.is_relevant(&paths);
+            }
+            if !relevant {
+                continue;
+            }
+
+            match reload(&layers) {
+                Ok(snapshot) => watched.update(&mut watcher, &layers.path, &snapshot.settings),
+                Err(e) => tracing::error!("{:#}", e),
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Что сейчас под наблюдением. Файлы отслеживаются через их каталог:
+/// редакторы часто заменяют файл новым, и слежение за самим файлом теряется
+#[derive(Default)]
+struct WatchSet {
+    /// Каталоги и режим слежения
+    dirs: Vec<(PathBuf, RecursiveMode)>,
+    /// Каталог профилей, любые изменения в нем
+    profiles_dir: Option<PathBuf>,
+    /// Отдельные файлы: настройки и правила выбора
+    files: Vec<PathBuf>,
+}
+
+impl WatchSet {
+    /// Следит за путями из настроек; после перезагрузки каталог профилей мог смениться
+    fn update(&mut self, watcher: &mut RecommendedWatcher, settings_path: &str, settings: &Settings) {
+        let profiles_dir = absolute(Path::new(&settings.profiles.dir));
+        let files = vec![
+            absolute(Path::new(settings_path)),
+            absolute(Path::new(&settings.profiles.selection)),
+        ];
+
+        let mut dirs = vec![(profiles_dir.clone(), RecursiveMode::Recursive)];
+        for file in &files {
+            let parent = file.parent().map(Path::to_path_buf).unwrap_or_default();
+            if !dirs.iter().any(|(dir, _)| *dir == parent) {
+                dirs.push((parent, RecursiveMode::NonRecursive));
+            }
+        }
+
+        if dirs == self.dirs {
+            return;
+        }
+        for (dir, _) in &self.dirs {
+            let _ = watcher.unwatch(dir);
+        }
+        for (dir, mode) in &dirs {
+            match watcher.watch(dir, *mode) {
+                Ok(()) => tracing::debug!("Слежение за {}", dir.display()),
+                Err(e) => tracing::warn!("Не удалось следить за {}: {}", dir.display(), e),
+            }
+        }
+
+        self.dirs = dirs;
+        self.profiles_dir = Some(profiles_dir);
+        self.files = files;
+    }
+
+    fn is_relevant(&self, paths: &[PathBuf]) -> bool {
+        paths.iter().any(|path| {
+            self.files.contains(path)
+                || self
+                    .profiles_dir
+                    .as_ref()
+                    .is_some_and(|dir| path.starts_with(dir))
+        })
+    }
+}
+
+/// Абсолютный путь без обращения к файлу: файла может не быть, а события приходят
+/// с абсолютными путями
+fn absolute(path: &Path) -> PathBuf {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    match (path.parent(), path.file_name()) {
+        (Some(parent), Some(name)) => parent
+            .canonicalize()
+            .map(|parent| parent.join(name))
+            .unwrap_or(path),
+        _ => path,
+    }
+}
@@ -1,26 +1,84 @@
+use anyhow::{Result, bail};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use snmp2::v3::{AuthProtocol, Cipher};
 
-/// Базовые настройки приложения
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::profile::RowLimit;
+
+/// Базовые настройки приложения. В файле можно задать только часть полей,
+/// остальные берутся из значений по умолчанию
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct Settings {
+    /// HTTP сервер
+    pub server: ServerSettings,
+    /// Логирование
+    pub logging: LoggingSettings,
     /// Настройки подключения
     pub connection: ConnectionSettings,
     /// Настройки аутентификации
     pub auth: AuthSettings,
     /// Настройки сбора данных
     pub collection: CollectionSettings,
+    /// Каталоги с MIB модулями
+    pub mib: MibSettings,
+    /// Файлы реестра производителей
+    pub vendors: VendorSettings,
+    /// Выбор профилей по типу устройства
+    pub profiles: ProfileSettings,
+    /// Периодический опрос по расписанию
+    pub scheduler: SchedulerSettings,
+    /// Инвентарь устройств
+    pub inventory: InventorySettings,
+    /// Одновременный опрос нескольких устройств
+    pub fleet: FleetSettings,
+    /// Именованные учетные данные: на них ссылаются устройства инвентаря
+    pub credentials: BTreeMap<String, CredentialSettings>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSettings {
+    /// Адрес и порт HTTP сервера
+    pub bind: String,
+}
+
+impl Default for ServerSettings {
+    fn default() -> Self {
+        Self {
+            bind: "127.0.0.1:8000".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LoggingSettings {
+    /// Фильтр в формате RUST_LOG: "info", "hello_rust=debug,tower_http=warn"
+    pub level: String,
+}
+
+impl Default for LoggingSettings {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct ConnectionSettings {
+    /// Устройство по умолчанию (host:port)
+    pub target: String,
     /// Таймаут для SNMP операций (секунды)
     pub timeout: u64,
     /// Количество повторов при ошибках
     pub retries: u32,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct AuthSettings {
     /// Настройки SNMPv2c
     pub v2c: SnmpV2cSettings,
@@ -29,12 +87,14 @@ pub struct AuthSettings {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct SnmpV2cSettings {
     /// Community string
     pub community: String,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct SnmpV3Settings {
     /// Имя пользователя
     pub username: String,
@@ -45,42 +105,221 @@ pub struct SnmpV3Settings {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
 pub struct CollectionSettings {
     /// Собирать ли табличные данные (по умолчанию false)
     pub collect_tables: bool,
+    /// Лимит строк таблицы, если в профиле не задан свой max_rows
+    pub max_table_rows: RowLimit,
+    /// max-repetitions для GETBULK, если в профиле не задан свой
+    pub max_repetitions: u32,
+    /// Брать 64-битные HC счетчики из ifXTable, если агент их отдает
+    pub prefer_hc_counters: bool,
+    /// Перед сбором проверять, какие OID профиля есть на устройстве, и не опрашивать отсутствующие
+    pub probe_capabilities: bool,
+    /// Сколько секунд помнить результат проверки устройства
+    pub capabilities_ttl: u64,
+    /// Считать скорость счетчиков (в секунду) по предыдущему опросу устройства
+    pub counter_rates: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MibSettings {
+    /// Каталоги, из которых загружаются MIB файлы
+    pub dirs: Vec<String>,
+}
+
+impl Default for MibSettings {
+    fn default() -> Self {
+        Self {
+            dirs: vec!["./mibs".to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VendorSettings {
+    /// Правила по префиксам sysObjectID (YAML)
+    pub registry: String,
+    /// Полный список IANA Private Enterprise Numbers (scripts/update-enterprise-numbers.sh)
+    pub enterprise_numbers: String,
+    /// Выборка из репозитория, если полного списка нет
+    pub enterprise_numbers_fallback: String,
+}
+
+impl Default for VendorSettings {
+    fn default() -> Self {
+        Self {
+            registry: "./vendors/registry.yaml".to_string(),
+            enterprise_numbers: "./data/enterprise-numbers.txt".to_string(),
+            enterprise_numbers_fallback: "./vendors/enterprise-numbers.txt".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ProfileSettings {
+    /// Каталог профилей: его проверяет lint, из него читает API
+    pub dir: String,
+    /// Правила выбора профиля по определенному устройству
+    pub selection: String,
+}
+
+impl Default for ProfileSettings {
+    fn default() -> Self {
+        Self {
+            dir: "./profiles".to_string(),
+            selection: "./profiles/selection.yaml".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SchedulerSettings {
+    /// Запускать опрос по расписанию вместе с сервером (команда daemon включает его всегда)
+    pub enabled: bool,
+    /// Файл расписания: устройства, интервалы, группы метрик, выходы
+    pub file: String,
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: "./schedule.yaml".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InventorySettings {
+    /// Файл встроенной БД инвентаря
+    pub db: String,
+}
+
+impl Default for InventorySettings {
+    fn default() -> Self {
+        Self {
+            db: "./data/inventory.db".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FleetSettings {
+    /// Сколько устройств опрашивается одновременно
+    pub concurrency: usize,
+    /// Сколько устройств одной подсети опрашивается одновременно
+    pub per_subnet: usize,
+    /// Длина префикса подсети IPv4 и IPv6 для per_subnet
+    pub subnet_prefix_v4: u8,
+    pub subnet_prefix_v6: u8,
+    /// Предельное время опроса одного устройства, секунды
+    pub device_timeout: u64,
+}
+
+impl Default for FleetSettings {
+    fn default() -> Self {
+        Self {
+            concurrency: 32,
+            per_subnet: 4,
+            subnet_prefix_v4: 24,
+            subnet_prefix_v6: 64,
+            device_timeout: 60,
+        }
+    }
+}
+
+/// Учетные данные устройства. Не заданные поля берутся из auth
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CredentialSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub community: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub auth_password: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub privacy_password: Option<String>,
 }
 
 impl Default for CollectionSettings {
     fn default() -> Self {
         Self {
             collect_tables: false,
+            max_table_rows: RowLimit::Limited(1000),
+            max_repetitions: 10,
+            prefer_hc_counters: true,
+            probe_capabilities: true,
+            capabilities_ttl: 3600,
+            counter_rates: true,
+        }
+    }
+}
+
+impl Default for ConnectionSettings {
+    fn default() -> Self {
+        Self {
+            target: "127.0.0.1:161".to_string(),
+            timeout: 10,
+            retries: 2,
+        }
+    }
+}
+
+impl Default for SnmpV2cSettings {
+    fn default() -> Self {
+        Self {
+            community: "public".to_string(),
         }
     }
 }
 
-impl Default for Settings {
+impl Default for SnmpV3Settings {
     fn default() -> Self {
         Self {
-            connection: ConnectionSettings {
-                timeout: 10,
-                retries: 2,
-            },
-            auth: AuthSettings {
-                v2c: SnmpV2cSettings {
-                    community: "public".to_string(),
-                },
-                v3: SnmpV3Settings {
-                    username: "myuser".to_string(),
-                    auth_password: "myauthpass".to_string(),
-                    privacy_password: "myprivpass".to_string(),
-                },
-            },
-            collection: CollectionSettings::default(),
+            username: "myuser".to_string(),
+            auth_password: "myauthpass".to_string(),
+            privacy_password: "myprivpass".to_string(),
         }
     }
 }
 
 impl Settings {
+    /// Проверяет значения, с которыми сбор не сможет работать
+    pub fn validate(&self) -> Result<()> {
+        if self.connection.timeout == 0 {
+            bail!("connection.timeout должен быть больше 0");
+        }
+        if self.collection.max_repetitions == 0 {
+            bail!("collection.max_repetitions должен быть больше 0");
+        }
+        if !self.server.bind.contains(':') {
+            bail!(
+                "server.bind должен быть в виде адрес:порт, получено '{}'",
+                self.server.bind
+            );
+        }
+        if self.profiles.selection.is_empty() {
+            bail!("profiles.selection не задан");
+        }
+        let fleet = &self.fleet;
+        if fleet.concurrency == 0 || fleet.per_subnet == 0 || fleet.device_timeout == 0 {
+            bail!("fleet.concurrency, fleet.per_subnet и fleet.device_timeout должны быть больше 0");
+        }
+        if fleet.subnet_prefix_v4 > 32 || fleet.subnet_prefix_v6 > 128 {
+            bail!("fleet.subnet_prefix_v4 - до 32, fleet.subnet_prefix_v6 - до 128");
+        }
+        Ok(())
+    }
+
     /// Получает протокол аутентификации (всегда SHA1)
     pub fn get_auth_protocol(&self) -> AuthProtocol {
         AuthProtocol::Sha1
@@ -91,6 +330,11 @@ impl Settings {
         Cipher::Aes128
     }
 
+    /// Каталоги с MIB (SNMP_MIB_DIRS уже учтен при загрузке настроек)
+    pub fn mib_dirs(&self) -> Vec<String> {
+        self.mib.dirs.clone()
+    }
+
     /// Проверяет, нужно ли собирать табличные данные
     pub fn should_collect_tables(&self) -> bool {
         self.collection.collect_tables
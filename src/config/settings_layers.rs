@@ -0,0 +1,416 @@
+use anyhow::{Context, Result, bail};
+use serde_yml::{Mapping, Value};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+use super::settings::Settings;
+
+/// Файл настроек, если не задан ни --config, ни SNMP_SETTINGS
+pub const DEFAULT_SETTINGS_PATH: &str = "./settings.yaml";
+
+/// Переменные окружения и ключи настроек. Если для ключа задано несколько переменных,
+/// побеждает последняя в списке
+const ENV_KEYS: &[(&str, &str)] = &[
+    ("SNMP_BIND", "server.bind"),
+    ("RUST_LOG", "logging.level"),
+    ("SNMP_LOG_LEVEL", "logging.level"),
+    ("SNMP_TARGET", "connection.target"),
+    ("SNMP_TIMEOUT", "connection.timeout"),
+    ("SNMP_RETRIES", "connection.retries"),
+    ("SNMP_COMMUNITY", "auth.v2c.community"),
+    ("SNMP_USERNAME", "auth.v3.username"),
+    ("SNMP_AUTH_PASSWORD", "auth.v3.auth_password"),
+    ("SNMP_PRIVACY_PASSWORD", "auth.v3.privacy_password"),
+    ("SNMP_COLLECT_TABLES", "collection.collect_tables"),
+    ("SNMP_MAX_TABLE_ROWS", "collection.max_table_rows"),
+    ("SNMP_MAX_REPETITIONS", "collection.max_repetitions"),
+    ("SNMP_MIB_DIRS", "mib.dirs"),
+    ("SNMP_PROFILES_DIR", "profiles.dir"),
+    ("SNMP_PROFILE_SELECTION", "profiles.selection"),
+    ("SNMP_SCHEDULE", "scheduler.file"),
+    ("SNMP_INVENTORY_DB", "inventory.db"),
+    ("SNMP_FLEET_CONCURRENCY", "fleet.concurrency"),
+];
+
+/// Ключи с паролями: при выводе настроек заменяются на ***
+pub const SECRET_KEYS: &[&str] = &[
+    "auth.v2c.community",
+    "auth.v3.auth_password",
+    "auth.v3.privacy_password",
+];
+
+/// Ключи с паролями для этих настроек: SECRET_KEYS и заданные поля в credentials
+pub fn secret_keys(settings: &Settings) -> Vec<String> {
+    let mut keys: Vec<String> = SECRET_KEYS.iter().map(|key| key.to_string()).collect();
+    for (name, credential) in &settings.credentials {
+        let fields = [
+            ("community", &credential.community),
+            ("auth_password", &credential.auth_password),
+            ("privacy_password", &credential.privacy_password),
+        ];
+        for (field, value) in fields {
+            if value.is_some() {
+                keys.push(format!("credentials.{}.{}", name, field));
+            }
+        }
+    }
+    keys
+}
+
+/// Откуда взято значение настройки
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Layer {
+    Default,
+    File(String),
+    Env(&'static str),
+    Cli,
+}
+
+impl std::fmt::Display for Layer {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Layer::Default => write!(f, "по умолчанию"),
+            Layer::File(path) => write!(f, "файл {}", path),
+            Layer::Env(var) => write!(f, "env {}", var),
+            Layer::Cli => write!(f, "CLI"),
+        }
+    }
+}
+
+/// Настройки с источником каждого значения
+#[derive(Debug, Clone)]
+pub struct EffectiveSettings {
+    pub settings: Settings,
+    /// Ключ через точку -> откуда взято значение
+    pub sources: BTreeMap<String, Layer>,
+}
+
+/// Сборка настроек по слоям: значения по умолчанию, файл (YAML или TOML),
+/// переменные окружения, флаги командной строки. Каждый следующий слой переопределяет
+/// предыдущие. Слои перечитываются при каждой загрузке, в том числе при перезагрузке
+#[derive(Debug, Clone)]
+pub struct SettingsLayers {
+    /// Файл настроек
+    pub path: String,
+    /// Файл указан явно: тогда его отсутствие - ошибка
+    explicit: bool,
+    /// Переопределения из командной строки: ключ через точку и значение
+    cli: Vec<(String, String)>,
+}
+
+impl SettingsLayers {
+    /// path - из --config; без него SNMP_SETTINGS или ./settings.yaml
+    pub fn new(path: Option<String>, cli: Vec<(String, String)>) -> Self {
+        let (path, explicit) = match path.or_else(|| std::env::var("SNMP_SETTINGS").ok()) {
+            Some(path) => (path, true),
+            None => (DEFAULT_SETTINGS_PATH.to_string(), false),
+        };
+        Self {
+            path,
+            explicit,
+            cli,
+        }
+    }
+
+    /// Итоговые настройки
+    pub fn load(&self) -> Result<Settings> {
+        Ok(self.load_effective()?.settings)
+    }
+
+    /// Итоговые настройки и источник каждого значения
+    pub fn load_effective(&self) -> Result<EffectiveSettings> {
+        let file = self.read_file()?;
+        self.merge(file, |var| std::env::var(var).ok())
+    }
+
+    /// Накладывает слои на значения по умолчанию. env - значение переменной окружения
+    fn merge(
+        &self,
+        file: Option<Value>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<EffectiveSettings> {
+        let defaults = serde_yml::to_value(Settings::default())?;
+        let mut merged = defaults.clone();
+        let mut sources: BTreeMap<String, Layer> = leaves(&defaults)
+            .into_iter()
+            .map(|(key, _)| (key, Layer::Default))
+            .collect();
+
+        if let Some(file) = file {
+            for (key, value) in leaves(&file) {
+                // "server:" или "server: {}" - раздел не задан, а не заменен пустым
+                if value.is_null() || value.as_mapping().is_some_and(Mapping::is_empty) {
+                    continue;
+                }
+                set(&mut merged, &key, value);
+                sources.insert(key, Layer::File(self.path.clone()));
+            }
+        }
+
+        for (var, key) in ENV_KEYS {
+            let Some(raw) = env(var) else {
+                continue;
+            };
+            if raw.trim().is_empty() {
+                continue;
+            }
+            let value = parse_value(&defaults, key, &raw).context(format!("Переменная {}", var))?;
+            set(&mut merged, key, value);
+            sources.insert(key.to_string(), Layer::Env(var));
+        }
+
+        for (key, raw) in &self.cli {
+            let value = parse_value(&defaults, key, raw).context(format!("Флаг --set {}", key))?;
+            set(&mut merged, key, value);
+            sources.insert(key.clone(), Layer::Cli);
+        }
+
+        let settings: Settings = serde_yml::from_value(merged)
+            .context(format!("Не удалось разобрать настройки {}", self.path))?;
+        settings
+            .validate()
+            .context(format!("Настройки {}", self.path))?;
+
+        Ok(EffectiveSettings { settings, sources })
+    }
+
+    /// Файл настроек: .toml - TOML, остальное - YAML. Нет файла - None
+    fn read_file(&self) -> Result<Option<Value>> {
+        let path = Path::new(&self.path);
+        if !path.exists() {
+            if self.explicit {
+                bail!("Файл настроек не найден: {}", self.path);
+            }
+            tracing::debug!("Файл настроек {} не найден, настройки по умолчанию", self.path);
+            return Ok(None);
+        }
+
+        let content = std::fs::read_to_string(path)
+            .context(format!("Не удалось прочитать файл: {}", self.path))?;
+        let value: Value = if path.extension().is_some_and(|ext| ext == "toml") {
+            toml::from_str(&content)
+                .context(format!("Не удалось распарсить TOML {}", self.path))?
+        } else {
+            serde_yml::from_str(&content)
+                .context(format!("Не удалось распарсить YAML {}", self.path))?
+        };
+
+        match value {
+            Value::Null => Ok(None),
+            Value::Mapping(_) => Ok(Some(value)),
+            _ => bail!("Файл настроек {}: ожидается набор разделов", self.path),
+        }
+    }
+}
+
+/// Листья дерева значений: "connection.timeout" -> 10. Списки - целиком
+pub fn leaves(value: &Value) -> Vec<(String, Value)> {
+    fn walk(prefix: &str, value: &Value, out: &mut Vec<(String, Value)>) {
+        match value {
+            Value::Mapping(mapping) if !mapping.is_empty() => {
+                for (key, child) in mapping {
+                    let key = key.as_str().map(String::from).unwrap_or_else(|| {
+                        serde_yml::to_string(key).unwrap_or_default().trim().to_string()
+                    });
+                    let path = if prefix.is_empty() {
+                        key
+                    } else {
+                        format!("{}.{}", prefix, key)
+                    };
+                    walk(&path, child, out);
+                }
+            }
+            _ => out.push((prefix.to_string(), value.clone())),
+        }
+    }
+
+    let mut out = Vec::new();
+    walk("", value, &mut out);
+    out
+}
+
+/// Значение по ключу через точку
+pub fn get<'a>(value: &'a Value, key: &str) -> Option<&'a Value> {
+    key.split('.')
+        .try_fold(value, |value, part| value.as_mapping()?.get(part))
+}
+
+/// Записывает значение по ключу через точку, создавая промежуточные разделы
+pub fn set(root: &mut Value, key: &str, value: Value) {
+    let mut current = root;
+    for part in key.split('.') {
+        if !current.is_mapping() {
+            *current = Value::Mapping(Mapping::new());
+        }
+        let Value::Mapping(mapping) = current else {
+            unreachable!();
+        };
+        current = mapping
+            .entry(Value::String(part.to_string()))
+            .or_insert(Value::Null);
+    }
+    *current = value;
+}
+
+/// Значение из строки (env, --set) с типом по значению по умолчанию: строки остаются
+/// строками ("0123" в community), списки - через запятую или двоеточие, остальное - как YAML
+fn parse_value(defaults: &Value, key: &str, raw: &str) -> Result<Value> {
+    let Some(default) = get(defaults, key) else {
+        bail!("неизвестный ключ настроек '{}'", key);
+    };
+
+    let value = match default {
+        Value::String(_) => Value::String(raw.to_string()),
+        Value::Sequence(_) => Value::Sequence(
+            raw.split([',', ':'])
+                .map(str::trim)
+                .filter(|item| !item.is_empty())
+                .map(|item| Value::String(item.to_string()))
+                .collect(),
+        ),
+        Value::Mapping(_) => bail!("'{}' - раздел настроек, укажите ключ внутри него", key),
+        _ => serde_yml::from_str(raw).context(format!("'{}': не удалось разобрать '{}'", key, raw))?,
+    };
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn layers(cli: &[(&str, &str)]) -> SettingsLayers {
+        SettingsLayers {
+            path: "settings.yaml".to_string(),
+            explicit: false,
+            cli: cli
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+        }
+    }
+
+    /// Слои из YAML файла, переменных окружения и --set
+    fn merge(file: &str, env: &[(&str, &str)], cli: &[(&str, &str)]) -> Result<EffectiveSettings> {
+        let file = serde_yml::from_str(file).unwrap();
+        let env: HashMap<&str, &str> = env.iter().copied().collect();
+        layers(cli).merge(Some(file), |var| env.get(var).map(|v| v.to_string()))
+    }
+
+    #[test]
+    fn later_layers_win() {
+        let file = "
+connection:
+  timeout: 20
+  retries: 5
+logging:
+  level: debug
+";
+        let env = [
+            ("SNMP_TIMEOUT", "30"),
+            ("SNMP_BIND", "127.0.0.1:9000"),
+            ("RUST_LOG", "warn"),
+            ("SNMP_LOG_LEVEL", "trace"),
+        ];
+        let cli = [("connection.timeout", "40")];
+
+        let effective = merge(file, &env, &cli).unwrap();
+
+        let settings = &effective.settings;
+        assert_eq!(settings.connection.timeout, 40);
+        assert_eq!(settings.connection.retries, 5);
+        assert_eq!(settings.server.bind, "127.0.0.1:9000");
+        // Из двух переменных для одного ключа побеждает последняя в списке
+        assert_eq!(settings.logging.level, "trace");
+        assert_eq!(settings.auth.v2c.community, "public");
+
+        let sources = &effective.sources;
+        assert_eq!(sources["connection.timeout"], Layer::Cli);
+        assert_eq!(
+            sources["connection.retries"],
+            Layer::File("settings.yaml".to_string())
+        );
+        assert_eq!(sources["server.bind"], Layer::Env("SNMP_BIND"));
+        assert_eq!(sources["logging.level"], Layer::Env("SNMP_LOG_LEVEL"));
+        assert_eq!(sources["auth.v2c.community"], Layer::Default);
+    }
+
+    #[test]
+    fn env_and_cli_values_are_typed_by_defaults() {
+        let env = [("SNMP_COMMUNITY", "0123"), ("SNMP_TARGET", "")];
+        let cli = [("mib.dirs", "./a, ./b")];
+
+        let settings = merge("{}", &env, &cli).unwrap().settings;
+
+        assert_eq!(settings.auth.v2c.community, "0123");
+        assert_eq!(
+            settings.connection.target,
+            Settings::default().connection.target
+        );
+        assert_eq!(settings.mib.dirs, ["./a", "./b"]);
+
+        assert!(merge("{}", &[], &[("connection.nope", "1")]).is_err());
+        assert!(merge("{}", &[], &[("connection", "1")]).is_err());
+        assert!(merge("{}", &[("SNMP_TIMEOUT", "soon")], &[]).is_err());
+    }
+
+    #[test]
+    fn null_sections_keep_defaults() {
+        let file = "
+server:
+connection: null
+logging: {}
+fleet:
+  per_subnet: 8
+  concurrency: ~
+";
+
+        let effective = merge(file, &[], &[]).unwrap();
+
+        let defaults = Settings::default();
+        let settings = &effective.settings;
+        assert_eq!(settings.server.bind, defaults.server.bind);
+        assert_eq!(settings.connection.timeout, defaults.connection.timeout);
+        assert_eq!(settings.logging.level, defaults.logging.level);
+        assert_eq!(settings.fleet.concurrency, defaults.fleet.concurrency);
+        assert_eq!(settings.fleet.per_subnet, 8);
+        assert_eq!(effective.sources["server.bind"], Layer::Default);
+        assert_eq!(effective.sources["fleet.concurrency"], Layer::Default);
+    }
+
+    #[test]
+    fn secrets_are_masked() {
+        let file = "
+credentials:
+  core:
+    community: secret
+    username: admin
+  edge:
+    auth_password: hidden
+";
+        let settings = merge(file, &[], &[]).unwrap().settings;
+
+        let keys = secret_keys(&settings);
+
+        assert!(keys.contains(&"auth.v3.auth_password".to_string()));
+        assert!(keys.contains(&"credentials.core.community".to_string()));
+        assert!(keys.contains(&"credentials.edge.auth_password".to_string()));
+        // Не заданные поля не добавляются, иначе при выводе они появились бы как ***
+        assert!(!keys.contains(&"credentials.edge.community".to_string()));
+
+        let mut value = serde_yml::to_value(&settings).unwrap();
+        for key in &keys {
+            set(&mut value, key, "***".into());
+        }
+        assert_eq!(get(&value, "auth.v2c.community"), Some(&"***".into()));
+        assert_eq!(
+            get(&value, "credentials.core.community"),
+            Some(&"***".into())
+        );
+        assert_eq!(
+            get(&value, "credentials.core.username"),
+            Some(&"admin".into())
+        );
+        assert_eq!(get(&value, "credentials.edge.community"), None);
+    }
+}
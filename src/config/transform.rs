@@ -0,0 +1,293 @@
+use anyhow::{Context, Result, bail};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_yml::{Mapping, Value};
+use std::collections::BTreeMap;
+
+/// Шаг преобразования значения метрики. Шаги применяются по порядку к тексту значения
+/// (без префикса типа "OCTET STRING: ")
+///
+/// ```yaml
+/// scalars:
+///   firmware:
+///     oid: "SNMPv2-MIB::sysDescr.0"
+///     transform:
+///       - regex: "Version ([^ ,]+)"     # первая группа захвата, без групп - все совпадение
+///   serial:
+///     oid: "1.3.6.1.4.1.9999.1.1.0"
+///     transform: [hex, trim]            # "4A 4B 31 32" -> "JK12"
+///   status:
+///     oid: "1.3.6.1.4.1.9999.1.2.0"
+///     transform:
+///       - map: { "1": ok, "2": warning, "3": critical, "*": unknown }  # "*" - для остальных
+///   temperature:
+///     oid: "1.3.6.1.4.1.9999.1.3.0"
+///     transform:
+///       - split: { sep: " ", index: 0 } # "23.5 C" -> "23.5"; index -1 - последний кусок
+///       - number
+/// ```
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "TransformRaw", into = "TransformRaw")]
+pub enum Transform {
+    /// Извлечь по регулярному выражению
+    Regex(Pattern),
+    /// Заменить по таблице; нет ключа и нет "*" - значение без изменений
+    Map(BTreeMap<String, String>),
+    /// Hex-строка ("4A 4B", "4a:4b", "0x4a4b") в текст
+    Hex,
+    /// Убрать пробелы и нулевые байты по краям
+    Trim,
+    /// Разделить и взять кусок по номеру
+    Split { sep: String, index: i64 },
+    /// Привести к числу; дальше значение отдается числом
+    Number,
+}
+
+/// Регулярное выражение, компилируется при загрузке профиля
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct Pattern(Regex);
+
+impl Pattern {
+    pub fn is_match(&self, text: &str) -> bool {
+        self.0.is_match(text)
+    }
+
+    pub fn as_str(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl TryFrom<String> for Pattern {
+    type Error = anyhow::Error;
+
+    fn try_from(source: String) -> Result<Self> {
+        Regex::new(&source)
+            .map(Pattern)
+            .map_err(|e| anyhow::anyhow!("regex '{}': {}", source, e))
+    }
+}
+
+impl From<Pattern> for String {
+    fn from(pattern: Pattern) -> Self {
+        pattern.0.as_str().to_string()
+    }
+}
+
+/// Шаг в YAML: имя ("hex") или объект из одного ключа ({ regex: "..." })
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum TransformRaw {
+    Name(String),
+    Step(BTreeMap<String, Value>),
+}
+
+impl TryFrom<TransformRaw> for Transform {
+    type Error = anyhow::Error;
+
+    fn try_from(raw: TransformRaw) -> Result<Self> {
+        let (name, value) = match raw {
+            TransformRaw::Name(name) => (name, Value::Null),
+            TransformRaw::Step(step) if step.len() == 1 => step.into_iter().next().unwrap(),
+            TransformRaw::Step(_) => {
+                bail!("шаг преобразования - объект из одного ключа: {{ regex: ... }}")
+            }
+        };
+
+        Ok(match (name.as_str(), value) {
+            ("hex", Value::Null) => Transform::Hex,
+            ("trim", Value::Null) => Transform::Trim,
+            ("number", Value::Null) => Transform::Number,
+            ("regex", Value::String(source)) => Transform::Regex(Pattern::try_from(source)?),
+            ("map", Value::Mapping(mapping)) => Transform::Map(
+                mapping
+                    .into_iter()
+                    .map(|(key, value)| Ok((Self::scalar_text(key)?, Self::scalar_text(value)?)))
+                    .collect::<Result<_>>()?,
+            ),
+            ("split", Value::Mapping(mut mapping)) => {
+                let sep = match mapping.remove("sep") {
+                    Some(sep) => Self::scalar_text(sep)?,
+                    None => bail!("split: нужен sep"),
+                };
+                let index = match mapping.remove("index") {
+                    None => 0,
+                    Some(Value::Number(n)) if n.as_i64().is_some() => n.as_i64().unwrap_or(0),
+                    Some(_) => bail!("split: index - целое число"),
+                };
+                if let Some((key, _)) = mapping.into_iter().next() {
+                    bail!("split: неизвестное поле {:?}", key);
+                }
+                Transform::Split { sep, index }
+            }
+            ("hex" | "trim" | "number", _) => {
+                bail!("{}: шаг без параметров, пишется просто '{}'", name, name)
+            }
+            ("regex" | "map" | "split", _) => bail!("{}: неверные параметры", name),
+            _ => bail!(
+                "неизвестный шаг преобразования '{}': regex, map, hex, trim, split, number",
+                name
+            ),
+        })
+    }
+}
+
+impl From<Transform> for TransformRaw {
+    fn from(transform: Transform) -> Self {
+        let name = transform.name().to_string();
+        let value = match transform {
+            Transform::Hex | Transform::Trim | Transform::Number => {
+                return TransformRaw::Name(name);
+            }
+            Transform::Regex(Pattern(regex)) => Value::String(regex.as_str().to_string()),
+            Transform::Map(table) => Value::Mapping(
+                table
+                    .into_iter()
+                    .map(|(key, value)| (Value::String(key), Value::String(value)))
+                    .collect(),
+            ),
+            Transform::Split { sep, index } => {
+                let mut mapping = Mapping::new();
+                mapping.insert("sep".into(), Value::String(sep));
+                mapping.insert("index".into(), index.into());
+                Value::Mapping(mapping)
+            }
+        };
+        TransformRaw::Step(BTreeMap::from([(name, value)]))
+    }
+}
+
+impl Transform {
+    /// Применяет шаги к сырому значению varbind'а ("ТИП: значение").
+    /// Результат - "OCTET STRING: текст" или число, если последний шаг - number
+    pub fn pipeline(steps: &[Transform], raw: &str) -> Result<String> {
+        let mut value = Self::payload(raw).to_string();
+        for step in steps {
+            value = step
+                .apply(&value)
+                .context(format!("{} ('{}')", step.name(), value))?;
+        }
+
+        Ok(match steps.last() {
+            Some(Transform::Number) => value,
+            _ => format!("OCTET STRING: {}", value),
+        })
+    }
+
+    /// Один шаг над текстом значения
+    pub fn apply(&self, value: &str) -> Result<String> {
+        Ok(match self {
+            Transform::Regex(Pattern(regex)) => {
+                let Some(captures) = regex.captures(value) else {
+                    bail!("нет совпадения с '{}'", regex.as_str());
+                };
+                captures
+                    .get(1)
+                    .or_else(|| captures.get(0))
+                    .map(|matched| matched.as_str().to_string())
+                    .unwrap_or_default()
+            }
+            Transform::Map(table) => match table.get(value.trim()).or_else(|| table.get("*")) {
+                Some(mapped) => mapped.clone(),
+                None => value.to_string(),
+            },
+            Transform::Hex => Self::decode_hex(value)?,
+            Transform::Trim => value
+                .trim_matches(|c: char| c.is_whitespace() || c == '\0')
+                .to_string(),
+            Transform::Split { sep, index } => {
+                if sep.is_empty() {
+                    bail!("пустой разделитель");
+                }
+                let parts: Vec<&str> = value.split(sep.as_str()).collect();
+                let position = if *index < 0 {
+                    parts.len() as i64 + index
+                } else {
+                    *index
+                };
+                match usize::try_from(position).ok().and_then(|i| parts.get(i)) {
+                    Some(part) => part.to_string(),
+                    None => bail!("нет куска {} из {}", index, parts.len()),
+                }
+            }
+            Transform::Number => {
+                let text = value.trim();
+                match text.parse::<f64>() {
+                    Ok(number) if number.is_finite() => number.to_string(),
+                    _ => bail!("не число"),
+                }
+            }
+        })
+    }
+
+    /// Имя шага для сообщений
+    pub fn name(&self) -> &'static str {
+        match self {
+            Transform::Regex(_) => "regex",
+            Transform::Map(_) => "map",
+            Transform::Hex => "hex",
+            Transform::Trim => "trim",
+            Transform::Split { .. } => "split",
+            Transform::Number => "number",
+        }
+    }
+
+    /// Ключ или значение map как строка: "1", 1 и true равноценны
+    fn scalar_text(value: Value) -> Result<String> {
+        Ok(match value {
+            Value::String(text) => text,
+            Value::Number(n) => n.to_string(),
+            Value::Bool(b) => b.to_string(),
+            other => bail!("ожидается строка или число, получено {:?}", other),
+        })
+    }
+
+    /// Текст значения без префикса типа: "OCTET STRING: abc" -> "abc"
+    fn payload(raw: &str) -> &str {
+        match raw.split_once(": ") {
+            Some((kind, payload))
+                if !kind.is_empty()
+                    && kind
+                        .chars()
+                        .all(|c| c.is_ascii_uppercase() || c.is_ascii_digit() || c == ' ') =>
+            {
+                payload
+            }
+            _ => raw,
+        }
+    }
+
+    fn decode_hex(value: &str) -> Result<String> {
+        let value = value.trim();
+        let value = value
+            .strip_prefix("0x")
+            .or_else(|| value.strip_prefix("0X"))
+            .unwrap_or(value);
+        let digits: Vec<u8> = value
+            .bytes()
+            .filter(|b| !matches!(b, b' ' | b':' | b'-'))
+            .collect();
+        if !digits.len().is_multiple_of(2) {
+            bail!("нечетное число hex-цифр");
+        }
+
+        let bytes = digits
+            .chunks(2)
+            .map(|pair| {
+                let pair = std::str::from_utf8(pair).ok()?;
+                u8::from_str_radix(pair, 16).ok()
+            })
+            .collect::<Option<Vec<u8>>>()
+            .ok_or_else(|| anyhow::anyhow!("не hex-строка"))?;
+
+        Ok(String::from_utf8_lossy(&bytes)
+            .trim_end_matches('\0')
+            .to_string())
+    }
+}
@@ -1,14 +1,22 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
-use crate::collector::{MonitoringResult, ScalarResult, TableResult};
+use crate::collector::{
+    CounterRate, CounterSample, DerivedResult, DevicePoll, FleetEvent, FleetSummary, IndexComponent, MonitoringResult, PollStatus,
+    ScalarResult, TableResult, TableRow,
+};
+use crate::mib::{self, parse_numeric_oid};
+use crate::snmp::DeviceInfo;
+
+use super::metric::{MetricFormatter, MetricJson};
+use super::value_render::ValueRenderer;
+use crate::config::{MetricKind, MetricMeta};
 
 // TODO: Расширение JSON форматирования для интеграции:
 // - Добавить streaming JSON для очень больших результатов
 
 // TODO: Улучшение метаданных в JSON:
 // - Добавить информацию о производительности (время сбора, количество запросов)
-// - Добавить версию профиля устройства использованного для сбора
 
 // TODO: Оптимизация JSON структуры:
 // - Добавить опции для compact/verbose режимов
@@ -20,20 +28,65 @@ use crate::collector::{MonitoringResult, ScalarResult, TableResult};
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringResultJson {
     pub device_type: String,
+    /// Профиль, по которому собраны данные
+    pub profile: String,
     pub client_type: String,
     pub timestamp: String,
+    pub device: DeviceInfo,
     pub summary: ResultSummary,
     pub scalars: Vec<ScalarResultJson>,
     pub tables: Vec<TableResultJson>,
+    /// Производные метрики из скаляров
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub derived: Vec<DerivedResultJson>,
+    /// Записи профиля, которые не собирались, с причиной
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub skipped: Vec<SkippedJson>,
+    /// Результат проверки устройства перед сбором
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub capabilities: Option<CapabilitiesJson>,
     pub errors: Vec<ErrorInfo>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SkippedJson {
+    pub entry: String, // "scalars.имя" | "tables.имя" | "joins.имя" | "derived.имя"
+    pub reason: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CapabilitiesJson {
+    pub probed_at: String,
+    /// "SNMPv2c"/"SNMPv3" -> отвечает ли агент
+    pub versions: BTreeMap<String, bool>,
+    /// OID профиля, которых нет на устройстве
+    pub missing: Vec<String>,
+    /// Устройство не ответило при проверке, OID не проверены
+    #[serde(skip_serializing_if = "std::ops::Not::not", default)]
+    pub unreachable: bool,
+}
+
+/// Строка потока опроса нескольких устройств
+#[derive(Debug, Clone, Serialize)]
+pub struct DevicePollJson {
+    pub device: String,
+    pub target: String,
+    pub status: PollStatus,
+    pub started_at: String,
+    pub duration_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<MonitoringResultJson>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ResultSummary {
     pub total_scalars: usize,
     pub successful_scalars: usize,
     pub total_tables: usize,
     pub successful_tables: usize,
+    pub truncated_tables: usize,
     pub total_rows: usize,
 }
 
@@ -41,28 +94,93 @@ pub struct ResultSummary {
 pub struct ScalarResultJson {
     pub name: String,
     pub oid: String,
+    /// Имя из MIB: SNMPv2-MIB::sysUpTime.0
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mib_name: Option<String>,
     pub value: Option<String>,
+    /// Значение, отображенное по синтаксису из MIB: "up(1)", "00:1a:2b:3c:4d:5e", "3 days, 04:05:06.78"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+    /// Вид, единица и масштабированное значение (из профиля, иначе из MIB)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metric: Option<MetricJson>,
+    /// Скорость в секунду, если значение - счетчик
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<CounterRate>,
     pub status: String, // "success" | "error" | "timeout"
     pub error: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedResultJson {
+    pub name: String,
+    pub expr: String,
+    /// Вид, единица и значение с учетом scale
+    pub metric: MetricJson,
+    pub status: String, // "success" | "error"
+    pub error: Option<String>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableResultJson {
     pub name: String,
     pub oid: String,
     pub status: String, // "success" | "error" | "timeout"
     pub row_count: usize,
-    pub limited_to: Option<usize>,
+    pub varbind_count: usize,
+    pub limited_to: Option<usize>, // None - таблица обходилась без лимита
+    pub truncated: bool,           // true - таблица обрезана по limited_to, false - собрана полностью
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub sources: Vec<String>, // Для объединенных таблиц - исходные таблицы
     pub columns: HashMap<String, ColumnInfo>,
-    pub rows: Vec<RowData>,
+    /// Вычисляемые колонки: имя -> выражение и описание метрики
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub derived: BTreeMap<String, DerivedColumnInfo>,
+    pub rows: Vec<TableRowJson>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub varbinds: Option<Vec<RowData>>, // Сырой список varbind'ов, только по запросу
     pub error: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableRowJson {
+    pub index: String,
+    pub index_parts: Vec<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub index_fields: Option<Vec<IndexComponent>>, // Если индекс описан в профиле
+    pub values: BTreeMap<String, String>, // Имя колонки -> значение
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub display: BTreeMap<String, String>, // Имя колонки -> значение, отображенное по синтаксису из MIB
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub counters: BTreeMap<String, CounterSample>, // Счетчики интерфейса с разрядностью (32/64)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub metrics: BTreeMap<String, f64>, // Числовые колонки после масштабирования
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub rates: BTreeMap<String, CounterRate>, // Скорости счетчиков в секунду
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ColumnInfo {
     pub name: String,
     pub oid_pattern: String,
     pub value_count: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<MetricKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DerivedColumnInfo {
+    pub expr: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<MetricKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -74,11 +192,18 @@ pub struct RowData {
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ErrorInfo {
-    pub item_type: String, // "scalar" | "table"
+    pub item_type: String, // "scalar" | "table" | "derived"
     pub item_name: String,
     pub error_message: String,
 }
 
+/// Опции форматирования
+#[derive(Debug, Clone, Default)]
+pub struct FormatOptions {
+    /// Добавлять в таблицы сырой список varbind'ов
+    pub include_varbinds: bool,
+}
+
 /// JSON форматтер для результатов мониторинга
 pub struct JsonFormatter;
 
@@ -86,15 +211,24 @@ pub struct JsonFormatter;
 impl JsonFormatter {
     /// Конвертирует результат мониторинга в JSON
     pub fn format_monitoring_result(result: &MonitoringResult) -> MonitoringResultJson {
+        Self::format_monitoring_result_with(result, &FormatOptions::default())
+    }
+
+    /// Конвертирует результат мониторинга в JSON с опциями
+    pub fn format_monitoring_result_with(
+        result: &MonitoringResult,
+        options: &FormatOptions,
+    ) -> MonitoringResultJson {
         let timestamp = chrono::Utc::now().to_rfc3339();
 
         let successful_scalars = result.scalars.iter().filter(|s| s.error.is_none()).count();
-        let (successful_tables, total_rows) = match &result.tables {
+        let (successful_tables, truncated_tables, total_rows) = match &result.tables {
             Some(tables) => (
                 tables.iter().filter(|t| t.error.is_none()).count(),
+                tables.iter().filter(|t| t.truncated).count(),
                 tables.iter().map(|t| t.rows.len()).sum()
             ),
-            None => (0, 0)
+            None => (0, 0, 0)
         };
 
         let summary = ResultSummary {
@@ -102,36 +236,79 @@ impl JsonFormatter {
             successful_scalars,
             total_tables: result.tables.as_ref().map(|t| t.len()).unwrap_or(0),
             successful_tables,
+            truncated_tables,
             total_rows,
         };
 
         let scalars = result
             .scalars
             .iter()
-            .map(|s| Self::format_scalar(s))
+            .map(Self::format_scalar)
             .collect();
 
         let tables = match &result.tables {
             Some(tables) => tables
                 .iter()
-                .map(|t| Self::format_table(t))
+                .map(|t| Self::format_table(t, options))
                 .collect(),
             None => Vec::new(),
         };
 
+        let derived = result.derived.iter().map(Self::format_derived).collect();
+
+        let skipped = result
+            .skipped
+            .iter()
+            .map(|skipped| SkippedJson {
+                entry: skipped.entry.clone(),
+                reason: skipped.reason.clone(),
+            })
+            .collect();
+
+        let capabilities = result.capabilities.as_ref().map(|caps| CapabilitiesJson {
+            probed_at: caps.probed_at.to_rfc3339(),
+            versions: caps.versions.clone(),
+            missing: caps.missing().cloned().collect(),
+            unreachable: caps.unreachable,
+        });
+
         let errors = Self::extract_errors(result);
 
         MonitoringResultJson {
-            device_type: "auto_detected".to_string(), // Будет заполняться из device detection
+            device_type: result.device.device_type.clone(),
+            profile: result.profile.clone(),
             client_type: result.client_type.clone(),
             timestamp,
+            device: result.device.clone(),
             summary,
             scalars,
             tables,
+            derived,
+            skipped,
+            capabilities,
             errors,
         }
     }
 
+    /// Форматирует производную метрику для JSON
+    fn format_derived(derived: &DerivedResult) -> DerivedResultJson {
+        let value = derived.value.map(|value| value.to_string());
+        DerivedResultJson {
+            name: derived.name.clone(),
+            expr: derived.expr.clone(),
+            metric: MetricJson {
+                kind: derived.meta.kind,
+                unit: derived.meta.unit.clone(),
+                description: derived.meta.description.clone(),
+                value: value
+                    .as_deref()
+                    .and_then(|value| MetricFormatter::value(&derived.meta, value, None)),
+            },
+            status: if derived.error.is_none() { "success" } else { "error" }.to_string(),
+            error: derived.error.clone(),
+        }
+    }
+
     /// Форматирует скалярное значение для JSON
     fn format_scalar(scalar: &ScalarResult) -> ScalarResultJson {
         let status = match (&scalar.value, &scalar.error) {
@@ -141,139 +318,182 @@ impl JsonFormatter {
             _ => "unknown",
         };
 
+        let mib = mib::global();
+        let node = parse_numeric_oid(&scalar.oid)
+            .and_then(|oid| mib.resolve(&oid))
+            .map(|resolved| resolved.node);
+        let syntax = node.and_then(|node| node.syntax.as_ref());
+        let meta = scalar.meta.with_mib(node);
+
         ScalarResultJson {
             name: scalar.name.clone(),
             oid: scalar.oid.clone(),
+            mib_name: mib.format_name(&scalar.oid),
             value: scalar.value.clone(),
+            display: scalar
+                .value
+                .as_ref()
+                .map(|value| ValueRenderer::render(value, syntax, &mib)),
+            metric: MetricFormatter::scalar(&meta, scalar.value.as_deref()),
+            rate: scalar.rate.clone(),
             status: status.to_string(),
             error: scalar.error.clone(),
         }
     }
 
     /// Форматирует таблицу для JSON
-    fn format_table(table: &TableResult) -> TableResultJson {
+    fn format_table(table: &TableResult, options: &FormatOptions) -> TableResultJson {
         let status = match &table.error {
             None => "success",
             Some(error) if error.contains("TIMEOUT") => "timeout",
             Some(_) => "error",
         };
 
-        let (columns, rows) = Self::analyze_table_structure(&table.rows);
+        let (columns, rows) = Self::analyze_table_structure(table);
+        let derived = table
+            .derived
+            .iter()
+            .map(|(name, expr)| {
+                let meta = table.metrics.get(name).cloned().unwrap_or_default();
+                let info = DerivedColumnInfo {
+                    expr: expr.clone(),
+                    kind: meta.kind,
+                    unit: meta.unit,
+                    description: meta.description,
+                };
+                (name.clone(), info)
+            })
+            .collect();
+
+        let mib = mib::global();
+        let varbinds = options.include_varbinds.then(|| {
+            table
+                .varbinds
+                .iter()
+                .map(|(oid, value)| RowData {
+                    oid: oid.clone(),
+                    value: value.clone(),
+                    parsed_name: mib
+                        .format_name(oid)
+                        .or_else(|| Self::column_oid_name(table, oid)),
+                })
+                .collect()
+        });
 
         TableResultJson {
             name: table.name.clone(),
             oid: table.oid.clone(),
             status: status.to_string(),
             row_count: table.rows.len(),
+            varbind_count: table.varbinds.len(),
             limited_to: table.limited_to,
+            truncated: table.truncated,
+            sources: table.joined_from.clone(),
             columns,
+            derived,
             rows,
+            varbinds,
             error: table.error.clone(),
         }
     }
 
-    /// Анализирует структуру таблицы для JSON
-    fn analyze_table_structure(
-        rows: &[(String, String)],
-    ) -> (HashMap<String, ColumnInfo>, Vec<RowData>) {
-        let mut columns: HashMap<String, ColumnInfo> = HashMap::new();
-        let mut formatted_rows = Vec::new();
-
-        for (oid_str, value) in rows {
-            // Определяем колонку из OID
-            let column_oid = Self::extract_column_oid(oid_str);
-            let column_name = Self::get_column_name(&column_oid);
-
-            // Обновляем информацию о колонке
-            let column_info = columns.entry(column_oid.clone()).or_insert(ColumnInfo {
-                name: column_name,
-                oid_pattern: format!("{}.*", column_oid),
-                value_count: 0,
-            });
-            column_info.value_count += 1;
-
-            // Добавляем строку данных
-            formatted_rows.push(RowData {
-                oid: oid_str.clone(),
-                value: value.clone(),
-                parsed_name: Self::parse_oid_name(oid_str),
-            });
-        }
-
-        (columns, formatted_rows)
+    /// Имя varbind'а по колонкам таблицы, когда OID нет в MIB: "имя_колонки.индекс"
+    fn column_oid_name(table: &TableResult, oid: &str) -> Option<String> {
+        table.columns.iter().find_map(|column| {
+            let index = oid.strip_prefix(&column.oid)?.strip_prefix('.')?;
+            Some(format!("{}.{}", column.name, index))
+        })
     }
 
-    /// Извлекает OID колонки из полного OID
-    fn extract_column_oid(oid_str: &str) -> String {
-        let parts: Vec<&str> = oid_str.split('.').collect();
-
-        // Для ifTable: 1.3.6.1.2.1.2.2.1.X.INDEX -> 1.3.6.1.2.1.2.2.1.X
-        if parts.len() >= 10 && parts[0..9] == ["1", "3", "6", "1", "2", "1", "2", "2", "1"] {
-            return parts[0..10].join(".");
-        }
-
-        // Для hrStorageTable: 1.3.6.1.2.1.25.2.3.1.X.INDEX -> 1.3.6.1.2.1.25.2.3.1.X
-        if parts.len() >= 11 && parts[0..10] == ["1", "3", "6", "1", "2", "1", "25", "2", "3", "1"]
-        {
-            return parts[0..11].join(".");
+    /// Анализирует структуру таблицы для JSON
+    fn analyze_table_structure(
+        table: &TableResult,
+    ) -> (HashMap<String, ColumnInfo>, Vec<TableRowJson>) {
+        // Синтаксис и описание метрики колонок: профиль, затем MIB
+        let mib = mib::global();
+        let nodes: HashMap<&str, _> = table
+            .columns
+            .iter()
+            .filter_map(|column| {
+                let oid = parse_numeric_oid(&column.oid)?;
+                Some((column.name.as_str(), mib.node(&oid)?))
+            })
+            .collect();
+        let mut metas: HashMap<&str, MetricMeta> = table
+            .columns
+            .iter()
+            .map(|column| {
+                let declared = table.metrics.get(&column.name).cloned().unwrap_or_default();
+                let node = nodes.get(column.name.as_str()).copied();
+                (column.name.as_str(), declared.with_mib(node))
+            })
+            .collect();
+        for name in table.derived.keys() {
+            metas.insert(name, table.metrics.get(name).cloned().unwrap_or_default());
         }
 
-        // Для hrDeviceTable: 1.3.6.1.2.1.25.3.2.1.X.INDEX -> 1.3.6.1.2.1.25.3.2.1.X
-        if parts.len() >= 11 && parts[0..10] == ["1", "3", "6", "1", "2", "1", "25", "3", "2", "1"]
-        {
-            return parts[0..11].join(".");
-        }
+        let columns = table
+            .columns
+            .iter()
+            .map(|column| {
+                let meta = &metas[column.name.as_str()];
+                (
+                    column.oid.clone(),
+                    ColumnInfo {
+                        name: column.name.clone(),
+                        oid_pattern: format!("{}.*", column.oid),
+                        value_count: column.value_count,
+                        kind: meta.kind,
+                        unit: meta.unit.clone(),
+                        description: meta.description.clone(),
+                    },
+                )
+            })
+            .collect();
 
-        // Fallback - убираем последний компонент (индекс)
-        if parts.len() > 1 {
-            parts[0..parts.len() - 1].join(".")
-        } else {
-            oid_str.to_string()
-        }
-    }
+        let rows = table
+            .rows
+            .iter()
+            .map(|row| TableRowJson {
+                index: row.index.clone(),
+                index_parts: row.index_parts.clone(),
+                index_fields: row.index_fields.clone(),
+                values: row.values.clone(),
+                display: row
+                    .values
+                    .iter()
+                    .map(|(column, value)| {
+                        let syntax = nodes
+                            .get(column.as_str())
+                            .and_then(|node| node.syntax.as_ref());
+                        (column.clone(), ValueRenderer::render(value, syntax, &mib))
+                    })
+                    .collect(),
+                counters: row.counters.clone(),
+                metrics: Self::row_metrics(row, &metas),
+                rates: row.rates.clone(),
+            })
+            .collect();
 
-    /// Получает имя колонки по OID
-    fn get_column_name(column_oid: &str) -> String {
-        match column_oid {
-            // Interface Table columns
-            "1.3.6.1.2.1.2.2.1.1" => "ifIndex".to_string(),
-            "1.3.6.1.2.1.2.2.1.2" => "ifDescr".to_string(),
-            "1.3.6.1.2.1.2.2.1.3" => "ifType".to_string(),
-            "1.3.6.1.2.1.2.2.1.4" => "ifMtu".to_string(),
-            "1.3.6.1.2.1.2.2.1.5" => "ifSpeed".to_string(),
-            "1.3.6.1.2.1.2.2.1.7" => "ifAdminStatus".to_string(),
-            "1.3.6.1.2.1.2.2.1.8" => "ifOperStatus".to_string(),
-            "1.3.6.1.2.1.2.2.1.10" => "ifInOctets".to_string(),
-            "1.3.6.1.2.1.2.2.1.16" => "ifOutOctets".to_string(),
-            "1.3.6.1.2.1.2.2.1.13" => "ifInDiscards".to_string(),
-            "1.3.6.1.2.1.2.2.1.19" => "ifOutDiscards".to_string(),
-            "1.3.6.1.2.1.2.2.1.21" => "ifOutQLen".to_string(),
-
-            // Host Resources Storage Table
-            "1.3.6.1.2.1.25.2.3.1.1" => "hrStorageIndex".to_string(),
-            "1.3.6.1.2.1.25.2.3.1.2" => "hrStorageType".to_string(),
-            "1.3.6.1.2.1.25.2.3.1.3" => "hrStorageDescr".to_string(),
-            "1.3.6.1.2.1.25.2.3.1.5" => "hrStorageSize".to_string(),
-            "1.3.6.1.2.1.25.2.3.1.6" => "hrStorageUsed".to_string(),
-
-            // Host Resources Device Table
-            "1.3.6.1.2.1.25.3.2.1.1" => "hrDeviceIndex".to_string(),
-            "1.3.6.1.2.1.25.3.2.1.2" => "hrDeviceType".to_string(),
-            "1.3.6.1.2.1.25.3.2.1.3" => "hrDeviceDescr".to_string(),
-
-            _ => format!("column_{}", column_oid.replace(".", "_")),
-        }
+        (columns, rows)
     }
 
-    /// Парсит OID в читаемое имя с индексом
-    fn parse_oid_name(oid_str: &str) -> Option<String> {
-        let column_oid = Self::extract_column_oid(oid_str);
-        let column_name = Self::get_column_name(&column_oid);
-
-        // Извлекаем индекс
-        let index = oid_str.strip_prefix(&format!("{}.", column_oid))?;
-
-        Some(format!("{}.{}", column_name, index))
+    /// Числовые значения колонок строки. Для счетчиков берется выбранный (HC) счетчик
+    fn row_metrics(row: &TableRow, metas: &HashMap<&str, MetricMeta>) -> BTreeMap<String, f64> {
+        row.values
+            .iter()
+            .filter_map(|(column, value)| {
+                let meta = metas.get(column.as_str())?;
+                meta.kind?;
+                let raw = row
+                    .counters
+                    .get(column)
+                    .map(|sample| &sample.value)
+                    .unwrap_or(value);
+                let number = MetricFormatter::value(meta, raw, Some(&row.values))?;
+                Some((column.clone(), number))
+            })
+            .collect()
     }
 
     /// Извлекает ошибки из результата
@@ -291,6 +511,17 @@ impl JsonFormatter {
             }
         }
 
+        // Производные метрики, которые не удалось вычислить
+        for derived in &result.derived {
+            if let Some(ref error) = derived.error {
+                errors.push(ErrorInfo {
+                    item_type: "derived".to_string(),
+                    item_name: derived.name.clone(),
+                    error_message: error.clone(),
+                });
+            }
+        }
+
         // Ошибки таблиц
         if let Some(tables) = &result.tables {
             for table in tables {
@@ -307,17 +538,38 @@ impl JsonFormatter {
         errors
     }
 
-    /// Сериализует результат в JSON строку
-    pub fn to_json_string(result: &MonitoringResult) -> anyhow::Result<String> {
-        let json_result = Self::format_monitoring_result(result);
-        serde_json::to_string_pretty(&json_result)
-            .map_err(|e| anyhow::anyhow!("Ошибка сериализации в JSON: {}", e))
+    /// Итог опроса устройства для потока результатов
+    pub fn format_device_poll(poll: &DevicePoll, options: &FormatOptions) -> DevicePollJson {
+        DevicePollJson {
+            device: poll.device.clone(),
+            target: poll.target.clone(),
+            status: poll.status,
+            started_at: poll.started_at.to_rfc3339(),
+            duration_ms: poll.duration.as_millis() as u64,
+            error: poll.error.clone(),
+            result: poll
+                .result
+                .as_ref()
+                .map(|result| Self::format_monitoring_result_with(result, options)),
+        }
     }
 
-    /// Сериализует результат в компактный JSON
-    pub fn to_json_compact(result: &MonitoringResult) -> anyhow::Result<String> {
-        let json_result = Self::format_monitoring_result(result);
-        serde_json::to_string(&json_result)
-            .map_err(|e| anyhow::anyhow!("Ошибка сериализации в JSON: {}", e))
+    /// Событие опроса нескольких устройств строкой JSON Lines: результат устройства
+    /// или {"summary": ...} в конце
+    pub fn fleet_event_line(event: &FleetEvent, options: &FormatOptions) -> anyhow::Result<String> {
+        let line = match event {
+            FleetEvent::Device(poll) => {
+                serde_json::to_string(&Self::format_device_poll(poll, options))
+            }
+            FleetEvent::Finished(summary) => {
+                #[derive(Serialize)]
+                struct SummaryLine<'a> {
+                    summary: &'a FleetSummary,
+                }
+                serde_json::to_string(&SummaryLine { summary })
+            }
+        };
+        line.map_err(|e| anyhow::anyhow!("Ошибка сериализации в JSON: {}", e))
     }
 }
+
@@ -0,0 +1,59 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use super::value_render::ValueRenderer;
+use crate::config::{MetricKind, MetricMeta, Scale};
+
+/// Метрика в JSON: вид, единица и числовое значение с учетом множителя
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricJson {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub kind: Option<MetricKind>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Число после масштабирования; для info и нечисловых значений нет
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub value: Option<f64>,
+}
+
+/// Приведение значений к метрикам по описанию из профиля и MIB
+pub struct MetricFormatter;
+
+impl MetricFormatter {
+    /// Метрика скаляра. None, если ни профиль, ни MIB ничего о ней не говорят
+    pub fn scalar(meta: &MetricMeta, raw: Option<&str>) -> Option<MetricJson> {
+        if meta.is_empty() {
+            return None;
+        }
+        Some(MetricJson {
+            kind: meta.kind,
+            unit: meta.unit.clone(),
+            description: meta.description.clone(),
+            value: raw.and_then(|raw| Self::value(meta, raw, None)),
+        })
+    }
+
+    /// Числовое значение метрики. Множитель-колонка берется из той же строки таблицы
+    pub fn value(
+        meta: &MetricMeta,
+        raw: &str,
+        row: Option<&BTreeMap<String, String>>,
+    ) -> Option<f64> {
+        match meta.kind {
+            Some(MetricKind::Info) => return None,
+            // Номер из перечисления не масштабируется
+            Some(MetricKind::Enum) => return ValueRenderer::numeric(raw),
+            _ => {}
+        }
+
+        let value = ValueRenderer::numeric(raw)?;
+        let factor = match &meta.scale {
+            None => 1.0,
+            Some(Scale::Factor(factor)) => *factor,
+            Some(Scale::Column(column)) => ValueRenderer::numeric(row?.get(column)?)?,
+        };
+        Some(value * factor)
+    }
+}
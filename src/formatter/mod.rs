@@ -1,2 +1,6 @@
 pub mod json;
-pub use json::JsonFormatter;
+pub mod metric;
+pub mod value_render;
+
+pub use json::{FormatOptions, JsonFormatter};
+pub use value_render::ValueRenderer;
@@ -0,0 +1,316 @@
+use crate::collector::IndexDecoder;
+use crate::mib::{BaseType, MibTree, Syntax};
+
+/// Значение varbind'а, разобранное из строки "ТИП: значение"
+#[derive(Debug, Clone, PartialEq)]
+enum RawValue {
+    Integer(i64),
+    /// Counter32/Counter64/Gauge32/Unsigned32
+    Unsigned(u64),
+    Timeticks(u64),
+    Octets(Vec<u8>),
+    Oid(String),
+    IpAddress(String),
+    /// NULL, NO SUCH OBJECT и прочее - отдаем как есть
+    Other(String),
+}
+
+impl RawValue {
+    fn parse(raw: &str) -> Self {
+        let Some((kind, payload)) = raw.split_once(": ") else {
+            return RawValue::Other(raw.to_string());
+        };
+
+        let parsed = match kind {
+            "INTEGER" => payload.trim().parse().ok().map(RawValue::Integer),
+            "COUNTER32" | "COUNTER64" | "UNSIGNED32" | "GAUGE32" => {
+                payload.trim().parse().ok().map(RawValue::Unsigned)
+            }
+            "TIMETICKS" => payload.trim().parse().ok().map(RawValue::Timeticks),
+            "OCTET STRING" => Some(RawValue::Octets(payload.as_bytes().to_vec())),
+            "HEX STRING" => payload
+                .split_whitespace()
+                .map(|byte| u8::from_str_radix(byte, 16).ok())
+                .collect::<Option<Vec<u8>>>()
+                .map(RawValue::Octets),
+            "OBJECT IDENTIFIER" => Some(RawValue::Oid(payload.trim().to_string())),
+            "IP ADDRESS" => Some(RawValue::IpAddress(payload.trim().to_string())),
+            _ => None,
+        };
+
+        parsed.unwrap_or_else(|| RawValue::Other(raw.to_string()))
+    }
+}
+
+/// Отображение значений по синтаксису из MIB: перечисления, textual conventions, DISPLAY-HINT
+pub struct ValueRenderer;
+
+impl ValueRenderer {
+    /// Отображаемое значение. syntax - синтаксис объекта из MIB, если он известен
+    pub fn render(raw: &str, syntax: Option<&Syntax>, tree: &MibTree) -> String {
+        let value = RawValue::parse(raw);
+
+        if let Some(syntax) = syntax
+            && let Some(rendered) = Self::render_with_syntax(&value, syntax)
+        {
+            return rendered;
+        }
+
+        Self::render_plain(&value, tree)
+    }
+
+    fn render_with_syntax(value: &RawValue, syntax: &Syntax) -> Option<String> {
+        match value {
+            RawValue::Integer(n) if !syntax.enums.is_empty() => Some(match syntax.enum_label(*n) {
+                Some(label) => format!("{}({})", label, n),
+                None => n.to_string(),
+            }),
+            RawValue::Integer(n) => syntax
+                .display_hint
+                .as_deref()
+                .and_then(|hint| Self::integer_hint(*n as i128, hint)),
+            RawValue::Unsigned(n) => syntax
+                .display_hint
+                .as_deref()
+                .and_then(|hint| Self::integer_hint(*n as i128, hint)),
+            RawValue::Octets(bytes) => Self::render_octets(bytes, syntax),
+            _ => None,
+        }
+    }
+
+    fn render_octets(bytes: &[u8], syntax: &Syntax) -> Option<String> {
+        if syntax.is_tc("DateAndTime") {
+            return Self::format_date_and_time(bytes);
+        }
+        if syntax.is_tc("InetAddress") {
+            return Some(IndexDecoder::format_inet_address(bytes, None));
+        }
+        if syntax.is_tc("MacAddress") || syntax.is_tc("PhysAddress") {
+            return Some(IndexDecoder::format_mac(bytes));
+        }
+        if syntax.base == BaseType::Bits {
+            return Some(Self::format_bits(bytes, syntax));
+        }
+        if syntax.base == BaseType::IpAddress && bytes.len() == 4 {
+            return Some(IndexDecoder::format_ipv4(bytes));
+        }
+        syntax
+            .display_hint
+            .as_deref()
+            .and_then(|hint| Self::octet_hint(bytes, hint))
+    }
+
+    /// Числовое значение varbind'а: целые, счетчики, TimeTicks и строки с числом ("23.5").
+    /// Значение без типа (вычисляемые колонки) - просто число
+    pub fn numeric(raw: &str) -> Option<f64> {
+        match RawValue::parse(raw) {
+            RawValue::Integer(n) => Some(n as f64),
+            RawValue::Unsigned(n) | RawValue::Timeticks(n) => Some(n as f64),
+            RawValue::Octets(bytes) => std::str::from_utf8(&bytes).ok()?.trim().parse().ok(),
+            RawValue::Other(text) => text.trim().parse().ok(),
+            _ => None,
+        }
+    }
+
+    /// Отображение без MIB: по типу из самого значения
+    fn render_plain(value: &RawValue, tree: &MibTree) -> String {
+        match value {
+            RawValue::Integer(n) => n.to_string(),
+            RawValue::Unsigned(n) => n.to_string(),
+            RawValue::Timeticks(n) => Self::format_duration(*n),
+            RawValue::Octets(bytes) => match std::str::from_utf8(bytes) {
+                Ok(text) => text.trim_end_matches('\0').to_string(),
+                Err(_) => IndexDecoder::format_mac(bytes),
+            },
+            RawValue::Oid(oid) => tree.format_name(oid).unwrap_or_else(|| oid.clone()),
+            RawValue::IpAddress(address) => address.clone(),
+            RawValue::Other(raw) => raw.clone(),
+        }
+    }
+
+    /// Сотые доли секунды -> "3 days, 04:05:06.78"
+    fn format_duration(ticks: u64) -> String {
+        let centis = ticks % 100;
+        let seconds = ticks / 100;
+        let (days, hours, minutes, secs) = (
+            seconds / 86_400,
+            seconds % 86_400 / 3600,
+            seconds % 3600 / 60,
+            seconds % 60,
+        );
+        let time = format!("{:02}:{:02}:{:02}.{:02}", hours, minutes, secs, centis);
+        match days {
+            0 => time,
+            1 => format!("1 day, {}", time),
+            _ => format!("{} days, {}", days, time),
+        }
+    }
+
+    /// DateAndTime (SNMPv2-TC): 8 или 11 байт -> "2024-01-15 10:30:00.0 +03:00"
+    fn format_date_and_time(bytes: &[u8]) -> Option<String> {
+        if bytes.len() != 8 && bytes.len() != 11 {
+            return None;
+        }
+        let year = u16::from_be_bytes([bytes[0], bytes[1]]);
+        let mut text = format!(
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{}",
+            year, bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7]
+        );
+        if bytes.len() == 11 {
+            text.push_str(&format!(
+                " {}{:02}:{:02}",
+                bytes[8] as char, bytes[9], bytes[10]
+            ));
+        }
+        Some(text)
+    }
+
+    /// BITS: старший бит первого байта - бит 0
+    fn format_bits(bytes: &[u8], syntax: &Syntax) -> String {
+        let set: Vec<String> = (0..bytes.len() * 8)
+            .filter(|bit| bytes[bit / 8] & (0x80 >> (bit % 8)) != 0)
+            .map(|bit| match syntax.enum_label(bit as i64) {
+                Some(label) => format!("{}({})", label, bit),
+                None => bit.to_string(),
+            })
+            .collect();
+        set.join(", ")
+    }
+
+    /// DISPLAY-HINT для целых: "d-2" -> 1234 = "12.34", "x" - hex, "o" - восьмеричное, "b" - двоичное
+    fn integer_hint(n: i128, hint: &str) -> Option<String> {
+        let mut chars = hint.chars();
+        match chars.next()? {
+            'd' => {
+                let decimals: usize = match chars.as_str().strip_prefix('-') {
+                    Some(digits) => digits.parse().ok()?,
+                    None => 0,
+                };
+                if decimals == 0 {
+                    return Some(n.to_string());
+                }
+                let digits = format!("{:0width$}", n.unsigned_abs(), width = decimals + 1);
+                let (int, frac) = digits.split_at(digits.len() - decimals);
+                let sign = if n < 0 { "-" } else { "" };
+                Some(format!("{}{}.{}", sign, int, frac))
+            }
+            'x' => Some(format!("{:x}", n)),
+            'o' => Some(format!("{:o}", n)),
+            'b' => Some(format!("{:b}", n)),
+            _ => None,
+        }
+    }
+
+    /// DISPLAY-HINT для OCTET STRING (RFC 2579): "255a", "1x:", "1d.1d.1d.1d", "2x:2x"
+    fn octet_hint(bytes: &[u8], hint: &str) -> Option<String> {
+        let specs = Self::parse_octet_hint(hint)?;
+        let mut out = String::new();
+        let mut rest = bytes;
+        let mut spec_index = 0;
+
+        while !rest.is_empty() {
+            // Последняя спецификация применяется ко всем оставшимся байтам
+            let spec = &specs[spec_index.min(specs.len() - 1)];
+            spec_index += 1;
+
+            let repeat = if spec.repeat {
+                let count = rest[0] as usize;
+                rest = &rest[1..];
+                count
+            } else {
+                1
+            };
+
+            for i in 0..repeat {
+                if rest.is_empty() {
+                    break;
+                }
+                let take = spec.length.min(rest.len());
+                let (chunk, tail) = rest.split_at(take);
+                rest = tail;
+
+                match spec.format {
+                    'a' | 't' => out.push_str(&String::from_utf8_lossy(chunk)),
+                    _ => {
+                        let n = chunk.iter().fold(0u128, |acc, b| (acc << 8) | *b as u128);
+                        out.push_str(&match spec.format {
+                            'd' => n.to_string(),
+                            'x' => format!("{:0width$x}", n, width = take * 2),
+                            'o' => format!("{:o}", n),
+                            _ => return None,
+                        });
+                    }
+                }
+
+                let last_in_group = i + 1 == repeat;
+                if !rest.is_empty() {
+                    match (last_in_group, spec.terminator, spec.separator) {
+                        (true, Some(terminator), _) => out.push(terminator),
+                        (_, _, Some(separator)) => out.push(separator),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Some(out.trim_end_matches('\0').to_string())
+    }
+
+    fn parse_octet_hint(hint: &str) -> Option<Vec<HintSpec>> {
+        let chars: Vec<char> = hint.chars().collect();
+        let mut specs = Vec::new();
+        let mut pos = 0;
+
+        while pos < chars.len() {
+            let repeat = chars[pos] == '*';
+            if repeat {
+                pos += 1;
+            }
+            let start = pos;
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            let length: usize = chars[start..pos].iter().collect::<String>().parse().ok()?;
+            let format = *chars.get(pos)?;
+            if !matches!(format, 'a' | 't' | 'd' | 'x' | 'o') {
+                return None;
+            }
+            pos += 1;
+
+            let is_separator =
+                |c: &char| !c.is_ascii_digit() && *c != '*' && !matches!(c, 'a' | 't' | 'd' | 'x' | 'o');
+            let separator = chars.get(pos).filter(|c| is_separator(c)).copied();
+            if separator.is_some() {
+                pos += 1;
+            }
+            let terminator = if repeat {
+                let terminator = chars.get(pos).filter(|c| is_separator(c)).copied();
+                if terminator.is_some() {
+                    pos += 1;
+                }
+                terminator
+            } else {
+                None
+            };
+
+            specs.push(HintSpec {
+                repeat,
+                length: length.max(1),
+                format,
+                separator,
+                terminator,
+            });
+        }
+
+        (!specs.is_empty()).then_some(specs)
+    }
+}
+
+/// Одна спецификация DISPLAY-HINT: [*]длина формат [разделитель] [терминатор]
+struct HintSpec {
+    repeat: bool,
+    length: usize,
+    format: char,
+    separator: Option<char>,
+    terminator: Option<char>,
+}
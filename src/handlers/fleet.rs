@@ -0,0 +1,41 @@
+use axum::{
+    Json,
+    body::Body,
+    http::{StatusCode, header},
+    response::IntoResponse,
+};
+use std::convert::Infallible;
+use tokio_stream::{StreamExt, wrappers::ReceiverStream};
+
+use super::inventory::blocking;
+use crate::collector::FleetCollector;
+use crate::formatter::{FormatOptions, JsonFormatter};
+use crate::models::FleetRequest;
+
+/// Опрос устройств инвентаря. Ответ - JSON Lines: строка на каждое устройство по мере
+/// готовности и {"summary": ...} в конце. Закрытое соединение отменяет опрос
+pub async fn collect_fleet(
+    Json(request): Json<FleetRequest>,
+) -> Result<impl IntoResponse, (StatusCode, String)> {
+    let (names, tag) = (request.names, request.tag);
+    let devices = blocking(move |inventory| inventory.select(&names, tag.as_deref()))
+        .await?
+        .map_err(|e| (StatusCode::NOT_FOUND, format!("{:#}", e)))?;
+
+    let options = FormatOptions {
+        include_varbinds: request.include_varbinds,
+    };
+    let events = ReceiverStream::new(FleetCollector::spawn(devices));
+    let lines = events.map(move |event| {
+        let line = JsonFormatter::fleet_event_line(&event, &options).unwrap_or_else(|e| {
+            tracing::error!("{:#}", e);
+            serde_json::json!({ "error": e.to_string() }).to_string()
+        });
+        Ok::<_, Infallible>(line + "\n")
+    });
+
+    Ok((
+        [(header::CONTENT_TYPE, "application/x-ndjson")],
+        Body::from_stream(lines),
+    ))
+}
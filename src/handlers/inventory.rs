@@ -0,0 +1,156 @@
+use axum::{
+    Json,
+    extract::{Path, Query},
+    http::StatusCode,
+};
+use std::sync::Arc;
+
+use crate::collector::{CapabilityCache, CounterRates};
+use crate::inventory::{self, Device, ImportReport, Inventory, InventoryImport};
+use crate::models::{InventoryImportRequest, InventoryQuery};
+
+type ApiError = (StatusCode, String);
+
+/// Устройства инвентаря, с ?tag= - только с этим тегом
+pub async fn list_devices(
+    Query(query): Query<InventoryQuery>,
+) -> Result<Json<Vec<Device>>, ApiError> {
+    let mut devices = blocking(|inventory| inventory.list())
+        .await?
+        .map_err(internal)?;
+    if let Some(tag) = &query.tag {
+        devices.retain(|device| device.has_tag(tag));
+    }
+    Ok(Json(devices))
+}
+
+pub async fn get_device(Path(name): Path<String>) -> Result<Json<Device>, ApiError> {
+    let found = blocking({
+        let name = name.clone();
+        move |inventory| inventory.get(&name)
+    })
+    .await?
+    .map_err(internal)?;
+    match found {
+        Some(device) => Ok(Json(device)),
+        None => Err(not_found(&name)),
+    }
+}
+
+/// Новое устройство; 409, если имя занято
+pub async fn create_device(
+    Json(device): Json<Device>,
+) -> Result<(StatusCode, Json<Device>), ApiError> {
+    device
+        .validate(opened()?.profiles_dir())
+        .map_err(bad_request)?;
+    let created = blocking({
+        let device = device.clone();
+        move |inventory| inventory.create(&device)
+    })
+    .await?
+    .map_err(internal)?;
+    if !created {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("Устройство '{}' уже есть", device.name),
+        ));
+    }
+    Ok((StatusCode::CREATED, Json(device)))
+}
+
+/// Замена устройства целиком (или добавление под этим именем)
+pub async fn update_device(
+    Path(name): Path<String>,
+    Json(device): Json<Device>,
+) -> Result<Json<Device>, ApiError> {
+    if device.name != name {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!("Имя в теле '{}' не совпадает с '{}'", device.name, name),
+        ));
+    }
+    device
+        .validate(opened()?.profiles_dir())
+        .map_err(bad_request)?;
+    let previous = blocking({
+        let device = device.clone();
+        move |inventory| inventory.upsert(&device)
+    })
+    .await?
+    .map_err(internal)?;
+    // Состояние хранится по адресу: со сменой адреса или порта старое не нужно
+    if let Some(previous) = previous
+        && previous.target() != device.target()
+    {
+        forget(&previous);
+    }
+    Ok(Json(device))
+}
+
+pub async fn delete_device(Path(name): Path<String>) -> Result<StatusCode, ApiError> {
+    let removed = blocking({
+        let name = name.clone();
+        move |inventory| inventory.remove(&name)
+    })
+    .await?
+    .map_err(internal)?;
+    match removed {
+        Some(removed) => {
+            forget(&removed);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        None => Err(not_found(&name)),
+    }
+}
+
+/// Загрузка устройств из YAML или CSV одной транзакцией
+pub async fn import_devices(
+    Json(request): Json<InventoryImportRequest>,
+) -> Result<Json<ImportReport>, ApiError> {
+    let devices =
+        InventoryImport::parse(&request.content, request.format, opened()?.profiles_dir())
+            .map_err(bad_request)?;
+    let report = blocking(move |inventory| inventory.import(&devices, request.replace))
+        .await?
+        .map_err(internal)?;
+    Ok(Json(report))
+}
+
+/// Вызов инвентаря в пуле blocking потоков: транзакции redb синхронные и пишут на диск
+pub(super) async fn blocking<T: Send + 'static>(
+    call: impl FnOnce(&Inventory) -> anyhow::Result<T> + Send + 'static,
+) -> Result<anyhow::Result<T>, ApiError> {
+    let inventory = opened()?;
+    tokio::task::spawn_blocking(move || call(&inventory))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Прошлые значения счетчиков и возможности устройства больше не нужны
+fn forget(device: &Device) {
+    CounterRates::forget(&device.target());
+    CapabilityCache::invalidate(&device.target());
+}
+
+fn opened() -> Result<Arc<Inventory>, ApiError> {
+    inventory::global().ok_or((
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Инвентарь не открыт, см. лог сервера".to_string(),
+    ))
+}
+
+fn not_found(name: &str) -> ApiError {
+    (
+        StatusCode::NOT_FOUND,
+        format!("Устройство не найдено: {}", name),
+    )
+}
+
+fn bad_request(e: anyhow::Error) -> ApiError {
+    (StatusCode::BAD_REQUEST, format!("{:#}", e))
+}
+
+fn internal(e: anyhow::Error) -> ApiError {
+    (StatusCode::INTERNAL_SERVER_ERROR, format!("{:#}", e))
+}
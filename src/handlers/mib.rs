@@ -0,0 +1,74 @@
+use axum::{Json, extract::Query, http::StatusCode};
+
+use crate::mib::{self, format_oid};
+use crate::models::{
+    NodeQuery, NodeResponse, NodeSummary, SearchQuery, TranslateQuery, TranslateResponse,
+};
+use crate::snmp::parse_oid;
+
+const SEARCH_LIMIT: usize = 50;
+/// Узел, с которого начинается просмотр дерева
+const ROOT_OID: &str = "1";
+
+/// Перевод OID <-> имя: принимает числовой OID или имя MIB
+pub async fn mib_translate(
+    Query(query): Query<TranslateQuery>,
+) -> Result<Json<TranslateResponse>, (StatusCode, String)> {
+    let tree = mib::global();
+    let oid = tree
+        .parse_name(&query.oid)
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+
+    // OID должен быть пригоден для SNMP запроса
+    let oid_str = format_oid(&oid);
+    parse_oid(&oid_str).map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
+
+    let resolved = tree.resolve(&oid);
+    Ok(Json(TranslateResponse {
+        input: query.oid,
+        name: resolved.as_ref().map(|r| r.to_string()),
+        object: resolved
+            .as_ref()
+            .map(|r| NodeSummary::new(r.node, &tree)),
+        index: resolved
+            .as_ref()
+            .filter(|r| !r.suffix.is_empty())
+            .map(|r| format_oid(&r.suffix)),
+        oid: oid_str,
+    }))
+}
+
+/// Узел дерева MIB с потомками. Без oid - iso(1)
+pub async fn mib_node(
+    Query(query): Query<NodeQuery>,
+) -> Result<Json<NodeResponse>, (StatusCode, String)> {
+    let tree = mib::global();
+    let text = query.oid.unwrap_or_else(|| ROOT_OID.to_string());
+    let oid = tree
+        .parse_name(&text)
+        .map_err(|e| (StatusCode::NOT_FOUND, e.to_string()))?;
+    let node = tree.node(&oid).ok_or_else(|| {
+        let nearest = tree
+            .resolve(&oid)
+            .map(|r| format!(" (ближайший объект: {})", r))
+            .unwrap_or_default();
+        (
+            StatusCode::NOT_FOUND,
+            format!("Узел {} не найден в MIB{}", format_oid(&oid), nearest),
+        )
+    })?;
+
+    Ok(Json(NodeResponse::new(node, &tree)))
+}
+
+/// Поиск объектов по началу имени (для подсказок при составлении профиля)
+pub async fn mib_search(Query(query): Query<SearchQuery>) -> Json<Vec<NodeSummary>> {
+    let tree = mib::global();
+    let limit = query.limit.unwrap_or(SEARCH_LIMIT).min(SEARCH_LIMIT);
+    Json(
+        tree.search(query.q.trim(), limit)
+            .into_iter()
+            .map(|node| NodeSummary::new(node, &tree))
+            .collect(),
+    )
+}
@@ -1,5 +1,15 @@
+    pub mod fleet;
     pub mod health;
+    pub mod inventory;
+    pub mod mib;
+    pub mod profiles;
+    pub mod scheduler;
     pub mod snmp;
     
+    pub use fleet::collect_fleet;
     pub use health::health;
-    pub use snmp::handle_snmpv2c;
\ No newline at end of file
+    pub use inventory::{create_device, delete_device, get_device, import_devices, list_devices, update_device};
+    pub use mib::{mib_node, mib_search, mib_translate};
+    pub use profiles::lint_profiles;
+    pub use scheduler::scheduler_status;
+    pub use snmp::handle_snmpv2c;
@@ -0,0 +1,63 @@
+use axum::{Json, http::StatusCode};
+use std::path::{Path, PathBuf};
+
+use crate::config::{LintReport, ProfileLinter, reload};
+use crate::models::LintRequest;
+
+/// Имя для присланного текста профиля без path
+const INLINE_PROFILE: &str = "inline.yaml";
+
+/// Проверка профилей. Без path и content - все профили каталога
+pub async fn lint_profiles(
+    Json(request): Json<LintRequest>,
+) -> Result<Json<Vec<LintReport>>, (StatusCode, String)> {
+    let config = reload::global();
+    let settings = &config.settings;
+    let dir = Path::new(&settings.profiles.dir);
+
+    let reports = match (request.path, request.content) {
+        (None, None) => {
+            let exclude = [PathBuf::from(&settings.profiles.selection)];
+            ProfileLinter::lint_paths(&[dir.to_path_buf()], &exclude)
+        }
+        (Some(path), None) => {
+            let path = profile_path(dir, &path)?;
+            if !path.is_file() {
+                return Err((
+                    StatusCode::NOT_FOUND,
+                    format!("Профиль не найден: {}", path.display()),
+                ));
+            }
+            vec![ProfileLinter::lint_file(&path.display().to_string())]
+        }
+        (path, Some(content)) => {
+            let path = profile_path(dir, path.as_deref().unwrap_or(INLINE_PROFILE))?;
+            vec![ProfileLinter::lint_source_within(
+                dir,
+                &path.display().to_string(),
+                &content,
+            )]
+        }
+    };
+
+    Ok(Json(reports))
+}
+
+/// Путь внутри каталога профилей; выйти за его пределы нельзя
+fn profile_path(dir: &Path, path: &str) -> Result<PathBuf, (StatusCode, String)> {
+    let path = Path::new(path);
+    let forbidden = path.is_absolute()
+        || path
+            .components()
+            .any(|component| matches!(component, std::path::Component::ParentDir));
+    if forbidden {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Путь должен быть относительным и внутри каталога профилей: {}",
+                path.display()
+            ),
+        ));
+    }
+    Ok(dir.join(path))
+}
@@ -0,0 +1,8 @@
+use axum::Json;
+
+use crate::scheduler::{JobStatus, SchedulerStatus};
+
+/// Состояние задач периодического опроса. Пусто, если планировщик не запущен
+pub async fn scheduler_status() -> Json<Vec<JobStatus>> {
+    Json(SchedulerStatus::snapshot())
+}
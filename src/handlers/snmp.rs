@@ -1,7 +1,7 @@
 use axum::{Json, http::StatusCode, response::IntoResponse};
 use tokio::time::{timeout, Duration};
 
-use crate::models::snmpv2c::Snmpv2c;
+use crate::models::Snmpv2c;
 use crate::snmp::{create_v2c_client, parse_oid};
 
 const SNMP_TIMEOUT_SECS: u64 = 10;
@@ -15,7 +15,7 @@ pub async fn handle_snmpv2c(Json(params): Json<Snmpv2c>) -> Result<impl IntoResp
         .map_err(|e| (StatusCode::BAD_REQUEST, e.to_string()))?;
 
     let work = async {
-        let mut client = create_v2c_client(&target, &params.community.as_bytes()).await
+        let mut client = create_v2c_client(&target, params.community.as_bytes()).await
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
         let value = client.get(&oid).await
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
@@ -0,0 +1,152 @@
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::net::IpAddr;
+use std::path::Path;
+
+use crate::config::profile::one_or_many;
+use crate::config::settings::CredentialSettings;
+use crate::config::{Profile, Settings};
+use crate::snmp::{SnmpClient, create_v2c_client, create_v3_client_auth_priv};
+
+/// Устройство инвентаря
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Device {
+    /// Уникальное имя: по нему API, статус и файлы результатов
+    pub name: String,
+    /// IP адрес или имя хоста
+    pub address: String,
+    #[serde(default = "default_port")]
+    pub port: u16,
+    #[serde(default)]
+    pub version: SnmpVersion,
+    /// Имя набора в credentials настроек; не задано - auth
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credentials: Option<String>,
+    /// Пути к профилям внутри каталога профилей, собираются в один; пусто - по правилам выбора
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub profiles: Vec<String>,
+    #[serde(
+        default,
+        deserialize_with = "one_or_many",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub tags: Vec<String>,
+    /// Интервал опроса, секунды; не задан - по расписанию
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interval: Option<u64>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SnmpVersion {
+    #[default]
+    V2c,
+    V3,
+}
+
+fn default_port() -> u16 {
+    161
+}
+
+impl Device {
+    /// Проверка полей. Профили должны быть внутри profiles_dir: устройства приходят из API
+    pub fn validate(&self, profiles_dir: &Path) -> Result<()> {
+        if self.name.trim().is_empty() {
+            bail!("у устройства должно быть имя");
+        }
+        // Имя идет в URL и имена файлов
+        if self.name.contains(['/', '\\']) || self.name.starts_with('.') {
+            bail!(
+                "{}: в имени не должно быть '/', '\\' и точки в начале",
+                self.name
+            );
+        }
+        let address = self.address.trim();
+        if address.is_empty() || address.contains(char::is_whitespace) {
+            bail!("{}: неверный адрес '{}'", self.name, self.address);
+        }
+        if address.parse::<IpAddr>().is_err() && address.contains(':') {
+            bail!("{}: порт задается отдельно от адреса (port)", self.name);
+        }
+        if self.port == 0 {
+            bail!("{}: порт должен быть больше 0", self.name);
+        }
+        if self.interval == Some(0) {
+            bail!("{}: интервал должен быть больше 0", self.name);
+        }
+        for profile in &self.profiles {
+            Profile::check_within(profiles_dir, profile)
+                .context(format!("{}: профиль '{}'", self.name, profile))?;
+        }
+        Ok(())
+    }
+
+    /// Адрес с портом: "10.0.0.1" -> "10.0.0.1:161", "::1" -> "[::1]:161"
+    pub fn target(&self) -> String {
+        match self.address.trim().parse::<IpAddr>() {
+            Ok(IpAddr::V6(ip)) => format!("[{}]:{}", ip, self.port),
+            _ => format!("{}:{}", self.address.trim(), self.port),
+        }
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|own| own.eq_ignore_ascii_case(tag))
+    }
+
+    /// Клиент нужной версии; поля набора credentials важнее auth из настроек
+    pub async fn connect(&self, settings: &Settings) -> Result<SnmpClient> {
+        let none = CredentialSettings::default();
+        let credential = match &self.credentials {
+            Some(name) => settings.credentials.get(name).context(format!(
+                "{}: нет учетных данных '{}' в credentials",
+                self.name, name
+            ))?,
+            None => &none,
+        };
+
+        let target = self.target();
+        let auth = &settings.auth;
+        match self.version {
+            SnmpVersion::V2c => {
+                let community = credential.community.as_ref().unwrap_or(&auth.v2c.community);
+                create_v2c_client(&target, community.as_bytes()).await
+            }
+            SnmpVersion::V3 => {
+                let username = credential.username.as_ref().unwrap_or(&auth.v3.username);
+                let auth_password = credential
+                    .auth_password
+                    .as_ref()
+                    .unwrap_or(&auth.v3.auth_password);
+                let privacy_password = credential
+                    .privacy_password
+                    .as_ref()
+                    .unwrap_or(&auth.v3.privacy_password);
+                create_v3_client_auth_priv(
+                    &target,
+                    username.as_bytes(),
+                    auth_password.as_bytes(),
+                    settings.get_auth_protocol(),
+                    settings.get_privacy_protocol(),
+                    privacy_password.as_bytes(),
+                )
+                .await
+            }
+        }
+        .context(format!(
+            "{}: не удалось создать SNMP клиент для {}",
+            self.name, target
+        ))
+    }
+
+    pub fn client_type(&self) -> &'static str {
+        match self.version {
+            SnmpVersion::V2c => "SNMPv2c",
+            SnmpVersion::V3 => "SNMPv3",
+        }
+    }
+}
@@ -0,0 +1,173 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::device::{Device, SnmpVersion};
+
+/// Формат файла инвентаря
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum InventoryFormat {
+    Yaml,
+    Csv,
+}
+
+/// YAML: список устройств в devices.
+///
+/// ```yaml
+/// devices:
+///   - name: core-sw1
+///     address: 10.0.0.1
+///     credentials: core
+///     profiles: [profiles/cisco-ios.yaml]
+///     tags: [core, msk]
+///     interval: 60
+/// ```
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InventoryFile {
+    devices: Vec<Device>,
+}
+
+/// Чтение устройств из YAML и CSV
+pub struct InventoryImport;
+
+impl InventoryImport {
+    /// Формат по расширению: .csv - CSV, остальное - YAML
+    pub fn read_file(path: &str, profiles_dir: &Path) -> Result<Vec<Device>> {
+        let content = std::fs::read_to_string(path)
+            .context(format!("Не удалось прочитать файл: {}", path))?;
+        let format = match Path::new(path).extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("csv") => InventoryFormat::Csv,
+            _ => InventoryFormat::Yaml,
+        };
+        Self::parse(&content, format, profiles_dir).context(format!("Ошибка в инвентаре {}", path))
+    }
+
+    /// Устройства из текста; имена не должны повторяться, профили - внутри profiles_dir
+    pub fn parse(
+        content: &str,
+        format: InventoryFormat,
+        profiles_dir: &Path,
+    ) -> Result<Vec<Device>> {
+        let devices = match format {
+            InventoryFormat::Yaml => {
+                serde_yml::from_str::<InventoryFile>(content)
+                    .context("Не удалось распарсить YAML")?
+                    .devices
+            }
+            InventoryFormat::Csv => Self::parse_csv(content, profiles_dir)?,
+        };
+
+        let mut names = HashSet::new();
+        for device in &devices {
+            device.validate(profiles_dir)?;
+            if !names.insert(device.name.as_str()) {
+                bail!("имя '{}' повторяется", device.name);
+            }
+        }
+        Ok(devices)
+    }
+
+    /// CSV с заголовком. Обязательны name и address, остальные колонки (port, version,
+    /// credentials, profiles, tags, interval) можно опустить. Списки - через ';'
+    ///
+    /// ```text
+    /// name,address,port,version,credentials,profiles,tags,interval
+    /// core-sw1,10.0.0.1,,v2c,core,profiles/cisco-ios.yaml,core;msk,60
+    /// ```
+    fn parse_csv(content: &str, profiles_dir: &Path) -> Result<Vec<Device>> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .comment(Some(b'#'))
+            .from_reader(content.as_bytes());
+        let headers = reader
+            .headers()
+            .context("Не удалось прочитать заголовок CSV")?
+            .clone();
+
+        const COLUMNS: &[&str] = &[
+            "name",
+            "address",
+            "port",
+            "version",
+            "credentials",
+            "profiles",
+            "tags",
+            "interval",
+        ];
+        if let Some(unknown) = headers.iter().find(|header| !COLUMNS.contains(header)) {
+            bail!("неизвестная колонка '{}'", unknown);
+        }
+        for required in ["name", "address"] {
+            if !headers.iter().any(|header| header == required) {
+                bail!("нет колонки '{}'", required);
+            }
+        }
+
+        let mut devices = Vec::new();
+        for record in reader.records() {
+            let record = record.context("Не удалось прочитать строку CSV")?;
+            let line = record
+                .position()
+                .map(|position| position.line())
+                .unwrap_or(0);
+            let cell = |column: &str| {
+                headers
+                    .iter()
+                    .position(|header| header == column)
+                    .and_then(|index| record.get(index))
+                    .filter(|value| !value.is_empty())
+            };
+            let list = |column: &str| -> Vec<String> {
+                cell(column)
+                    .map(|value| {
+                        value
+                            .split(';')
+                            .map(str::trim)
+                            .filter(|item| !item.is_empty())
+                            .map(str::to_string)
+                            .collect()
+                    })
+                    .unwrap_or_default()
+            };
+
+            let version = match cell("version").map(str::to_ascii_lowercase).as_deref() {
+                None | Some("v2c") | Some("2c") => SnmpVersion::V2c,
+                Some("v3") | Some("3") => SnmpVersion::V3,
+                Some(other) => bail!("строка {}: неизвестная версия '{}'", line, other),
+            };
+            let port = match cell("port") {
+                Some(port) => port
+                    .parse()
+                    .context(format!("строка {}: неверный порт '{}'", line, port))?,
+                None => 161,
+            };
+            let interval = match cell("interval") {
+                Some(interval) => Some(
+                    interval
+                        .parse()
+                        .context(format!("строка {}: неверный интервал '{}'", line, interval))?,
+                ),
+                None => None,
+            };
+
+            let device = Device {
+                name: cell("name").unwrap_or_default().to_string(),
+                address: cell("address").unwrap_or_default().to_string(),
+                port,
+                version,
+                credentials: cell("credentials").map(str::to_string),
+                profiles: list("profiles"),
+                tags: list("tags"),
+                interval,
+            };
+            device
+                .validate(profiles_dir)
+                .context(format!("строка {}", line))?;
+            devices.push(device);
+        }
+        Ok(devices)
+    }
+}
@@ -0,0 +1,32 @@
+use std::sync::{Arc, LazyLock, RwLock};
+
+mod device;
+mod import;
+mod store;
+
+pub use device::Device;
+pub use import::{InventoryFormat, InventoryImport};
+pub use store::{ImportReport, Inventory};
+
+static GLOBAL_INVENTORY: LazyLock<RwLock<Option<Arc<Inventory>>>> =
+    LazyLock::new(|| RwLock::new(None));
+
+/// Открытый инвентарь (None, если БД не удалось открыть)
+pub fn global() -> Option<Arc<Inventory>> {
+    GLOBAL_INVENTORY.read().unwrap().clone()
+}
+
+/// Открывает БД инвентаря и делает ее глобальной. Без инвентаря сервер работает,
+/// но его API отвечает ошибкой
+pub fn open_and_install(path: &str, profiles_dir: &str) {
+    match Inventory::open(path, profiles_dir) {
+        Ok(inventory) => {
+            match inventory.list() {
+                Ok(devices) => tracing::info!("Инвентарь: {} устройств, {}", devices.len(), path),
+                Err(e) => tracing::warn!("Инвентарь {}: {:#}", path, e),
+            }
+            *GLOBAL_INVENTORY.write().unwrap() = Some(Arc::new(inventory));
+        }
+        Err(e) => tracing::error!("{:#}", e),
+    }
+}
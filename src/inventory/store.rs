@@ -0,0 +1,208 @@
+use anyhow::{Context, Result, bail};
+use redb::{Database, ReadableTable, TableDefinition};
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+
+use super::device::Device;
+
+/// Имя устройства -> устройство в JSON
+const DEVICES: TableDefinition<&str, &str> = TableDefinition::new("devices");
+
+/// Итог импорта
+#[derive(Debug, Default, Serialize)]
+pub struct ImportReport {
+    pub added: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    /// Удалено устройств, которых нет в файле (импорт с replace)
+    pub removed: usize,
+}
+
+/// Инвентарь устройств во встроенной БД. Файл БД открывается одним процессом
+pub struct Inventory {
+    db: Database,
+    /// Каталог профилей: профили устройств должны быть внутри него
+    profiles_dir: PathBuf,
+    /// Номер изменения: растет после каждой записи, на него подписывается планировщик
+    changes: watch::Sender<u64>,
+}
+
+impl Inventory {
+    /// Открывает или создает БД. profiles_dir - каталог профилей для проверки устройств
+    pub fn open(path: &str, profiles_dir: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent() {
+            std::fs::create_dir_all(parent)
+                .context(format!("Не удалось создать каталог для {}", path))?;
+        }
+        let db = match Database::create(path) {
+            Ok(db) => db,
+            Err(redb::DatabaseError::DatabaseAlreadyOpen) => bail!(
+                "Инвентарь {} занят запущенным сервером, используйте его API \
+                 (/inventory, /fleet/collect)",
+                path
+            ),
+            Err(e) => {
+                return Err(e).context(format!("Не удалось открыть инвентарь {}", path));
+            }
+        };
+
+        // Таблица создается при первой записи, пустой инвентарь тоже должен читаться
+        let write = db.begin_write()?;
+        write.open_table(DEVICES)?;
+        write.commit()?;
+        Ok(Self {
+            db,
+            profiles_dir: PathBuf::from(profiles_dir),
+            changes: watch::Sender::new(0),
+        })
+    }
+
+    pub fn profiles_dir(&self) -> &Path {
+        &self.profiles_dir
+    }
+
+    /// Подписка на изменения: получатель узнает, что инвентарь поменялся, и перечитывает его
+    pub fn subscribe(&self) -> watch::Receiver<u64> {
+        self.changes.subscribe()
+    }
+
+    pub fn list(&self) -> Result<Vec<Device>> {
+        let read = self.db.begin_read()?;
+        let table = read.open_table(DEVICES)?;
+        let mut devices = Vec::new();
+        for entry in table.iter()? {
+            let (_, value) = entry?;
+            devices.push(Self::decode(value.value())?);
+        }
+        Ok(devices)
+    }
+
+    pub fn get(&self, name: &str) -> Result<Option<Device>> {
+        let read = self.db.begin_read()?;
+        let table = read.open_table(DEVICES)?;
+        table
+            .get(name)?
+            .map(|value| Self::decode(value.value()))
+            .transpose()
+    }
+
+    /// Устройства по именам и тегу; без имен и тега - все. Ошибка, если имени нет
+    pub fn select(&self, names: &[String], tag: Option<&str>) -> Result<Vec<Device>> {
+        let mut devices = self.list()?;
+        if let Some(missing) = names
+            .iter()
+            .find(|name| !devices.iter().any(|device| &device.name == *name))
+        {
+            bail!("устройство '{}' не найдено в инвентаре", missing);
+        }
+        devices.retain(|device| {
+            (names.is_empty() || names.contains(&device.name))
+                && tag.is_none_or(|tag| device.has_tag(tag))
+        });
+        Ok(devices)
+    }
+
+    /// Добавляет новое устройство; false, если имя занято. Проверка и запись - в одной
+    /// транзакции, одновременные запросы с одним именем не пройдут оба
+    pub fn create(&self, device: &Device) -> Result<bool> {
+        device.validate(&self.profiles_dir)?;
+        let write = self.db.begin_write()?;
+        {
+            let mut table = write.open_table(DEVICES)?;
+            if table.get(device.name.as_str())?.is_some() {
+                return Ok(false);
+            }
+            table.insert(
+                device.name.as_str(),
+                serde_json::to_string(device)?.as_str(),
+            )?;
+        }
+        write.commit()?;
+        self.changed();
+        Ok(true)
+    }
+
+    /// Добавляет или заменяет устройство, возвращает прежнее
+    pub fn upsert(&self, device: &Device) -> Result<Option<Device>> {
+        device.validate(&self.profiles_dir)?;
+        let write = self.db.begin_write()?;
+        let previous = {
+            let mut table = write.open_table(DEVICES)?;
+            let json = serde_json::to_string(device)?;
+            let previous = table.insert(device.name.as_str(), json.as_str())?;
+            previous
+                .map(|value| Self::decode(value.value()))
+                .transpose()?
+        };
+        write.commit()?;
+        self.changed();
+        Ok(previous)
+    }
+
+    pub fn remove(&self, name: &str) -> Result<Option<Device>> {
+        let write = self.db.begin_write()?;
+        let removed = {
+            let mut table = write.open_table(DEVICES)?;
+            let removed = table.remove(name)?;
+            removed
+                .map(|value| Self::decode(value.value()))
+                .transpose()?
+        };
+        write.commit()?;
+        if removed.is_some() {
+            self.changed();
+        }
+        Ok(removed)
+    }
+
+    /// Загружает устройства одной транзакцией: при ошибке инвентарь не меняется.
+    /// replace - удалить устройства, которых нет среди загружаемых
+    pub fn import(&self, devices: &[Device], replace: bool) -> Result<ImportReport> {
+        for device in devices {
+            device.validate(&self.profiles_dir)?;
+        }
+
+        let mut report = ImportReport::default();
+        let write = self.db.begin_write()?;
+        {
+            let mut table = write.open_table(DEVICES)?;
+            if replace {
+                let stale: Vec<String> = table
+                    .iter()?
+                    .map(|entry| entry.map(|(name, _)| name.value().to_string()))
+                    .collect::<Result<_, _>>()?;
+                for name in stale {
+                    if !devices.iter().any(|device| device.name == name) {
+                        table.remove(name.as_str())?;
+                        report.removed += 1;
+                    }
+                }
+            }
+
+            for device in devices {
+                let json = serde_json::to_string(device)?;
+                let previous = table.insert(device.name.as_str(), json.as_str())?;
+                match previous
+                    .map(|value| Self::decode(value.value()))
+                    .transpose()?
+                {
+                    None => report.added += 1,
+                    Some(previous) if previous == *device => report.unchanged += 1,
+                    Some(_) => report.updated += 1,
+                }
+            }
+        }
+        write.commit()?;
+        self.changed();
+        Ok(report)
+    }
+
+    fn changed(&self) {
+        self.changes.send_modify(|version| *version += 1);
+    }
+
+    fn decode(json: &str) -> Result<Device> {
+        serde_json::from_str(json).context("Поврежденная запись инвентаря")
+    }
+}
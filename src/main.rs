@@ -1,106 +1,288 @@
+
+mod cli;
 mod collector;
 mod config;
 mod formatter;
+mod mib;
 mod snmp;
 mod routes;
 mod handlers;
+mod inventory;
 mod models;
+mod scheduler;
 
+use clap::Parser;
+use cli::{Cli, Command, ConfigFormat, InventoryCommand};
 use routes::create_router;
+use std::path::PathBuf;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
-use collector::SnmpCollector;
-use formatter::JsonFormatter;
 
 fn main() {
     dotenvy::dotenv().ok();
+    let cli = Cli::parse();
+    let layers = config::SettingsLayers::new(cli.config.clone(), cli.overrides());
+
+    if let Some(Command::Config {
+        format,
+        sources,
+        show_secrets,
+    }) = cli.command
+    {
+        std::process::exit(print_config(&layers, format, sources, show_secrets));
+    }
+
+    // Ошибку в настройках пишем в лог после его настройки
+    let (settings, settings_error) = match layers.load() {
+        Ok(settings) => (settings, None),
+        Err(e) => (config::Settings::default(), Some(e)),
+    };
 
     tracing_subscriber::registry()
-        .with(tracing_subscriber::EnvFilter::new(
-            std::env::var("RUST_LOG").unwrap(),
-        ))
-        .with(tracing_subscriber::fmt::layer())
+        .with(
+            tracing_subscriber::EnvFilter::try_new(&settings.logging.level)
+                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
+        )
+        .with(tracing_subscriber::fmt::layer().with_writer(std::io::stderr))
         .init();
-    
+    if let Some(e) = settings_error {
+        tracing::error!("{:#}, настройки по умолчанию", e);
+    }
+
+    if let Some(Command::Inventory { action }) = cli.command {
+        std::process::exit(inventory_command(&settings, action));
+    }
+
     // TODO поменять/убрать лимит
     let rt = tokio::runtime::Builder::new_multi_thread()
-        .thread_stack_size(4 * 1024 * 1024) 
+        .thread_stack_size(4 * 1024 * 1024)
         .enable_all()
         .build()
         .expect("Не удалось создать runtime");
 
+    // MIB и реестр нужны до профилей: в профилях могут быть имена из MIB
+    mib::load_and_install(&settings.mib_dirs());
+    snmp::vendor_registry::load_and_install(&settings.vendors);
+    config::reload::load_and_install(&layers);
+
+    if let Some(Command::Lint { paths, json }) = cli.command {
+        std::process::exit(lint(&settings, paths, json));
+    }
+    if let Some(Command::Collect {
+        names,
+        tag,
+        varbinds,
+    }) = cli.command
+    {
+        std::process::exit(rt.block_on(collect(&settings, names, tag, varbinds)));
+    }
+
+    // Инвентарь держит сервер: БД открывается одним процессом
+    inventory::open_and_install(&settings.inventory.db, &settings.profiles.dir);
+
     rt.block_on(async {
+        config::ConfigWatcher::spawn(layers);
+        if settings.scheduler.enabled
+            && let Err(e) =
+                scheduler::Scheduler::spawn(&settings.scheduler.file, &settings.profiles.dir)
+        {
+            tracing::error!("Планировщик не запущен: {:#}", e);
+        }
         let app = create_router();
-        let listener = tokio::net::TcpListener::bind("127.0.0.1:8000").await.expect("Не удалось сделать bind");
+        let listener = tokio::net::TcpListener::bind(&settings.server.bind).await.expect("Не удалось сделать bind");
+        tracing::info!("Сервер слушает {}", settings.server.bind);
         axum::serve(listener, app).await.expect("Не удалось создать сервер");
     });
 }
-    
-    // let config = config::AppConfig::load("./profiles/generic-endpoint.yaml")?;
-    // let target = config.get_target();
-
-    // // Результаты для JSON вывода
-    // let mut results = Vec::new();
-
-    // match create_snmpv3_client(&config, &target).await {
-    //     Ok(client) => match SnmpCollector::collect_all(client, &config, "SNMPv3").await {
-    //         Ok(result) => {
-    //             results.push(result);
-    //         }
-    //         Err(e) => {
-    //             eprintln!("SNMPv3 сбор данных не удался: {}", e);
-    //         }
-    //     },
-    //     Err(_) => {
-    //         eprintln!("SNMPv3 клиент недоступен");
-    //     }
-    // }
-
-//     match create_snmpv2c_client(&config, &target).await {
-//         Ok(client) => match SnmpCollector::collect_all(client, &config, "SNMPv2c").await {
-//             Ok(result) => {
-//                 results.push(result);
-//             }
-//             Err(_) => {
-//                 eprintln!("SNMPv2c недоступен");
-//             }
-//         },
-//         Err(_) => {
-//             eprintln!("snmp недоступен");
-//         }
-//     }
-
-//     // Выводим результаты в JSON
-//     for result in results {
-//         match JsonFormatter::to_json_string(&result) {
-//             Ok(json) => println!("{}", json),
-//             Err(e) => eprintln!("❌ Ошибка JSON сериализации: {}", e),
-//         }
-//     }
-
-//     Ok(())
-// }
-
-// /// Создает SNMPv3 клиент
-// async fn create_snmpv3_client(
-//     config: &config::AppConfig,
-//     target: &str,
-// ) -> Result<snmp::SnmpClient> {
-//     snmp::create_v3_client_auth_priv(
-//         target,
-//         &config.get_username(),
-//         &config.get_auth_password(),
-//         config.settings.get_auth_protocol(),
-//         config.settings.get_privacy_protocol(),
-//         &config.get_privacy_password(),
-//     )
-//     .await
-// }
-
-// /// Создает SNMPv2c клиент
-// async fn create_snmpv2c_client(
-//     config: &config::AppConfig,
-//     target: &str,
-// ) -> Result<snmp::SnmpClient> {
-//     snmp::create_v2c_client(target, &config.get_community()).await
-// }
\ No newline at end of file
+
+/// Итоговые настройки после всех слоев. Пароли скрыты, если не задан --show-secrets
+fn print_config(
+    layers: &config::SettingsLayers,
+    format: ConfigFormat,
+    sources: bool,
+    show_secrets: bool,
+) -> i32 {
+    let effective = match layers.load_effective() {
+        Ok(effective) => effective,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return 1;
+        }
+    };
+
+    let mut value = match serde_yml::to_value(&effective.settings) {
+        Ok(value) => value,
+        Err(e) => {
+            eprintln!("Ошибка сериализации настроек: {}", e);
+            return 1;
+        }
+    };
+    if !show_secrets {
+        for key in config::settings_layers::secret_keys(&effective.settings) {
+            config::settings_layers::set(&mut value, &key, "***".into());
+        }
+    }
+
+    if sources {
+        for (key, leaf) in config::settings_layers::leaves(&value) {
+            let source = effective
+                .sources
+                .get(&key)
+                .cloned()
+                .unwrap_or(config::Layer::Default);
+            let leaf = serde_json::to_string(&leaf).unwrap_or_default();
+            println!("{} = {}  # {}", key, leaf, source);
+        }
+        return 0;
+    }
+
+    let text = match format {
+        ConfigFormat::Yaml => serde_yml::to_string(&value).map_err(|e| e.to_string()),
+        ConfigFormat::Toml => toml::to_string(&value).map_err(|e| e.to_string()),
+        ConfigFormat::Json => serde_json::to_string_pretty(&value).map_err(|e| e.to_string()),
+    };
+    match text {
+        Ok(text) => {
+            println!("{}", text.trim_end());
+            0
+        }
+        Err(e) => {
+            eprintln!("Ошибка сериализации настроек: {}", e);
+            1
+        }
+    }
+}
+
+/// Команды инвентаря. БД открывается одним процессом: при работающем сервере
+/// менять инвентарь нужно через API
+fn inventory_command(settings: &config::Settings, action: InventoryCommand) -> i32 {
+    let store = match inventory::Inventory::open(&settings.inventory.db, &settings.profiles.dir) {
+        Ok(store) => store,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return 1;
+        }
+    };
+
+    match action {
+        InventoryCommand::Import { files, replace } => {
+            let mut devices = Vec::new();
+            for file in &files {
+                match inventory::InventoryImport::read_file(file, store.profiles_dir()) {
+                    Ok(loaded) => devices.extend(loaded),
+                    Err(e) => {
+                        eprintln!("{:#}", e);
+                        return 1;
+                    }
+                }
+            }
+            match store.import(&devices, replace) {
+                Ok(report) => {
+                    println!(
+                        "Добавлено: {}, изменено: {}, без изменений: {}, удалено: {}",
+                        report.added, report.updated, report.unchanged, report.removed
+                    );
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{:#}", e);
+                    1
+                }
+            }
+        }
+        InventoryCommand::List { tag, json } => {
+            let mut devices = match store.list() {
+                Ok(devices) => devices,
+                Err(e) => {
+                    eprintln!("{:#}", e);
+                    return 1;
+                }
+            };
+            if let Some(tag) = &tag {
+                devices.retain(|device| device.has_tag(tag));
+            }
+            let text = if json {
+                serde_json::to_string_pretty(&devices).map_err(|e| e.to_string())
+            } else {
+                let file = std::collections::BTreeMap::from([("devices", &devices)]);
+                serde_yml::to_string(&file).map_err(|e| e.to_string())
+            };
+            match text {
+                Ok(text) => {
+                    println!("{}", text.trim_end());
+                    0
+                }
+                Err(e) => {
+                    eprintln!("Ошибка сериализации инвентаря: {}", e);
+                    1
+                }
+            }
+        }
+    }
+}
+
+/// Опрос устройств инвентаря из командной строки. Код выхода 1, если хоть одно
+/// устройство не опрошено
+async fn collect(
+    settings: &config::Settings,
+    names: Vec<String>,
+    tag: Option<String>,
+    varbinds: bool,
+) -> i32 {
+    // БД нужна только для выбора устройств, на время опроса она закрыта
+    let devices = inventory::Inventory::open(&settings.inventory.db, &settings.profiles.dir)
+        .and_then(|store| store.select(&names, tag.as_deref()));
+    let devices = match devices {
+        Ok(devices) => devices,
+        Err(e) => {
+            eprintln!("{:#}", e);
+            return 1;
+        }
+    };
+
+    let options = formatter::FormatOptions {
+        include_varbinds: varbinds,
+    };
+    let mut events = collector::FleetCollector::spawn(devices);
+    let mut code = 1;
+    while let Some(event) = events.recv().await {
+        match formatter::JsonFormatter::fleet_event_line(&event, &options) {
+            Ok(line) => println!("{}", line),
+            Err(e) => eprintln!("{:#}", e),
+        }
+        if let collector::FleetEvent::Finished(summary) = &event {
+            code = i32::from(summary.ok < summary.devices);
+        }
+    }
+    code
+}
+
+/// Проверка профилей из командной строки. Код выхода 1, если есть ошибки
+fn lint(settings: &config::Settings, paths: Vec<String>, json: bool) -> i32 {
+    let paths: Vec<PathBuf> = if paths.is_empty() {
+        vec![PathBuf::from(&settings.profiles.dir)]
+    } else {
+        paths.into_iter().map(PathBuf::from).collect()
+    };
+    let exclude = [PathBuf::from(&settings.profiles.selection)];
+    let reports = config::ProfileLinter::lint_paths(&paths, &exclude);
+
+    if json {
+        match serde_json::to_string_pretty(&reports) {
+            Ok(text) => println!("{}", text),
+            Err(e) => eprintln!("Ошибка JSON сериализации: {}", e),
+        }
+    } else {
+        for report in &reports {
+            print!("{}", report);
+        }
+    }
+
+    let failed = reports.iter().filter(|report| report.has_errors()).count();
+    if failed > 0 {
+        eprintln!("Профилей с ошибками: {} из {}", failed, reports.len());
+        1
+    } else {
+        0
+    }
+}
@@ -0,0 +1,158 @@
+use anyhow::{Result, bail};
+
+/// Токен SMI
+#[derive(Debug, Clone, PartialEq)]
+pub enum Token {
+    /// Идентификатор или ключевое слово: ifDescr, OBJECT-TYPE, INTEGER
+    Ident(String),
+    /// Число, может быть отрицательным
+    Number(i64),
+    /// Строка в кавычках
+    Text(String),
+    /// Бинарная/hex строка: '0A'H, '0101'B
+    BinaryString(String),
+    /// Символы: ::= { } ( ) [ ] , ; | .. .
+    Symbol(&'static str),
+}
+
+/// Токен с номером строки для сообщений об ошибках
+#[derive(Debug, Clone)]
+pub struct Spanned {
+    pub token: Token,
+    pub line: usize,
+}
+
+const SYMBOLS: &[&str] = &[
+    "::=", "..", "{", "}", "(", ")", "[", "]", ",", ";", "|", ".", "<", ">", "@", "!",
+];
+
+/// Разбивает текст MIB модуля на токены, комментарии (-- ... -- или до конца строки) пропускаются
+pub fn tokenize(source: &str) -> Result<Vec<Spanned>> {
+    let chars: Vec<char> = source.chars().collect();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut line = 1;
+
+    while pos < chars.len() {
+        let c = chars[pos];
+
+        if c == '\n' {
+            line += 1;
+            pos += 1;
+            continue;
+        }
+        if c.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        // Комментарий: до следующего "--" или до конца строки
+        if c == '-' && chars.get(pos + 1) == Some(&'-') {
+            pos += 2;
+            while pos < chars.len() && chars[pos] != '\n' {
+                if chars[pos] == '-' && chars.get(pos + 1) == Some(&'-') {
+                    pos += 2;
+                    break;
+                }
+                pos += 1;
+            }
+            continue;
+        }
+
+        let start_line = line;
+
+        if c == '"' {
+            let start = pos + 1;
+            pos += 1;
+            while pos < chars.len() && chars[pos] != '"' {
+                if chars[pos] == '\n' {
+                    line += 1;
+                }
+                pos += 1;
+            }
+            if pos >= chars.len() {
+                bail!("строка {}: незакрытая кавычка", start_line);
+            }
+            let text: String = chars[start..pos].iter().collect();
+            pos += 1;
+            tokens.push(Spanned {
+                token: Token::Text(text),
+                line: start_line,
+            });
+            continue;
+        }
+
+        if c == '\'' {
+            let start = pos + 1;
+            pos += 1;
+            while pos < chars.len() && chars[pos] != '\'' {
+                pos += 1;
+            }
+            if pos + 1 >= chars.len() {
+                bail!("строка {}: незакрытая бинарная строка", start_line);
+            }
+            let text: String = chars[start..pos].iter().collect();
+            // Суффикс H или B
+            pos += 2;
+            tokens.push(Spanned {
+                token: Token::BinaryString(text),
+                line: start_line,
+            });
+            continue;
+        }
+
+        let is_negative_number =
+            c == '-' && chars.get(pos + 1).is_some_and(|next| next.is_ascii_digit());
+        if c.is_ascii_digit() || is_negative_number {
+            let start = pos;
+            pos += 1;
+            while pos < chars.len() && chars[pos].is_ascii_digit() {
+                pos += 1;
+            }
+            let text: String = chars[start..pos].iter().collect();
+            let number = text
+                .parse::<i64>()
+                .or_else(|_| text.parse::<u64>().map(|n| n as i64))
+                .map_err(|_| anyhow::anyhow!("строка {}: некорректное число '{}'", line, text))?;
+            tokens.push(Spanned {
+                token: Token::Number(number),
+                line: start_line,
+            });
+            continue;
+        }
+
+        if c.is_ascii_alphabetic() {
+            let start = pos;
+            pos += 1;
+            while pos < chars.len() {
+                let ch = chars[pos];
+                let starts_comment = ch == '-' && chars.get(pos + 1) == Some(&'-');
+                if (ch.is_ascii_alphanumeric() || ch == '-' || ch == '_') && !starts_comment {
+                    pos += 1;
+                } else {
+                    break;
+                }
+            }
+            let text: String = chars[start..pos].iter().collect();
+            tokens.push(Spanned {
+                token: Token::Ident(text),
+                line: start_line,
+            });
+            continue;
+        }
+
+        let rest: String = chars[pos..(pos + 3).min(chars.len())].iter().collect();
+        match SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) {
+            Some(symbol) => {
+                pos += symbol.len();
+                tokens.push(Spanned {
+                    token: Token::Symbol(symbol),
+                    line: start_line,
+                });
+            }
+            None => bail!("строка {}: неожиданный символ '{}'", line, c),
+        }
+    }
+
+    Ok(tokens)
+}
@@ -0,0 +1,44 @@
+mod lexer;
+mod parser;
+mod tree;
+mod types;
+
+#[cfg(test)]
+pub(crate) use parser::parse_mib;
+pub use tree::{LoadReport, MibTree};
+pub use types::{
+    BaseType, IndexPart, MibNode, NodeKind, NodeRole, Syntax, format_oid, parse_numeric_oid,
+};
+
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+
+/// Дерево MIB, общее для коллектора, форматтера и API
+static GLOBAL_TREE: LazyLock<RwLock<Arc<MibTree>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(MibTree::empty())));
+
+/// Текущее дерево MIB (пустое, пока не вызван install)
+pub fn global() -> Arc<MibTree> {
+    GLOBAL_TREE.read().unwrap().clone()
+}
+
+/// Заменяет глобальное дерево
+pub fn install(tree: MibTree) {
+    *GLOBAL_TREE.write().unwrap() = Arc::new(tree);
+}
+
+/// Загружает MIB из каталогов и делает дерево глобальным
+pub fn load_and_install(dirs: &[impl AsRef<Path>]) -> LoadReport {
+    let (tree, report) = MibTree::load_dirs(dirs);
+    for error in &report.errors {
+        tracing::warn!("MIB: {}", error);
+    }
+    tracing::info!(
+        "MIB: загружено {} модулей ({} файлов, {} узлов)",
+        report.modules,
+        report.files,
+        report.nodes
+    );
+    install(tree);
+    report
+}
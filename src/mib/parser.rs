@@ -0,0 +1,580 @@
+use anyhow::{Result, anyhow, bail};
+
+use super::lexer::{Spanned, Token, tokenize};
+use super::types::{
+    EnumValue, IndexPart, NodeKind, OidComponent, ParsedModule, ParsedObject, ParsedType, TypeRef,
+};
+
+/// Разбирает текст MIB файла (SMIv1/SMIv2). В файле может быть несколько модулей
+pub fn parse_mib(source: &str) -> Result<Vec<ParsedModule>> {
+    let tokens = tokenize(source)?;
+    let mut parser = Parser { tokens, pos: 0 };
+    let mut modules = Vec::new();
+
+    while !parser.at_end() {
+        modules.push(parser.parse_module()?);
+    }
+
+    Ok(modules)
+}
+
+struct Parser {
+    tokens: Vec<Spanned>,
+    pos: usize,
+}
+
+impl Parser {
+    fn at_end(&self) -> bool {
+        self.pos >= self.tokens.len()
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos).map(|t| &t.token)
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<&Token> {
+        self.tokens.get(self.pos + offset).map(|t| &t.token)
+    }
+
+    fn line(&self) -> usize {
+        self.tokens
+            .get(self.pos)
+            .or_else(|| self.tokens.last())
+            .map(|t| t.line)
+            .unwrap_or(0)
+    }
+
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .map(|t| t.token.clone())
+            .ok_or_else(|| anyhow!("неожиданный конец файла"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol)
+    }
+
+    fn is_ident(&self, ident: &str) -> bool {
+        matches!(self.peek(), Some(Token::Ident(s)) if s == ident)
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<()> {
+        let line = self.line();
+        match self.next()? {
+            Token::Symbol(s) if s == symbol => Ok(()),
+            other => bail!(
+                "строка {}: ожидалось '{}', получено {:?}",
+                line,
+                symbol,
+                other
+            ),
+        }
+    }
+
+    fn expect_ident(&mut self) -> Result<String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Ident(s) => Ok(s),
+            other => bail!(
+                "строка {}: ожидался идентификатор, получено {:?}",
+                line,
+                other
+            ),
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        let line = self.line();
+        let ident = self.expect_ident()?;
+        if ident != keyword {
+            bail!(
+                "строка {}: ожидалось '{}', получено '{}'",
+                line,
+                keyword,
+                ident
+            );
+        }
+        Ok(())
+    }
+
+    fn expect_number(&mut self) -> Result<i64> {
+        let line = self.line();
+        match self.next()? {
+            Token::Number(n) => Ok(n),
+            other => bail!("строка {}: ожидалось число, получено {:?}", line, other),
+        }
+    }
+
+    fn expect_text(&mut self) -> Result<String> {
+        let line = self.line();
+        match self.next()? {
+            Token::Text(s) => Ok(s),
+            other => bail!("строка {}: ожидалась строка, получено {:?}", line, other),
+        }
+    }
+
+    /// Пропускает сбалансированный блок, начиная с открывающей скобки
+    fn skip_balanced(&mut self) -> Result<()> {
+        let (open, close) = match self.next()? {
+            Token::Symbol("{") => ("{", "}"),
+            Token::Symbol("(") => ("(", ")"),
+            Token::Symbol("[") => ("[", "]"),
+            other => bail!(
+                "строка {}: ожидалась скобка, получено {:?}",
+                self.line(),
+                other
+            ),
+        };
+        let mut depth = 1;
+        while depth > 0 {
+            match self.next()? {
+                Token::Symbol(s) if s == open => depth += 1,
+                Token::Symbol(s) if s == close => depth -= 1,
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+
+    /// MODULE-NAME [{ oid }] DEFINITIONS ::= BEGIN ... END
+    fn parse_module(&mut self) -> Result<ParsedModule> {
+        let name = self.expect_ident()?;
+        if self.is_symbol("{") {
+            self.skip_balanced()?;
+        }
+        self.expect_keyword("DEFINITIONS")?;
+        // DEFINITIONS IMPLICIT TAGS ::= BEGIN
+        while !self.is_symbol("::=") {
+            self.next()?;
+        }
+        self.expect_symbol("::=")?;
+        self.expect_keyword("BEGIN")?;
+
+        let mut module = ParsedModule {
+            name,
+            imports: Vec::new(),
+            objects: Vec::new(),
+            types: Vec::new(),
+        };
+
+        loop {
+            if self.at_end() {
+                bail!("модуль '{}': нет END", module.name);
+            }
+            if self.is_ident("END") {
+                self.next()?;
+                break;
+            }
+            if self.is_ident("IMPORTS") {
+                self.next()?;
+                module.imports = self.parse_imports()?;
+                continue;
+            }
+            if self.is_ident("EXPORTS") {
+                while !self.is_symbol(";") {
+                    self.next()?;
+                }
+                self.next()?;
+                continue;
+            }
+            self.parse_assignment(&mut module)?;
+        }
+
+        Ok(module)
+    }
+
+    /// IMPORTS a, b FROM MODULE-A c FROM MODULE-B ;
+    fn parse_imports(&mut self) -> Result<Vec<(String, String)>> {
+        let mut imports = Vec::new();
+        let mut pending = Vec::new();
+
+        loop {
+            match self.next()? {
+                Token::Symbol(";") => break,
+                Token::Symbol(",") => {}
+                Token::Ident(ident) if ident == "FROM" => {
+                    let module = self.expect_ident()?;
+                    for symbol in pending.drain(..) {
+                        imports.push((symbol, module.clone()));
+                    }
+                }
+                Token::Ident(ident) => pending.push(ident),
+                other => bail!(
+                    "строка {}: неожиданный токен в IMPORTS: {:?}",
+                    self.line(),
+                    other
+                ),
+            }
+        }
+
+        Ok(imports)
+    }
+
+    /// Одно определение модуля
+    fn parse_assignment(&mut self, module: &mut ParsedModule) -> Result<()> {
+        let line = self.line();
+        let name = self.expect_ident()?;
+
+        // Name ::= TYPE | TEXTUAL-CONVENTION
+        if self.is_symbol("::=") {
+            self.next()?;
+            let parsed = if self.is_ident("TEXTUAL-CONVENTION") {
+                self.next()?;
+                self.parse_textual_convention(name)?
+            } else {
+                ParsedType {
+                    name,
+                    syntax: self.parse_type()?,
+                    display_hint: None,
+                    is_tc: false,
+                }
+            };
+            module.types.push(parsed);
+            return Ok(());
+        }
+
+        // NAME MACRO ::= BEGIN ... END
+        if self.is_ident("MACRO") {
+            while !self.is_ident("END") {
+                self.next()?;
+            }
+            self.next()?;
+            return Ok(());
+        }
+
+        // name OBJECT IDENTIFIER ::= { ... }
+        if self.is_ident("OBJECT")
+            && matches!(self.peek_at(1), Some(Token::Ident(s)) if s == "IDENTIFIER")
+        {
+            self.next()?;
+            self.next()?;
+            self.expect_symbol("::=")?;
+            let oid = self.parse_oid_value()?;
+            module.objects.push(Self::new_object(
+                name,
+                NodeKind::ObjectIdentifier,
+                oid,
+                line,
+            ));
+            return Ok(());
+        }
+
+        // name MACRO-NAME clauses ::= value
+        let macro_name = self.expect_ident()?;
+        let kind = match macro_name.as_str() {
+            "OBJECT-TYPE" => NodeKind::ObjectType,
+            "MODULE-IDENTITY" => NodeKind::ModuleIdentity,
+            "OBJECT-IDENTITY" => NodeKind::ObjectIdentity,
+            "NOTIFICATION-TYPE" | "TRAP-TYPE" => NodeKind::NotificationType,
+            _ => NodeKind::Conformance,
+        };
+
+        let mut object = Self::new_object(name, kind, Vec::new(), line);
+        self.parse_clauses(&mut object)?;
+        self.expect_symbol("::=")?;
+
+        // TRAP-TYPE (SMIv1) задается номером, а не OID - в дерево не попадает
+        if self.is_symbol("{") {
+            object.oid = self.parse_oid_value()?;
+            module.objects.push(object);
+        } else {
+            self.next()?;
+        }
+
+        Ok(())
+    }
+
+    fn new_object(
+        name: String,
+        kind: NodeKind,
+        oid: Vec<OidComponent>,
+        line: usize,
+    ) -> ParsedObject {
+        ParsedObject {
+            name,
+            kind,
+            oid,
+            syntax: None,
+            access: None,
+            status: None,
+            description: None,
+            units: None,
+            index: Vec::new(),
+            augments: None,
+            line,
+        }
+    }
+
+    /// Клаузы макроса до "::="
+    fn parse_clauses(&mut self, object: &mut ParsedObject) -> Result<()> {
+        while !self.is_symbol("::=") {
+            if self.at_end() {
+                bail!("'{}': нет '::='", object.name);
+            }
+            let Some(Token::Ident(keyword)) = self.peek().cloned() else {
+                if self.is_symbol("{") || self.is_symbol("(") {
+                    self.skip_balanced()?;
+                } else {
+                    self.next()?;
+                }
+                continue;
+            };
+            self.next()?;
+
+            match keyword.as_str() {
+                // В MODULE-COMPLIANCE/AGENT-CAPABILITIES тоже есть SYNTAX, но там он нам не нужен
+                "SYNTAX" if object.kind == NodeKind::ObjectType => {
+                    object.syntax = Some(self.parse_type()?)
+                }
+                "SYNTAX" | "WRITE-SYNTAX" => {
+                    self.parse_type()?;
+                }
+                "MAX-ACCESS" | "ACCESS" | "MIN-ACCESS" => {
+                    let access = self.expect_ident()?;
+                    if object.kind == NodeKind::ObjectType {
+                        object.access = Some(access);
+                    }
+                }
+                "STATUS" => object.status = Some(self.expect_ident()?),
+                "DESCRIPTION" => {
+                    let text = self.expect_text()?;
+                    if object.description.is_none() {
+                        object.description = Some(normalize_text(&text));
+                    }
+                }
+                "UNITS" => object.units = Some(self.expect_text()?),
+                "INDEX" => object.index = self.parse_index()?,
+                "AUGMENTS" => {
+                    self.expect_symbol("{")?;
+                    object.augments = Some(self.expect_ident()?);
+                    self.expect_symbol("}")?;
+                }
+                _ => {}
+            }
+        }
+
+        Ok(())
+    }
+
+    /// INDEX { [IMPLIED] a, b }
+    fn parse_index(&mut self) -> Result<Vec<IndexPart>> {
+        self.expect_symbol("{")?;
+        let mut parts = Vec::new();
+        let mut implied = false;
+
+        loop {
+            match self.next()? {
+                Token::Symbol("}") => break,
+                Token::Symbol(",") => {}
+                Token::Ident(ident) if ident == "IMPLIED" => implied = true,
+                Token::Ident(name) => {
+                    parts.push(IndexPart { name, implied });
+                    implied = false;
+                }
+                other => bail!(
+                    "строка {}: неожиданный токен в INDEX: {:?}",
+                    self.line(),
+                    other
+                ),
+            }
+        }
+
+        Ok(parts)
+    }
+
+    /// Значение OID: { parent 1 }, { iso org(3) dod(6) 1 }
+    fn parse_oid_value(&mut self) -> Result<Vec<OidComponent>> {
+        self.expect_symbol("{")?;
+        let mut components = Vec::new();
+
+        loop {
+            match self.next()? {
+                Token::Symbol("}") => break,
+                Token::Number(n) => components.push(OidComponent::Number(to_arc(n, self.line())?)),
+                Token::Ident(name) => {
+                    if self.is_symbol("(") {
+                        self.next()?;
+                        let n = self.expect_number()?;
+                        self.expect_symbol(")")?;
+                        components.push(OidComponent::NamedNumber(name, to_arc(n, self.line())?));
+                    } else {
+                        components.push(OidComponent::Name(name));
+                    }
+                }
+                other => bail!(
+                    "строка {}: неожиданный токен в OID: {:?}",
+                    self.line(),
+                    other
+                ),
+            }
+        }
+
+        Ok(components)
+    }
+
+    /// TEXTUAL-CONVENTION: клаузы до SYNTAX, затем сам тип
+    fn parse_textual_convention(&mut self, name: String) -> Result<ParsedType> {
+        let mut display_hint = None;
+
+        loop {
+            match self.next()? {
+                Token::Ident(keyword) if keyword == "SYNTAX" => break,
+                Token::Ident(keyword) if keyword == "DISPLAY-HINT" => {
+                    display_hint = Some(self.expect_text()?)
+                }
+                _ => {}
+            }
+        }
+
+        Ok(ParsedType {
+            name,
+            syntax: self.parse_type()?,
+            display_hint,
+            is_tc: true,
+        })
+    }
+
+    /// Тип: INTEGER {..}, OCTET STRING (SIZE ..), SEQUENCE OF X, [APPLICATION n] IMPLICIT ..., Name
+    fn parse_type(&mut self) -> Result<TypeRef> {
+        // [APPLICATION n] IMPLICIT - тег нам не нужен, базовые типы узнаем по имени
+        if self.is_symbol("[") {
+            self.skip_balanced()?;
+            if self.is_ident("IMPLICIT") || self.is_ident("EXPLICIT") {
+                self.next()?;
+            }
+        }
+
+        let line = self.line();
+        let name = self.expect_ident()?;
+        let parsed = match name.as_str() {
+            "INTEGER" => TypeRef::Integer {
+                enums: self.parse_named_numbers()?,
+            },
+            "OCTET" => {
+                self.expect_keyword("STRING")?;
+                TypeRef::OctetString {
+                    sizes: self.parse_constraint()?,
+                }
+            }
+            "OBJECT" => {
+                self.expect_keyword("IDENTIFIER")?;
+                TypeRef::ObjectIdentifier
+            }
+            "BITS" => TypeRef::Bits {
+                bits: self.parse_named_numbers()?,
+            },
+            "SEQUENCE" => {
+                if self.is_ident("OF") {
+                    self.next()?;
+                    TypeRef::SequenceOf(self.expect_ident()?)
+                } else {
+                    self.skip_balanced()?;
+                    TypeRef::Sequence
+                }
+            }
+            "CHOICE" => {
+                self.skip_balanced()?;
+                TypeRef::Other
+            }
+            _ if name.chars().next().is_some_and(|c| c.is_ascii_uppercase()) => {
+                let enums = self.parse_named_numbers()?;
+                let sizes = if enums.is_empty() {
+                    self.parse_constraint()?
+                } else {
+                    Vec::new()
+                };
+                TypeRef::Named { name, enums, sizes }
+            }
+            _ => bail!("строка {}: неизвестный тип '{}'", line, name),
+        };
+
+        // Ограничения диапазона после перечислений и т.п.
+        if self.is_symbol("(") {
+            self.skip_balanced()?;
+        }
+
+        Ok(parsed)
+    }
+
+    /// Перечисление { up(1), down(2) }, если оно есть
+    fn parse_named_numbers(&mut self) -> Result<Vec<EnumValue>> {
+        let mut values = Vec::new();
+        if !self.is_symbol("{") {
+            return Ok(values);
+        }
+        self.next()?;
+
+        loop {
+            match self.next()? {
+                Token::Symbol("}") => break,
+                Token::Symbol(",") => {}
+                Token::Ident(label) => {
+                    self.expect_symbol("(")?;
+                    let value = self.expect_number()?;
+                    self.expect_symbol(")")?;
+                    values.push(EnumValue { value, label });
+                }
+                other => bail!(
+                    "строка {}: неожиданный токен в перечислении: {:?}",
+                    self.line(),
+                    other
+                ),
+            }
+        }
+
+        Ok(values)
+    }
+
+    /// Ограничение (SIZE (a..b | c)) - возвращает диапазоны SIZE, прочее пропускает
+    fn parse_constraint(&mut self) -> Result<Vec<(u64, u64)>> {
+        if !self.is_symbol("(") {
+            return Ok(Vec::new());
+        }
+        if !matches!(self.peek_at(1), Some(Token::Ident(s)) if s == "SIZE") {
+            self.skip_balanced()?;
+            return Ok(Vec::new());
+        }
+
+        self.next()?;
+        self.next()?;
+        self.expect_symbol("(")?;
+        let mut sizes = Vec::new();
+
+        loop {
+            match self.next()? {
+                Token::Symbol(")") => break,
+                Token::Symbol("|") => {}
+                Token::Number(min) => {
+                    let max = if self.is_symbol("..") {
+                        self.next()?;
+                        self.expect_number()?
+                    } else {
+                        min
+                    };
+                    sizes.push((min.max(0) as u64, max.max(0) as u64));
+                }
+                other => bail!(
+                    "строка {}: неожиданный токен в SIZE: {:?}",
+                    self.line(),
+                    other
+                ),
+            }
+        }
+        self.expect_symbol(")")?;
+
+        Ok(sizes)
+    }
+}
+
+fn to_arc(n: i64, line: usize) -> Result<u32> {
+    u32::try_from(n).map_err(|_| anyhow!("строка {}: некорректный компонент OID {}", line, n))
+}
+
+/// Убирает отступы из многострочного DESCRIPTION
+fn normalize_text(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
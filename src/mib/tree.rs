@@ -0,0 +1,524 @@
+use anyhow::{Result, anyhow};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use super::parser::parse_mib;
+use super::types::{
+    BaseType, EnumValue, MibNode, NodeKind, NodeRole, OidComponent, ParsedModule, ParsedObject,
+    ParsedType, Syntax, TypeRef, format_oid, parse_numeric_oid,
+};
+
+/// Корни дерева, которые не определяются ни в одном модуле
+const ROOTS: &[(&str, u32)] = &[("ccitt", 0), ("iso", 1), ("joint-iso-ccitt", 2)];
+
+/// Расширения файлов, которые считаются MIB модулями (пустое - файл без расширения)
+const MIB_EXTENSIONS: &[&str] = &["", "txt", "mib", "my", "smi"];
+
+/// Результат загрузки каталогов с MIB
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LoadReport {
+    pub files: usize,
+    pub modules: usize,
+    pub nodes: usize,
+    /// Ошибки разбора файлов и неразрешенные имена
+    pub errors: Vec<String>,
+}
+
+/// Информация о загруженном модуле
+#[derive(Debug, Clone, Serialize)]
+pub struct ModuleInfo {
+    pub name: String,
+    pub file: Option<PathBuf>,
+    pub objects: usize,
+}
+
+/// OID, разрешенный до ближайшего известного узла: IF-MIB::ifDescr + индекс .1
+#[derive(Debug, Clone)]
+pub struct ResolvedOid<'a> {
+    pub node: &'a MibNode,
+    /// Компоненты после OID узла (индекс строки, .0 для скаляров)
+    pub suffix: Vec<u32>,
+}
+
+impl fmt::Display for ResolvedOid<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.node.qualified_name())?;
+        if !self.suffix.is_empty() {
+            write!(f, ".{}", format_oid(&self.suffix))?;
+        }
+        Ok(())
+    }
+}
+
+/// Дерево OID, построенное из MIB модулей
+#[derive(Debug, Clone, Default)]
+pub struct MibTree {
+    nodes: BTreeMap<Vec<u32>, MibNode>,
+    /// Имя объекта -> OID (одно имя может быть определено в разных модулях)
+    names: HashMap<String, Vec<Vec<u32>>>,
+    modules: BTreeMap<String, ModuleInfo>,
+}
+
+impl MibTree {
+    /// Пустое дерево только с корнями iso/ccitt/joint-iso-ccitt
+    pub fn empty() -> Self {
+        let (tree, _) = Self::build(Vec::new());
+        tree
+    }
+
+    /// Загружает все MIB файлы из каталогов. Ошибки отдельных файлов попадают в отчет
+    pub fn load_dirs(dirs: &[impl AsRef<Path>]) -> (Self, LoadReport) {
+        let mut sources = Vec::new();
+        let mut report = LoadReport::default();
+
+        for dir in dirs {
+            let dir = dir.as_ref();
+            let entries = match std::fs::read_dir(dir) {
+                Ok(entries) => entries,
+                Err(e) => {
+                    report.errors.push(format!(
+                        "Не удалось прочитать каталог {}: {}",
+                        dir.display(),
+                        e
+                    ));
+                    continue;
+                }
+            };
+
+            let mut paths: Vec<PathBuf> = entries
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .filter(|path| path.is_file() && Self::is_mib_file(path))
+                .collect();
+            paths.sort();
+
+            for path in paths {
+                report.files += 1;
+                match std::fs::read_to_string(&path) {
+                    Ok(content) => match parse_mib(&content) {
+                        Ok(modules) => {
+                            sources.extend(modules.into_iter().map(|m| (m, Some(path.clone()))))
+                        }
+                        Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+                    },
+                    Err(e) => report.errors.push(format!("{}: {}", path.display(), e)),
+                }
+            }
+        }
+
+        let (tree, errors) = Self::build(sources);
+        report.errors.extend(errors);
+        report.modules = tree.modules.len();
+        report.nodes = tree.nodes.len();
+
+        (tree, report)
+    }
+
+    fn is_mib_file(path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        MIB_EXTENSIONS.contains(&extension.as_str())
+    }
+
+    /// Строит дерево из разобранных модулей: разрешает имена в OID и типы в базовые
+    pub fn build(sources: Vec<(ParsedModule, Option<PathBuf>)>) -> (Self, Vec<String>) {
+        let mut tree = MibTree::default();
+        let mut errors = Vec::new();
+        let resolver = Resolver::new(&sources);
+
+        for (name, arc) in ROOTS {
+            tree.insert(MibNode {
+                oid: vec![*arc],
+                name: name.to_string(),
+                module: "SNMPv2-SMI".to_string(),
+                kind: NodeKind::ObjectIdentifier,
+                syntax: None,
+                access: None,
+                status: None,
+                description: None,
+                units: None,
+                index: Vec::new(),
+                augments: None,
+            });
+        }
+
+        // (модуль, имя) -> OID. Разрешаем в несколько проходов, т.к. порядок определений произвольный
+        let mut resolved: HashMap<(String, String), Vec<u32>> = HashMap::new();
+        let mut pending: Vec<(&ParsedModule, &ParsedObject)> = sources
+            .iter()
+            .flat_map(|(module, _)| module.objects.iter().map(move |object| (module, object)))
+            .collect();
+
+        loop {
+            let before = pending.len();
+            pending.retain(|(module, object)| {
+                match resolver.resolve_oid(module, &object.oid, &resolved) {
+                    Some(oid) => {
+                        resolved.insert((module.name.clone(), object.name.clone()), oid);
+                        false
+                    }
+                    None => true,
+                }
+            });
+            if pending.is_empty() || pending.len() == before {
+                break;
+            }
+        }
+
+        for (module, object) in &pending {
+            errors.push(format!(
+                "{}::{} (строка {}): не удалось разрешить OID",
+                module.name, object.name, object.line
+            ));
+        }
+
+        for (module, file) in &sources {
+            let mut objects = 0;
+            for object in &module.objects {
+                let Some(oid) = resolved.get(&(module.name.clone(), object.name.clone())) else {
+                    continue;
+                };
+                let syntax = object
+                    .syntax
+                    .as_ref()
+                    .map(|syntax| resolver.resolve_syntax(module, syntax));
+                tree.insert(MibNode {
+                    oid: oid.clone(),
+                    name: object.name.clone(),
+                    module: module.name.clone(),
+                    kind: object.kind,
+                    syntax,
+                    access: object.access.clone(),
+                    status: object.status.clone(),
+                    description: object.description.clone(),
+                    units: object.units.clone(),
+                    index: object.index.clone(),
+                    augments: object.augments.clone(),
+                });
+                objects += 1;
+            }
+            tree.modules.insert(
+                module.name.clone(),
+                ModuleInfo {
+                    name: module.name.clone(),
+                    file: file.clone(),
+                    objects,
+                },
+            );
+        }
+
+        (tree, errors)
+    }
+
+    fn insert(&mut self, node: MibNode) {
+        let paths = self.names.entry(node.name.clone()).or_default();
+        if !paths.contains(&node.oid) {
+            paths.push(node.oid.clone());
+        }
+        self.nodes.insert(node.oid.clone(), node);
+    }
+
+    /// Узел с точно таким OID
+    pub fn node(&self, oid: &[u32]) -> Option<&MibNode> {
+        self.nodes.get(oid)
+    }
+
+    /// Прямые потомки узла
+    pub fn children(&self, oid: &[u32]) -> Vec<&MibNode> {
+        let depth = oid.len() + 1;
+        self.nodes
+            .range(oid.to_vec()..)
+            .skip_while(|(key, _)| key.as_slice() == oid)
+            .take_while(|(key, _)| key.starts_with(oid))
+            .filter(|(key, _)| key.len() == depth)
+            .map(|(_, node)| node)
+            .collect()
+    }
+
+    /// Родитель узла (ближайший известный предок)
+    pub fn parent(&self, oid: &[u32]) -> Option<&MibNode> {
+        (1..oid.len())
+            .rev()
+            .find_map(|len| self.nodes.get(&oid[..len]))
+    }
+
+    /// Роль узла: таблица, строка, колонка или скаляр
+    pub fn role(&self, node: &MibNode) -> NodeRole {
+        if node.is_table() {
+            NodeRole::Table
+        } else if node.is_row() {
+            NodeRole::Row
+        } else if node.kind != NodeKind::ObjectType {
+            NodeRole::Node
+        } else if self.parent(&node.oid).is_some_and(|parent| parent.is_row()) {
+            NodeRole::Column
+        } else {
+            NodeRole::Scalar
+        }
+    }
+
+    /// Разрешает OID до самого длинного известного префикса
+    pub fn resolve(&self, oid: &[u32]) -> Option<ResolvedOid<'_>> {
+        (1..=oid.len()).rev().find_map(|len| {
+            self.nodes.get(&oid[..len]).map(|node| ResolvedOid {
+                node,
+                suffix: oid[len..].to_vec(),
+            })
+        })
+    }
+
+    /// Числовой OID строкой -> MODULE::name.index, если OID известен
+    pub fn format_name(&self, oid_str: &str) -> Option<String> {
+        let oid = parse_numeric_oid(oid_str)?;
+        self.resolve(&oid).map(|resolved| resolved.to_string())
+    }
+
+    /// Ищет узел по имени: "ifDescr" или "IF-MIB::ifDescr"
+    pub fn lookup(&self, name: &str) -> Option<&MibNode> {
+        let (module, object) = match name.split_once("::") {
+            Some((module, object)) => (Some(module), object),
+            None => (None, name),
+        };
+        self.names
+            .get(object)?
+            .iter()
+            .filter_map(|oid| self.nodes.get(oid))
+            .find(|node| node.name == object && module.is_none_or(|m| node.module == m))
+    }
+
+    /// Переводит имя в числовой OID. Принимает числовой OID, "sysUpTime.0",
+    /// "SNMPv2-MIB::sysName.0", "IF-MIB::ifDescr.1"
+    pub fn parse_name(&self, text: &str) -> Result<Vec<u32>> {
+        let text = text.trim();
+        if let Some(oid) = parse_numeric_oid(text) {
+            return Ok(oid);
+        }
+
+        let (qualified, suffix) = Self::split_name(text);
+        let node = self
+            .lookup(qualified)
+            .ok_or_else(|| anyhow!("Неизвестное имя MIB '{}'", qualified))?;
+        let mut oid = node.oid.clone();
+        if !suffix.is_empty() {
+            let suffix = parse_numeric_oid(suffix)
+                .ok_or_else(|| anyhow!("Некорректный индекс '{}' в '{}'", suffix, text))?;
+            oid.extend(suffix);
+        }
+
+        Ok(oid)
+    }
+
+    /// Делит имя на объект и суффикс: "IF-MIB::ifDescr.1" -> ("IF-MIB::ifDescr", "1").
+    /// Имя может содержать точки только в суффиксе
+    pub fn split_name(text: &str) -> (&str, &str) {
+        match text.find("::") {
+            Some(pos) => {
+                let after = &text[pos + 2..];
+                match after.find('.') {
+                    Some(dot) => (&text[..pos + 2 + dot], &after[dot + 1..]),
+                    None => (text, ""),
+                }
+            }
+            None => match text.split_once('.') {
+                Some((name, suffix)) => (name, suffix),
+                None => (text, ""),
+            },
+        }
+    }
+
+    /// Имена всех объектов, начинающиеся с префикса (для подсказок)
+    pub fn search(&self, prefix: &str, limit: usize) -> Vec<&MibNode> {
+        let mut names: Vec<&String> = self
+            .names
+            .keys()
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        names.sort();
+        names
+            .into_iter()
+            .filter_map(|name| self.lookup(name))
+            .take(limit)
+            .collect()
+    }
+}
+
+/// Поиск имен и типов с учетом IMPORTS
+struct Resolver<'a> {
+    /// (модуль, имя) -> тип
+    types: HashMap<(&'a str, &'a str), &'a ParsedType>,
+    /// имя -> все модули, где оно определено (объект или тип)
+    type_modules: HashMap<&'a str, Vec<&'a str>>,
+    object_modules: HashMap<&'a str, Vec<&'a str>>,
+    modules: HashMap<&'a str, &'a ParsedModule>,
+}
+
+impl<'a> Resolver<'a> {
+    fn new(sources: &'a [(ParsedModule, Option<PathBuf>)]) -> Self {
+        let mut types = HashMap::new();
+        let mut type_modules: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut object_modules: HashMap<&str, Vec<&str>> = HashMap::new();
+        let mut modules = HashMap::new();
+
+        for (module, _) in sources {
+            modules.insert(module.name.as_str(), module);
+            for parsed in &module.types {
+                types.insert((module.name.as_str(), parsed.name.as_str()), parsed);
+                type_modules
+                    .entry(parsed.name.as_str())
+                    .or_default()
+                    .push(module.name.as_str());
+            }
+            for object in &module.objects {
+                object_modules
+                    .entry(object.name.as_str())
+                    .or_default()
+                    .push(module.name.as_str());
+            }
+        }
+
+        Self {
+            types,
+            type_modules,
+            object_modules,
+            modules,
+        }
+    }
+
+    /// Модули, в которых искать имя: сам модуль, затем модуль из IMPORTS, затем любой
+    fn candidate_modules(
+        &self,
+        module: &'a ParsedModule,
+        name: &str,
+        defined_in: &HashMap<&'a str, Vec<&'a str>>,
+    ) -> Vec<&'a str> {
+        let mut candidates = vec![module.name.as_str()];
+        if let Some((_, from)) = module.imports.iter().find(|(symbol, _)| symbol == name) {
+            candidates.push(from.as_str());
+        }
+        if let Some(modules) = defined_in.get(name) {
+            candidates.extend(modules.iter().copied());
+        }
+        candidates
+    }
+
+    fn resolve_oid(
+        &self,
+        module: &'a ParsedModule,
+        components: &[OidComponent],
+        resolved: &HashMap<(String, String), Vec<u32>>,
+    ) -> Option<Vec<u32>> {
+        let mut oid = Vec::new();
+
+        for (pos, component) in components.iter().enumerate() {
+            match component {
+                OidComponent::Number(n) | OidComponent::NamedNumber(_, n) => oid.push(*n),
+                OidComponent::Name(name) if pos == 0 => {
+                    if let Some((_, arc)) = ROOTS.iter().find(|(root, _)| root == name) {
+                        oid.push(*arc);
+                        continue;
+                    }
+                    let parent = self
+                        .candidate_modules(module, name, &self.object_modules)
+                        .into_iter()
+                        .find_map(|m| resolved.get(&(m.to_string(), name.clone())))?;
+                    oid.extend(parent);
+                }
+                OidComponent::Name(_) => return None,
+            }
+        }
+
+        (!oid.is_empty()).then_some(oid)
+    }
+
+    fn find_type(&self, module: &'a ParsedModule, name: &str) -> Option<(&'a ParsedType, &'a str)> {
+        self.candidate_modules(module, name, &self.type_modules)
+            .into_iter()
+            .find_map(|m| self.types.get(&(m, name)).map(|parsed| (*parsed, m)))
+    }
+
+    /// Сводит синтаксис объекта к базовому типу, собирая TC, DISPLAY-HINT и перечисления
+    fn resolve_syntax(&self, module: &'a ParsedModule, syntax: &TypeRef) -> Syntax {
+        let mut result = Syntax {
+            base: BaseType::Unknown,
+            textual_conventions: Vec::new(),
+            display_hint: None,
+            enums: Vec::new(),
+            sizes: Vec::new(),
+        };
+        let mut current = syntax.clone();
+        let mut current_module = module;
+
+        // Ограничиваем глубину на случай циклических определений
+        for _ in 0..16 {
+            match current {
+                TypeRef::Integer { enums } => {
+                    result.base = BaseType::Integer;
+                    Self::merge_enums(&mut result.enums, enums);
+                    break;
+                }
+                TypeRef::OctetString { sizes } => {
+                    result.base = BaseType::OctetString;
+                    if result.sizes.is_empty() {
+                        result.sizes = sizes;
+                    }
+                    break;
+                }
+                TypeRef::ObjectIdentifier => {
+                    result.base = BaseType::ObjectIdentifier;
+                    break;
+                }
+                TypeRef::Bits { bits } => {
+                    result.base = BaseType::Bits;
+                    Self::merge_enums(&mut result.enums, bits);
+                    break;
+                }
+                TypeRef::Sequence => {
+                    result.base = BaseType::Sequence;
+                    break;
+                }
+                TypeRef::SequenceOf(_) => {
+                    result.base = BaseType::SequenceOf;
+                    break;
+                }
+                TypeRef::Other => break,
+                TypeRef::Named { name, enums, sizes } => {
+                    Self::merge_enums(&mut result.enums, enums);
+                    if result.sizes.is_empty() {
+                        result.sizes = sizes;
+                    }
+                    if let Some(base) = BaseType::from_application_name(&name) {
+                        result.base = base;
+                        break;
+                    }
+                    let Some((parsed, defined_in)) = self.find_type(current_module, &name) else {
+                        break;
+                    };
+                    if parsed.is_tc {
+                        result.textual_conventions.push(name.clone());
+                        if result.display_hint.is_none() {
+                            result.display_hint = parsed.display_hint.clone();
+                        }
+                    }
+                    current = parsed.syntax.clone();
+                    // Имена внутри определения типа ищутся в модуле, где он определен
+                    if let Some(next_module) = self.modules.get(defined_in) {
+                        current_module = next_module;
+                    }
+                }
+            }
+        }
+
+        result
+    }
+
+    /// Перечисление из внешнего определения важнее (сужение в OBJECT-TYPE)
+    fn merge_enums(target: &mut Vec<EnumValue>, enums: Vec<EnumValue>) {
+        if target.is_empty() {
+            *target = enums;
+        }
+    }
+}
@@ -0,0 +1,270 @@
+use serde::{Serialize, Serializer};
+use std::fmt;
+
+/// Вид узла дерева MIB
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    /// name OBJECT IDENTIFIER ::= { ... }
+    ObjectIdentifier,
+    ModuleIdentity,
+    ObjectIdentity,
+    ObjectType,
+    NotificationType,
+    /// OBJECT-GROUP, NOTIFICATION-GROUP, MODULE-COMPLIANCE, AGENT-CAPABILITIES
+    Conformance,
+}
+
+/// Роль объекта в структуре данных агента
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeRole {
+    /// SEQUENCE OF - таблица
+    Table,
+    /// Entry с INDEX/AUGMENTS
+    Row,
+    /// Объект внутри строки таблицы
+    Column,
+    /// OBJECT-TYPE вне таблиц, читается как name.0
+    Scalar,
+    /// Ветка дерева без данных (OBJECT IDENTIFIER, MODULE-IDENTITY, группы и т.п.)
+    Node,
+}
+
+/// Базовый тип SMI, к которому сводится синтаксис объекта
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BaseType {
+    Integer,
+    OctetString,
+    ObjectIdentifier,
+    Bits,
+    IpAddress,
+    Counter32,
+    Counter64,
+    Gauge32,
+    Unsigned32,
+    TimeTicks,
+    Opaque,
+    Sequence,
+    SequenceOf,
+    Unknown,
+}
+
+impl BaseType {
+    /// Базовые типы, которые определены в SNMPv2-SMI/RFC1155-SMI через [APPLICATION n]
+    pub fn from_application_name(name: &str) -> Option<Self> {
+        match name {
+            "IpAddress" | "NetworkAddress" => Some(BaseType::IpAddress),
+            "Counter32" | "Counter" => Some(BaseType::Counter32),
+            "Counter64" => Some(BaseType::Counter64),
+            "Gauge32" | "Gauge" => Some(BaseType::Gauge32),
+            "Unsigned32" => Some(BaseType::Unsigned32),
+            "TimeTicks" => Some(BaseType::TimeTicks),
+            "Opaque" => Some(BaseType::Opaque),
+            "Integer32" => Some(BaseType::Integer),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for BaseType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BaseType::Integer => "INTEGER",
+            BaseType::OctetString => "OCTET STRING",
+            BaseType::ObjectIdentifier => "OBJECT IDENTIFIER",
+            BaseType::Bits => "BITS",
+            BaseType::IpAddress => "IpAddress",
+            BaseType::Counter32 => "Counter32",
+            BaseType::Counter64 => "Counter64",
+            BaseType::Gauge32 => "Gauge32",
+            BaseType::Unsigned32 => "Unsigned32",
+            BaseType::TimeTicks => "TimeTicks",
+            BaseType::Opaque => "Opaque",
+            BaseType::Sequence => "SEQUENCE",
+            BaseType::SequenceOf => "SEQUENCE OF",
+            BaseType::Unknown => "UNKNOWN",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Serialize for BaseType {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Именованное значение перечисления или бит BITS
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct EnumValue {
+    pub value: i64,
+    pub label: String,
+}
+
+/// Синтаксис объекта, сведенный к базовому типу
+#[derive(Debug, Clone, Serialize)]
+pub struct Syntax {
+    pub base: BaseType,
+    /// Цепочка textual conventions от внешней к внутренней, например ["MacAddress"]
+    pub textual_conventions: Vec<String>,
+    pub display_hint: Option<String>,
+    pub enums: Vec<EnumValue>,
+    /// Ограничения SIZE (min, max)
+    pub sizes: Vec<(u64, u64)>,
+}
+
+impl Syntax {
+    /// Объект использует textual convention с этим именем (на любом уровне)
+    pub fn is_tc(&self, name: &str) -> bool {
+        self.textual_conventions.iter().any(|tc| tc == name)
+    }
+
+    /// Фиксированная длина строки, если SIZE задан одним значением
+    pub fn fixed_size(&self) -> Option<u64> {
+        match self.sizes.as_slice() {
+            [(min, max)] if min == max => Some(*min),
+            _ => None,
+        }
+    }
+
+    /// Метка перечисления для значения
+    pub fn enum_label(&self, value: i64) -> Option<&str> {
+        self.enums
+            .iter()
+            .find(|e| e.value == value)
+            .map(|e| e.label.as_str())
+    }
+}
+
+/// Компонент INDEX строки таблицы
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexPart {
+    pub name: String,
+    pub implied: bool,
+}
+
+/// Узел дерева MIB
+#[derive(Debug, Clone, Serialize)]
+pub struct MibNode {
+    pub oid: Vec<u32>,
+    pub name: String,
+    pub module: String,
+    pub kind: NodeKind,
+    pub syntax: Option<Syntax>,
+    pub access: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub units: Option<String>,
+    pub index: Vec<IndexPart>,
+    pub augments: Option<String>,
+}
+
+impl MibNode {
+    /// OID узла строкой
+    pub fn oid_string(&self) -> String {
+        format_oid(&self.oid)
+    }
+
+    /// Полное имя MODULE::name
+    pub fn qualified_name(&self) -> String {
+        format!("{}::{}", self.module, self.name)
+    }
+
+    /// Таблица: SYNTAX SEQUENCE OF ...
+    pub fn is_table(&self) -> bool {
+        self.syntax
+            .as_ref()
+            .is_some_and(|s| s.base == BaseType::SequenceOf)
+    }
+
+    /// Строка таблицы: есть INDEX или AUGMENTS
+    pub fn is_row(&self) -> bool {
+        !self.index.is_empty() || self.augments.is_some()
+    }
+}
+
+/// OID из тела модуля до разрешения имен: { ifEntry 2 }, { iso org(3) dod(6) 1 }
+#[derive(Debug, Clone, PartialEq)]
+pub enum OidComponent {
+    Name(String),
+    Number(u32),
+    NamedNumber(String, u32),
+}
+
+/// Синтаксис в том виде, как он записан в модуле
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeRef {
+    Integer {
+        enums: Vec<EnumValue>,
+    },
+    OctetString {
+        sizes: Vec<(u64, u64)>,
+    },
+    ObjectIdentifier,
+    Bits {
+        bits: Vec<EnumValue>,
+    },
+    Sequence,
+    SequenceOf(String),
+    /// Ссылка на тип по имени: DisplayString, Counter32, InterfaceIndex
+    Named {
+        name: String,
+        enums: Vec<EnumValue>,
+        sizes: Vec<(u64, u64)>,
+    },
+    /// CHOICE и прочее, что нам не нужно
+    Other,
+}
+
+/// Объект (узел с OID) из модуля
+#[derive(Debug, Clone)]
+pub struct ParsedObject {
+    pub name: String,
+    pub kind: NodeKind,
+    pub oid: Vec<OidComponent>,
+    pub syntax: Option<TypeRef>,
+    pub access: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub units: Option<String>,
+    pub index: Vec<IndexPart>,
+    pub augments: Option<String>,
+    pub line: usize,
+}
+
+/// Определение типа: TEXTUAL-CONVENTION или Name ::= TYPE
+#[derive(Debug, Clone)]
+pub struct ParsedType {
+    pub name: String,
+    pub syntax: TypeRef,
+    pub display_hint: Option<String>,
+    pub is_tc: bool,
+}
+
+/// Разобранный MIB модуль
+#[derive(Debug, Clone)]
+pub struct ParsedModule {
+    pub name: String,
+    /// Импортированное имя -> модуль
+    pub imports: Vec<(String, String)>,
+    pub objects: Vec<ParsedObject>,
+    pub types: Vec<ParsedType>,
+}
+
+/// Числовой OID строкой: [1, 3, 6, 1] -> "1.3.6.1"
+pub fn format_oid(oid: &[u32]) -> String {
+    oid.iter()
+        .map(|arc| arc.to_string())
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Разбирает числовой OID: "1.3.6.1" или ".1.3.6.1"
+pub fn parse_numeric_oid(text: &str) -> Option<Vec<u32>> {
+    let text = text.trim().trim_start_matches('.');
+    if text.is_empty() {
+        return None;
+    }
+    text.split('.').map(|arc| arc.parse::<u32>().ok()).collect()
+}
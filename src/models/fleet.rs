@@ -0,0 +1,12 @@
+use serde::Deserialize;
+
+/// Опрос устройств инвентаря; без имен и тега - всех
+#[derive(Debug, Default, Deserialize)]
+pub struct FleetRequest {
+    #[serde(default)]
+    pub names: Vec<String>,
+    pub tag: Option<String>,
+    /// Добавить в таблицы сырой список varbind'ов
+    #[serde(default)]
+    pub include_varbinds: bool,
+}
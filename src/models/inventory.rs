@@ -0,0 +1,20 @@
+use serde::Deserialize;
+
+use crate::inventory::InventoryFormat;
+
+/// Отбор устройств инвентаря
+#[derive(Debug, Default, Deserialize)]
+pub struct InventoryQuery {
+    /// Только устройства с этим тегом
+    pub tag: Option<String>,
+}
+
+/// Загрузка устройств из присланного текста YAML или CSV
+#[derive(Debug, Deserialize)]
+pub struct InventoryImportRequest {
+    pub format: InventoryFormat,
+    pub content: String,
+    /// Удалить устройства, которых нет в тексте
+    #[serde(default)]
+    pub replace: bool,
+}
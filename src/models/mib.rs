@@ -0,0 +1,99 @@
+use serde::{Deserialize, Serialize};
+
+use crate::mib::{IndexPart, MibNode, MibTree, NodeKind, NodeRole, Syntax};
+
+/// Запрос перевода: числовой OID или имя ("1.3.6.1.2.1.1.5.0", "SNMPv2-MIB::sysName.0", "ifDescr.3")
+#[derive(Debug, Deserialize)]
+pub struct TranslateQuery {
+    pub oid: String,
+}
+
+/// Запрос узла дерева, без oid - iso(1)
+#[derive(Debug, Deserialize)]
+pub struct NodeQuery {
+    pub oid: Option<String>,
+}
+
+/// Поиск объектов по началу имени
+#[derive(Debug, Deserialize)]
+pub struct SearchQuery {
+    pub q: String,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TranslateResponse {
+    pub input: String,
+    pub oid: String,
+    /// MODULE::name.index, если OID известен MIB
+    pub name: Option<String>,
+    /// Ближайший известный объект
+    pub object: Option<NodeSummary>,
+    /// Часть OID после объекта (индекс строки или .0 скаляра)
+    pub index: Option<String>,
+}
+
+/// Краткое описание узла для списков
+#[derive(Debug, Serialize)]
+pub struct NodeSummary {
+    pub oid: String,
+    pub name: String,
+    pub module: String,
+    pub kind: NodeKind,
+    pub role: NodeRole,
+    pub has_children: bool,
+}
+
+impl NodeSummary {
+    pub fn new(node: &MibNode, tree: &MibTree) -> Self {
+        Self {
+            oid: node.oid_string(),
+            name: node.name.clone(),
+            module: node.module.clone(),
+            kind: node.kind,
+            role: tree.role(node),
+            has_children: !tree.children(&node.oid).is_empty(),
+        }
+    }
+}
+
+/// Узел дерева с синтаксисом, описанием, родителем и потомками
+#[derive(Debug, Serialize)]
+pub struct NodeResponse {
+    #[serde(flatten)]
+    pub summary: NodeSummary,
+    pub syntax: Option<Syntax>,
+    pub access: Option<String>,
+    pub status: Option<String>,
+    pub description: Option<String>,
+    pub units: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub index: Vec<IndexPart>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub augments: Option<String>,
+    pub parent: Option<NodeSummary>,
+    pub children: Vec<NodeSummary>,
+}
+
+impl NodeResponse {
+    pub fn new(node: &MibNode, tree: &MibTree) -> Self {
+        Self {
+            summary: NodeSummary::new(node, tree),
+            syntax: node.syntax.clone(),
+            access: node.access.clone(),
+            status: node.status.clone(),
+            description: node.description.clone(),
+            units: node.units.clone(),
+            index: node.index.clone(),
+            augments: node.augments.clone(),
+            parent: tree
+                .parent(&node.oid)
+                .map(|parent| NodeSummary::new(parent, tree)),
+            children: tree
+                .children(&node.oid)
+                .into_iter()
+                .map(|child| NodeSummary::new(child, tree))
+                .collect(),
+        }
+    }
+}
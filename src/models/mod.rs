@@ -1,3 +1,11 @@
+pub mod fleet;
+pub mod inventory;
+pub mod mib;
+pub mod profiles;
 pub mod snmpv2c;
 
+pub use fleet::FleetRequest;
+pub use inventory::{InventoryImportRequest, InventoryQuery};
+pub use mib::{NodeQuery, NodeResponse, NodeSummary, SearchQuery, TranslateQuery, TranslateResponse};
+pub use profiles::LintRequest;
 pub use snmpv2c::Snmpv2c;
@@ -0,0 +1,10 @@
+use serde::Deserialize;
+
+/// Проверка профилей: файл из каталога профилей, присланный текст или весь каталог
+#[derive(Debug, Default, Deserialize)]
+pub struct LintRequest {
+    /// Путь к профилю внутри каталога профилей
+    pub path: Option<String>,
+    /// Текст профиля; extends/include ищутся от path или от каталога профилей
+    pub content: Option<String>,
+}
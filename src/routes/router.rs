@@ -1,12 +1,28 @@
 use axum::{Router, routing::{get, post}};
 use tower_http::trace::TraceLayer;
 
-use crate::handlers::{health, handle_snmpv2c};
+use crate::handlers::{
+    collect_fleet, create_device, delete_device, get_device, handle_snmpv2c, health,
+    import_devices, lint_profiles, list_devices, mib_node, mib_search, mib_translate,
+    scheduler_status, update_device,
+};
 
 pub fn create_router() -> Router {
     Router::new()
         .route("/", get(|| async { "Hello, World!" }))
         .route("/health", get(health))
         .route("/home", post(handle_snmpv2c))
+        .route("/mib/translate", get(mib_translate))
+        .route("/mib/node", get(mib_node))
+        .route("/mib/search", get(mib_search))
+        .route("/profiles/lint", post(lint_profiles))
+        .route("/scheduler", get(scheduler_status))
+        .route("/inventory", get(list_devices).post(create_device))
+        .route("/inventory/import", post(import_devices))
+        .route("/fleet/collect", post(collect_fleet))
+        .route(
+            "/inventory/{name}",
+            get(get_device).put(update_device).delete(delete_device),
+        )
         .layer(TraceLayer::new_for_http())
 }
\ No newline at end of file
@@ -0,0 +1,539 @@
+use anyhow::{Context, Result};
+use std::collections::{HashMap, HashSet};
+use std::hash::BuildHasher;
+use std::path::Path;
+use std::sync::Arc;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant, sleep_until, timeout};
+
+mod output;
+mod schedule;
+mod status;
+
+pub use output::{Outputs, PollRecord};
+pub use schedule::ScheduleFile;
+pub use status::{JobStatus, SchedulerStatus};
+
+use crate::collector::{CapabilityCache, CounterRates, MonitoringResult, SnmpCollector};
+use crate::config::{Profile, reload};
+use crate::formatter::json::JsonFormatter;
+use crate::inventory::{self, Device, Inventory};
+
+/// Имя основной задачи устройства: все записи профиля, кроме вынесенных в группы
+const MAIN_JOB: &str = "main";
+/// Предельное время опроса по умолчанию, в интервалах: опрос дольше интервала
+/// доводится до конца и отмечается как overrun, а не обрывается
+const TIMEOUT_INTERVALS: u64 = 3;
+
+/// Задача опроса: устройство целиком или одна группа метрик устройства
+struct Job {
+    device: Device,
+    /// None - основной опрос
+    group: Option<String>,
+    /// Общий для всех задач устройства: одновременно идет только один опрос
+    busy: Arc<tokio::sync::Mutex<()>>,
+    /// Профиль, с которым последний раз сверены записи группы
+    checked: std::sync::Mutex<Option<String>>,
+}
+
+impl Job {
+    fn name(&self) -> &str {
+        self.group.as_deref().unwrap_or(MAIN_JOB)
+    }
+
+    fn interval(&self, schedule: &ScheduleFile, profile: Option<&str>) -> u64 {
+        match &self.group {
+            Some(group) => schedule.groups[group].interval,
+            None => schedule.device_interval(&self.device, profile),
+        }
+    }
+
+    /// Оставляет в профиле записи этой задачи
+    fn narrow(&self, schedule: &ScheduleFile, profile: &mut Profile) {
+        match &self.group {
+            Some(group) => {
+                self.check_entries(group, &schedule.groups[group].entries, profile);
+                let keep = Self::expand(profile, &schedule.groups[group].entries);
+                profile.retain_entries(|entry| keep.contains(entry));
+            }
+            None => {
+                let grouped: Vec<String> = schedule.grouped_entries().into_iter().collect();
+                let skip = Self::expand(profile, &grouped);
+                profile.retain_entries(|entry| !skip.contains(entry));
+            }
+        }
+    }
+
+    /// Предупреждает о записях группы, которых нет в профиле устройства. Сверяется
+    /// при первом опросе и после смены профиля (перезагрузка, другой выбор)
+    fn check_entries(&self, group: &str, entries: &[String], profile: &Profile) {
+        let mut checked = self.checked.lock().unwrap_or_else(|e| e.into_inner());
+        if checked.as_deref() == Some(profile.name.as_str()) {
+            return;
+        }
+        *checked = Some(profile.name.clone());
+
+        let missing: Vec<&str> = entries
+            .iter()
+            .filter(|entry| !profile.has_entry(entry))
+            .map(String::as_str)
+            .collect();
+        if missing.len() == entries.len() {
+            tracing::warn!(
+                "{}/{}: в профиле {} нет ни одной записи группы, опрашивать нечего",
+                self.device.name,
+                group,
+                profile.name
+            );
+        } else if !missing.is_empty() {
+            tracing::warn!(
+                "{}/{}: в профиле {} нет записей группы: {}",
+                self.device.name,
+                group,
+                profile.name,
+                missing.join(", ")
+            );
+        }
+    }
+
+    /// Записи вместе с таблицами входящих в них объединений
+    fn expand(profile: &Profile, entries: &[String]) -> HashSet<String> {
+        let mut expanded: HashSet<String> = entries.iter().cloned().collect();
+        for entry in entries {
+            if let Some(join) = entry
+                .strip_prefix("joins.")
+                .and_then(|name| profile.joins.get(name))
+            {
+                expanded.extend(join.table_names().map(|table| format!("tables.{}", table)));
+            }
+        }
+        expanded
+    }
+}
+
+/// Запущенные задачи планировщика по устройствам
+struct Jobs {
+    schedule: Arc<ScheduleFile>,
+    outputs: Arc<Outputs>,
+    /// Имя устройства -> устройство и его задачи (основная и группы)
+    running: HashMap<String, (Device, Vec<JoinHandle<()>>)>,
+    /// Устройства инвентаря, которые заслоняет одноименное устройство расписания
+    shadowed: HashSet<String>,
+}
+
+impl Jobs {
+    /// Запускает основную задачу и задачи групп устройства
+    fn start(&mut self, device: Device) {
+        let busy = Arc::new(tokio::sync::Mutex::new(()));
+        let groups = std::iter::once(None).chain(self.schedule.groups.keys().cloned().map(Some));
+        let mut handles = Vec::new();
+        for group in groups {
+            let job = Job {
+                device: device.clone(),
+                group,
+                busy: busy.clone(),
+                checked: std::sync::Mutex::new(None),
+            };
+            let interval = job.interval(&self.schedule, None);
+            SchedulerStatus::register(JobStatus::new(
+                &device.name,
+                job.name(),
+                &device.target(),
+                interval,
+            ));
+            handles.push(tokio::spawn(Scheduler::run(
+                job,
+                self.schedule.clone(),
+                self.outputs.clone(),
+            )));
+        }
+        self.running.insert(device.name.clone(), (device, handles));
+    }
+
+    /// Останавливает задачи устройства, в том числе идущий опрос
+    fn stop(&mut self, name: &str) -> Option<Device> {
+        let (device, handles) = self.running.remove(name)?;
+        for handle in handles {
+            handle.abort();
+        }
+        SchedulerStatus::remove(name);
+        Some(device)
+    }
+
+    /// Приводит задачи устройств инвентаря к его содержимому: новые запускаются,
+    /// удаленные останавливаются, измененные перезапускаются.
+    /// Возвращает число добавленных, удаленных и измененных устройств
+    fn sync(&mut self, inventory: Vec<Device>) -> (usize, usize, usize) {
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        let listed: HashSet<String> = inventory.iter().map(|device| device.name.clone()).collect();
+        let stale: Vec<String> = self
+            .running
+            .keys()
+            .filter(|name| !listed.contains(*name) && !self.is_own(name))
+            .cloned()
+            .collect();
+        for name in stale {
+            if let Some(device) = self.stop(&name) {
+                Self::forget(&device);
+                removed += 1;
+            }
+        }
+        self.shadowed.retain(|name| listed.contains(name));
+
+        for device in inventory {
+            if self.is_own(&device.name) {
+                if self.shadowed.insert(device.name.clone()) {
+                    tracing::warn!(
+                        "Устройство {} есть и в расписании, и в инвентаре, берется из расписания",
+                        device.name
+                    );
+                }
+                continue;
+            }
+            match self.running.get(&device.name) {
+                Some((running, _)) if *running == device => {}
+                Some(_) => {
+                    if let Some(previous) = self.stop(&device.name)
+                        && previous.target() != device.target()
+                    {
+                        Self::forget(&previous);
+                    }
+                    self.start(device);
+                    changed += 1;
+                }
+                None => {
+                    self.start(device);
+                    added += 1;
+                }
+            }
+        }
+        (added, removed, changed)
+    }
+
+    /// Устройство из файла расписания: инвентарь его не меняет
+    fn is_own(&self, name: &str) -> bool {
+        self.schedule
+            .devices
+            .iter()
+            .any(|device| device.name == name)
+    }
+
+    /// Остановленный опрос мог успеть записать состояние по старому адресу
+    fn forget(device: &Device) {
+        CounterRates::forget(&device.target());
+        CapabilityCache::invalidate(&device.target());
+    }
+}
+
+/// Периодический опрос устройств из файла расписания
+pub struct Scheduler;
+
+impl Scheduler {
+    /// Читает расписание и запускает задачи опроса в фоне: устройства из расписания
+    /// и из инвентаря. Изменения инвентаря применяются сразу: задачи добавленных
+    /// устройств запускаются, удаленных - останавливаются, измененных - перезапускаются
+    pub fn spawn(path: &str, profiles_dir: &str) -> Result<()> {
+        let schedule = Arc::new(ScheduleFile::load(path, Path::new(profiles_dir))?);
+        SchedulerStatus::clear();
+        let mut jobs = Jobs {
+            outputs: Arc::new(Outputs::new(schedule.outputs.clone())),
+            schedule: schedule.clone(),
+            running: HashMap::new(),
+            shadowed: HashSet::new(),
+        };
+        for device in &schedule.devices {
+            jobs.start(device.clone());
+        }
+
+        let inventory = inventory::global();
+        // Подписка до чтения: изменение между ними не потеряется
+        let changes = inventory.as_ref().map(|inventory| inventory.subscribe());
+        if let Some(inventory) = &inventory {
+            jobs.sync(inventory.list().context("Не удалось прочитать инвентарь")?);
+        }
+
+        tracing::info!(
+            "Планировщик: {} устройств, {} задач, расписание {}",
+            jobs.running.len(),
+            jobs.running
+                .values()
+                .map(|(_, handles)| handles.len())
+                .sum::<usize>(),
+            path
+        );
+        if let (Some(inventory), Some(changes)) = (inventory, changes) {
+            tokio::spawn(Self::follow(inventory, changes, jobs));
+        }
+        Ok(())
+    }
+
+    /// Перечитывает инвентарь после каждого его изменения. Несколько изменений подряд
+    /// сливаются в одно перечитывание
+    async fn follow(inventory: Arc<Inventory>, mut changes: watch::Receiver<u64>, mut jobs: Jobs) {
+        while changes.changed().await.is_ok() {
+            let inventory = inventory.clone();
+            match tokio::task::spawn_blocking(move || inventory.list()).await {
+                Ok(Ok(devices)) => {
+                    let (added, removed, changed) = jobs.sync(devices);
+                    if added + removed + changed > 0 {
+                        tracing::info!(
+                            "Планировщик: инвентарь изменился, добавлено {}, удалено {}, изменено {}",
+                            added,
+                            removed,
+                            changed
+                        );
+                    }
+                }
+                Ok(Err(e)) => tracing::warn!("Планировщик: инвентарь не прочитан: {:#}", e),
+                Err(e) => tracing::warn!("Планировщик: инвентарь не прочитан: {}", e),
+            }
+        }
+    }
+
+    /// Цикл задачи. Первый опрос - в случайный момент внутри интервала, чтобы
+    /// устройства не опрашивались все разом
+    async fn run(job: Job, schedule: Arc<ScheduleFile>, outputs: Arc<Outputs>) {
+        let device = job.device.name.clone();
+        let name = job.name().to_string();
+        let mut interval = job.interval(&schedule, None);
+        let mut next = Instant::now() + Duration::from_secs(interval).mul_f64(Self::random());
+
+        loop {
+            SchedulerStatus::update(&device, &name, |status| {
+                status.next_run = Some(Self::wall_clock(next));
+            });
+            sleep_until(next).await;
+
+            // Пока идет другая задача устройства, запуск ждет ее, но не дольше интервала:
+            // иначе группа с коротким интервалом не попадала бы между основными опросами
+            let Ok(_guard) = timeout(Duration::from_secs(interval), job.busy.lock()).await else {
+                tracing::warn!(
+                    "{}/{}: устройство занято другим опросом дольше интервала, запуск пропущен",
+                    device,
+                    name
+                );
+                SchedulerStatus::update(&device, &name, |status| status.overlaps += 1);
+                next = Self::next_run(next, interval, schedule.defaults.jitter).0;
+                continue;
+            };
+
+            let started_at = chrono::Utc::now();
+            let started = Instant::now();
+            let limit = Duration::from_secs(
+                schedule
+                    .defaults
+                    .timeout
+                    .unwrap_or(interval * TIMEOUT_INTERVALS),
+            );
+            let result = timeout(limit, Box::pin(Self::poll(&job, &schedule))).await;
+            let duration = started.elapsed();
+
+            let (status, result, error) = match result {
+                Ok(Ok(result)) => ("ok", Some(result), None),
+                Ok(Err(e)) => ("error", None, Some(format!("{:#}", e))),
+                Err(_) => (
+                    "timeout",
+                    None,
+                    Some(format!("опрос не уложился в {} с", limit.as_secs())),
+                ),
+            };
+            if let Some(error) = &error {
+                tracing::warn!("{}/{}: {}", device, name, error);
+            }
+
+            let profile = result.as_ref().map(|result| result.profile.clone());
+            if job.group.is_none() && profile.is_some() {
+                interval = job.interval(&schedule, profile.as_deref());
+            }
+            let overrun = duration > Duration::from_secs(interval);
+            let (following, missed) = Self::next_run(next, interval, schedule.defaults.jitter);
+            next = following;
+            if overrun {
+                tracing::warn!(
+                    "{}/{}: опрос шел {:.1} с при интервале {} с, пропущено запусков: {}",
+                    device,
+                    name,
+                    duration.as_secs_f64(),
+                    interval,
+                    missed
+                );
+            }
+            // Удачный, но слишком долгий опрос - отдельный статус, не ошибка
+            let status = if overrun && status == "ok" {
+                "overrun"
+            } else {
+                status
+            };
+
+            SchedulerStatus::update(&device, &name, |job_status| {
+                job_status.interval = interval;
+                job_status.last_started = Some(started_at);
+                job_status.last_duration_ms = Some(duration.as_millis() as u64);
+                job_status.last_status = Some(status.to_string());
+                job_status.last_error = error.clone();
+                job_status.runs += 1;
+                job_status.missed += missed;
+                if error.is_some() {
+                    job_status.failures += 1;
+                }
+                if overrun {
+                    job_status.overruns += 1;
+                }
+                if profile.is_some() {
+                    job_status.profile = profile;
+                }
+            });
+
+            outputs.write(&PollRecord {
+                device: device.clone(),
+                job: name.clone(),
+                target: job.device.target(),
+                started_at: started_at.to_rfc3339(),
+                duration_ms: duration.as_millis() as u64,
+                status: status.to_string(),
+                overrun,
+                missed,
+                error,
+                result: result.as_ref().map(JsonFormatter::format_monitoring_result),
+            });
+        }
+    }
+
+    /// Опрос по текущей конфигурации, в профиле - только записи задачи
+    async fn poll(job: &Job, schedule: &ScheduleFile) -> Result<MonitoringResult> {
+        let snapshot = reload::global();
+        SnmpCollector::collect_for(&job.device, &snapshot, |profile| {
+            job.narrow(schedule, profile)
+        })
+        .await
+    }
+
+    /// Следующий запуск по прошлому плановому со сдвигом ±jitter/2 интервала.
+    /// Запуски, время которых уже прошло, пропускаются; возвращается их число
+    fn next_run(previous: Instant, interval: u64, jitter: f64) -> (Instant, u64) {
+        let period = Duration::from_secs(interval);
+        let mut planned = previous + period;
+        let mut missed = 0;
+        let now = Instant::now();
+        while planned < now {
+            planned += period;
+            missed += 1;
+        }
+
+        let shift = interval as f64 * jitter * (Self::random() - 0.5);
+        let next = if shift < 0.0 {
+            planned
+                .checked_sub(Duration::from_secs_f64(-shift))
+                .unwrap_or(planned)
+        } else {
+            planned + Duration::from_secs_f64(shift)
+        };
+        (next.max(now), missed)
+    }
+
+    /// Случайное число от 0 до 1 без отдельного генератора
+    fn random() -> f64 {
+        let hash =
+            std::collections::hash_map::RandomState::new().hash_one(std::time::Instant::now());
+        hash as f64 / u64::MAX as f64
+    }
+
+    fn wall_clock(at: Instant) -> chrono::DateTime<chrono::Utc> {
+        let delay = at.saturating_duration_since(Instant::now());
+        chrono::Utc::now() + chrono::Duration::from_std(delay).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn job(group: Option<&str>, interval: Option<u64>) -> Job {
+        let mut device: Device = serde_yml::from_str("name: sw1\naddress: 10.0.0.1").unwrap();
+        device.interval = interval;
+        Job {
+            device,
+            group: group.map(String::from),
+            busy: Arc::default(),
+            checked: std::sync::Mutex::new(None),
+        }
+    }
+
+    #[test]
+    fn group_interval_wins() {
+        let schedule: ScheduleFile = serde_yml::from_str(
+            "
+defaults: { interval: 300 }
+profiles: { cisco-ios: 60 }
+groups:
+  interfaces: { interval: 30, entries: [tables.ifTable] }
+",
+        )
+        .unwrap();
+
+        let group = job(Some("interfaces"), Some(120));
+        assert_eq!(group.interval(&schedule, Some("cisco-ios")), 30);
+        assert_eq!(group.name(), "interfaces");
+        let main = job(None, Some(120));
+        assert_eq!(main.interval(&schedule, Some("cisco-ios")), 120);
+        assert_eq!(main.name(), MAIN_JOB);
+        assert_eq!(job(None, None).interval(&schedule, Some("cisco-ios")), 60);
+        assert_eq!(job(None, None).interval(&schedule, None), 300);
+    }
+
+    #[test]
+    fn next_run_without_jitter() {
+        let previous = Instant::now();
+
+        let (next, missed) = Scheduler::next_run(previous, 10, 0.0);
+
+        assert_eq!(next, previous + Duration::from_secs(10));
+        assert_eq!(missed, 0);
+    }
+
+    #[test]
+    fn next_run_skips_missed_ticks() {
+        // Опрос занял 35 секунд при интервале 10: тики на 10, 20 и 30 пропущены
+        let previous = Instant::now() - Duration::from_secs(35);
+
+        let (next, missed) = Scheduler::next_run(previous, 10, 0.0);
+
+        assert_eq!(missed, 3);
+        assert_eq!(next, previous + Duration::from_secs(40));
+    }
+
+    #[test]
+    fn jitter_stays_within_half_interval() {
+        for _ in 0..1000 {
+            let random = Scheduler::random();
+            assert!((0.0..=1.0).contains(&random), "{}", random);
+
+            let previous = Instant::now();
+            let (next, missed) = Scheduler::next_run(previous, 100, 0.5);
+            assert_eq!(missed, 0);
+            assert!(
+                next >= previous + Duration::from_secs(75),
+                "{:?}",
+                next - previous
+            );
+            assert!(
+                next <= previous + Duration::from_secs(125),
+                "{:?}",
+                next - previous
+            );
+        }
+    }
+
+    #[test]
+    fn jitter_never_schedules_in_the_past() {
+        // Планируемый момент почти наступил, сдвиг назад не уводит его в прошлое
+        let previous = Instant::now() - Duration::from_millis(9_900);
+
+        for _ in 0..100 {
+            let before = Instant::now();
+            let (next, _) = Scheduler::next_run(previous, 10, 1.0);
+            assert!(next >= before);
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+
+use super::schedule::OutputConfig;
+use crate::formatter::json::MonitoringResultJson;
+
+/// Результат одного опроса по расписанию
+#[derive(Debug, Clone, Serialize)]
+pub struct PollRecord {
+    pub device: String,
+    /// "main" или имя группы метрик
+    pub job: String,
+    pub target: String,
+    pub started_at: String,
+    pub duration_ms: u64,
+    pub status: String, // "ok" | "overrun" | "error" | "timeout"
+    /// Опрос шел дольше интервала
+    pub overrun: bool,
+    /// Запусков пропущено, пока шел этот опрос
+    pub missed: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub result: Option<MonitoringResultJson>,
+}
+
+/// Выходы планировщика из файла расписания
+pub struct Outputs {
+    outputs: Vec<OutputConfig>,
+}
+
+impl Outputs {
+    pub fn new(outputs: Vec<OutputConfig>) -> Self {
+        Self { outputs }
+    }
+
+    /// Отдает запись во все выходы. Ошибка одного выхода не мешает остальным
+    pub fn write(&self, record: &PollRecord) {
+        for output in &self.outputs {
+            if let Err(e) = Self::write_one(output, record) {
+                tracing::warn!("Выход {:?}: {:#}", output, e);
+            }
+        }
+    }
+
+    fn write_one(output: &OutputConfig, record: &PollRecord) -> Result<()> {
+        match output {
+            OutputConfig::Stdout => {
+                println!("{}", serde_json::to_string(record)?);
+            }
+            OutputConfig::Jsonl { path } => {
+                if let Some(parent) = Path::new(path).parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                let mut file = OpenOptions::new()
+                    .create(true)
+                    .append(true)
+                    .open(path)
+                    .context(format!("Не удалось открыть {}", path))?;
+                writeln!(file, "{}", serde_json::to_string(record)?)?;
+            }
+            OutputConfig::Directory { path } => {
+                fs::create_dir_all(path).context(format!("Не удалось создать каталог {}", path))?;
+                let name = match record.job.as_str() {
+                    "main" => format!("{}.json", record.device),
+                    group => format!("{}.{}.json", record.device, group),
+                };
+                // Через временный файл: читатель не увидит недописанный JSON
+                let file = Path::new(path).join(name);
+                let temporary = file.with_extension("json.tmp");
+                fs::write(&temporary, serde_json::to_string_pretty(record)?)?;
+                fs::rename(&temporary, &file)?;
+            }
+        }
+        Ok(())
+    }
+}
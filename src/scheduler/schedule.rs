@@ -0,0 +1,229 @@
+use anyhow::{Context, Result, bail};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use crate::inventory::Device;
+
+/// Файл расписания периодического опроса.
+///
+/// ```yaml
+/// defaults:
+///   interval: 300          # секунд между опросами устройства
+///   jitter: 0.1            # разброс момента опроса, доля интервала
+/// profiles:                # интервал по имени профиля
+///   cisco-ios: 60
+/// groups:                  # группы метрик со своим интервалом
+///   interfaces:
+///     interval: 30
+///     entries: [tables.ifTable, tables.ifXTable, joins.interfaces]
+/// devices:                # вместе с устройствами инвентаря
+///   - name: core-sw1
+///     address: 10.0.0.1
+///     credentials: core    # набор из credentials в настройках
+///     interval: 120        # важнее интервала профиля
+///   - name: fw1
+///     address: 10.0.0.2
+///     port: 1161
+///     version: v3
+///     profiles: [profiles/generic-endpoint.yaml]
+/// outputs:
+///   - { type: jsonl, path: ./data/results.jsonl }
+///   - { type: directory, path: ./data/latest }
+///   - { type: stdout }
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleFile {
+    #[serde(default)]
+    pub defaults: ScheduleDefaults,
+    /// Имя профиля -> интервал, секунды
+    #[serde(default)]
+    pub profiles: BTreeMap<String, u64>,
+    /// Группы метрик: записи профиля, которые опрашиваются отдельно со своим интервалом
+    #[serde(default)]
+    pub groups: BTreeMap<String, GroupConfig>,
+    /// Устройства в дополнение к инвентарю (см. Device)
+    #[serde(default)]
+    pub devices: Vec<Device>,
+    /// Куда отдавать результаты; пусто - только в лог и статус
+    #[serde(default)]
+    pub outputs: Vec<OutputConfig>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScheduleDefaults {
+    /// Интервал, если не задан ни у устройства, ни у профиля
+    pub interval: u64,
+    /// Случайный сдвиг каждого опроса в пределах ±jitter/2 интервала (0..1)
+    pub jitter: f64,
+    /// Предельное время опроса, секунды; по умолчанию - три интервала
+    pub timeout: Option<u64>,
+}
+
+impl Default for ScheduleDefaults {
+    fn default() -> Self {
+        Self {
+            interval: 300,
+            jitter: 0.1,
+            timeout: None,
+        }
+    }
+}
+
+/// Группа метрик
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GroupConfig {
+    pub interval: u64,
+    /// Записи профиля: "scalars.имя", "tables.имя", "joins.имя", "derived.имя".
+    /// Для объединения в группу входят и его таблицы
+    pub entries: Vec<String>,
+}
+
+/// Куда отдаются результаты опросов
+#[derive(Debug, Clone, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum OutputConfig {
+    /// JSON строкой в stdout
+    Stdout,
+    /// JSON Lines: каждый опрос - строка в конце файла
+    Jsonl { path: String },
+    /// Последний результат устройства в файле <path>/<устройство>[.<группа>].json
+    Directory { path: String },
+}
+
+impl ScheduleFile {
+    /// Читает и проверяет расписание; профили устройств - внутри profiles_dir
+    pub fn load(path: &str, profiles_dir: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .context(format!("Не удалось прочитать файл: {}", path))?;
+        let schedule: Self = serde_yml::from_str(&content)
+            .context(format!("Не удалось распарсить расписание {}", path))?;
+        schedule
+            .validate(profiles_dir)
+            .context(format!("Ошибка в расписании {}", path))?;
+        Ok(schedule)
+    }
+
+    pub fn validate(&self, profiles_dir: &Path) -> Result<()> {
+        if self.defaults.interval == 0 {
+            bail!("defaults.interval должен быть больше 0");
+        }
+        if !(0.0..=1.0).contains(&self.defaults.jitter) {
+            bail!("defaults.jitter - доля интервала от 0 до 1");
+        }
+        if let Some((name, _)) = self.profiles.iter().find(|(_, interval)| **interval == 0) {
+            bail!("profiles.{}: интервал должен быть больше 0", name);
+        }
+
+        let mut grouped = HashSet::new();
+        for (name, group) in &self.groups {
+            if group.interval == 0 {
+                bail!("groups.{}: интервал должен быть больше 0", name);
+            }
+            for entry in &group.entries {
+                if !matches!(
+                    entry.split_once('.'),
+                    Some(("scalars" | "tables" | "joins" | "derived", key)) if !key.is_empty()
+                ) {
+                    bail!(
+                        "groups.{}: '{}' - ожидается scalars.<имя>, tables.<имя>, joins.<имя> или derived.<имя>",
+                        name,
+                        entry
+                    );
+                }
+                if !grouped.insert(entry.as_str()) {
+                    bail!("groups.{}: '{}' уже входит в другую группу", name, entry);
+                }
+            }
+        }
+
+        let mut names = HashSet::new();
+        for device in &self.devices {
+            device.validate(profiles_dir).context("devices")?;
+            if !names.insert(device.name.as_str()) {
+                bail!("devices: имя '{}' повторяется", device.name);
+            }
+        }
+        Ok(())
+    }
+
+    /// Записи, вынесенные в группы: в основной опрос они не попадают
+    pub fn grouped_entries(&self) -> HashSet<String> {
+        self.groups
+            .values()
+            .flat_map(|group| group.entries.iter().cloned())
+            .collect()
+    }
+
+    /// Интервал основного опроса: у устройства, иначе у профиля, иначе по умолчанию
+    pub fn device_interval(&self, device: &Device, profile: Option<&str>) -> u64 {
+        device
+            .interval
+            .or_else(|| self.profiles.get(profile?).copied())
+            .unwrap_or(self.defaults.interval)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn schedule(yaml: &str) -> ScheduleFile {
+        serde_yml::from_str(yaml).unwrap()
+    }
+
+    fn device(yaml: &str) -> Device {
+        serde_yml::from_str(&format!("name: sw1\naddress: 10.0.0.1\n{}", yaml)).unwrap()
+    }
+
+    #[test]
+    fn interval_device_then_profile_then_default() {
+        let schedule = schedule("defaults: { interval: 300 }\nprofiles: { cisco-ios: 60 }\n");
+
+        assert_eq!(
+            schedule.device_interval(&device("interval: 120"), Some("cisco-ios")),
+            120
+        );
+        assert_eq!(schedule.device_interval(&device(""), Some("cisco-ios")), 60);
+        assert_eq!(
+            schedule.device_interval(&device(""), Some("generic-endpoint")),
+            300
+        );
+        assert_eq!(schedule.device_interval(&device(""), None), 300);
+    }
+
+    #[test]
+    fn default_interval_and_jitter() {
+        let schedule = schedule("{}");
+
+        assert_eq!(schedule.defaults.interval, 300);
+        assert_eq!(schedule.defaults.jitter, 0.1);
+        assert!(schedule.validate(Path::new(".")).is_ok());
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let invalid = [
+            "defaults: { interval: 0 }",
+            "defaults: { jitter: 1.5 }",
+            "defaults: { jitter: -0.1 }",
+            "profiles: { cisco-ios: 0 }",
+            "groups: { fast: { interval: 0, entries: [tables.ifTable] } }",
+            "groups: { fast: { interval: 10, entries: [ifTable] } }",
+            "groups:
+  a: { interval: 10, entries: [tables.ifTable] }
+  b: { interval: 20, entries: [tables.ifTable] }",
+        ];
+        for yaml in invalid {
+            assert!(schedule(yaml).validate(Path::new(".")).is_err(), "{}", yaml);
+        }
+        assert!(
+            schedule("defaults: { jitter: 1 }")
+                .validate(Path::new("."))
+                .is_ok()
+        );
+    }
+}
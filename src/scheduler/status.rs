@@ -0,0 +1,97 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{LazyLock, RwLock};
+
+/// Состояние задачи опроса: устройство целиком или группа метрик устройства
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub device: String,
+    /// "main" - основной опрос, иначе имя группы метрик
+    pub job: String,
+    pub target: String,
+    /// Текущий интервал, секунды
+    pub interval: u64,
+    pub next_run: Option<DateTime<Utc>>,
+    pub last_started: Option<DateTime<Utc>>,
+    pub last_duration_ms: Option<u64>,
+    /// "ok" | "overrun" (удачный, но дольше интервала) | "error" | "timeout"
+    pub last_status: Option<String>,
+    pub last_error: Option<String>,
+    /// Профиль последнего удачного опроса
+    pub profile: Option<String>,
+    pub runs: u64,
+    pub failures: u64,
+    /// Опросов дольше интервала
+    pub overruns: u64,
+    /// Пропущенных запусков: предыдущий опрос еще шел
+    pub missed: u64,
+    /// Пропущенных запусков: устройство дольше интервала занято другой задачей
+    pub overlaps: u64,
+}
+
+impl JobStatus {
+    pub fn new(device: &str, job: &str, target: &str, interval: u64) -> Self {
+        Self {
+            device: device.to_string(),
+            job: job.to_string(),
+            target: target.to_string(),
+            interval,
+            next_run: None,
+            last_started: None,
+            last_duration_ms: None,
+            last_status: None,
+            last_error: None,
+            profile: None,
+            runs: 0,
+            failures: 0,
+            overruns: 0,
+            missed: 0,
+            overlaps: 0,
+        }
+    }
+
+    fn key(device: &str, job: &str) -> String {
+        format!("{}/{}", device, job)
+    }
+}
+
+static STATUS: LazyLock<RwLock<BTreeMap<String, JobStatus>>> =
+    LazyLock::new(|| RwLock::new(BTreeMap::new()));
+
+/// Состояние всех задач планировщика, для API и логов
+pub struct SchedulerStatus;
+
+impl SchedulerStatus {
+    /// Заводит задачу заново (при запуске планировщика)
+    pub fn register(status: JobStatus) {
+        let mut jobs = STATUS.write().unwrap_or_else(|e| e.into_inner());
+        jobs.insert(JobStatus::key(&status.device, &status.job), status);
+    }
+
+    pub fn update(device: &str, job: &str, change: impl FnOnce(&mut JobStatus)) {
+        let mut jobs = STATUS.write().unwrap_or_else(|e| e.into_inner());
+        if let Some(status) = jobs.get_mut(&JobStatus::key(device, job)) {
+            change(status);
+        }
+    }
+
+    /// Убирает задачи устройства (устройство удалено или перезапускается)
+    pub fn remove(device: &str) {
+        let mut jobs = STATUS.write().unwrap_or_else(|e| e.into_inner());
+        jobs.retain(|_, status| status.device != device);
+    }
+
+    pub fn clear() {
+        STATUS.write().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+
+    pub fn snapshot() -> Vec<JobStatus> {
+        STATUS
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect()
+    }
+}
@@ -1,77 +1,157 @@
 use anyhow::Result;
+use serde::{Deserialize, Serialize};
 use snmp2::Oid;
-use std::sync::LazyLock;
 
-/// Информация об устройстве
-#[derive(Debug, Clone)]
+use super::vendor_registry;
+
+/// Вклад каждого сигнала в уверенность определения
+const CONFIDENCE_OID_RULE: f32 = 0.4;
+const CONFIDENCE_OID_ENTERPRISE: f32 = 0.2;
+const CONFIDENCE_SYS_DESCR: f32 = 0.3;
+const CONFIDENCE_ENTITY: f32 = 0.3;
+
+/// Информация об устройстве, собранная из sysObjectID, sysDescr и ENTITY-MIB
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DeviceInfo {
     pub device_type: String,
     pub description: String,
+    pub vendor: Option<String>,
+    pub family: Option<String>,
+    pub model: Option<String>,
+    pub os: Option<String>,
+    /// Версия ОС/ПО: из sysDescr или entPhysicalSoftwareRev
+    pub version: Option<String>,
+    /// entPhysicalFirmwareRev
+    pub firmware: Option<String>,
+    pub serial_number: Option<String>,
+    pub sys_object_id: Option<String>,
+    /// sysDescr как есть, для условий профиля (when_sysdescr_matches)
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub sys_descr: Option<String>,
+    /// Номер предприятия IANA из sysObjectID
+    pub enterprise: Option<u32>,
+    /// Уверенность определения от 0 до 1
+    pub confidence: f32,
+    /// Сигналы, по которым определено устройство: sysObjectID, sysDescr, ENTITY-MIB
+    pub signals: Vec<String>,
+}
+
+/// Сведения о шасси из entPhysicalTable (ENTITY-MIB)
+#[derive(Debug, Clone, Default)]
+pub struct EntityInfo {
+    pub model: Option<String>,
+    pub serial_number: Option<String>,
+    pub software_rev: Option<String>,
+    pub firmware_rev: Option<String>,
+    pub manufacturer: Option<String>,
+}
+
+/// Исходные данные для определения устройства
+#[derive(Debug, Clone, Default)]
+pub struct DeviceSignals {
+    pub sys_object_id: Option<String>,
+    pub sys_descr: Option<String>,
+    pub entity: Option<EntityInfo>,
 }
 
 /// Детектор типа устройства
 pub struct DeviceDetector;
 
 impl DeviceDetector {
-    /// Определяет тип устройства по sysObjectID
-    pub fn detect_device_type(sys_object_id: &str) -> DeviceInfo {
-        if sys_object_id.starts_with("1.3.6.1.4.1.8072.") {
-            DeviceInfo {
-                device_type: "linux".to_string(),
-                description: "Linux Net-SNMP Agent".to_string(),
-            }
-        } else if sys_object_id.starts_with("1.3.6.1.4.1.9.") {
-            DeviceInfo {
-                device_type: "cisco".to_string(),
-                description: "Cisco Device".to_string(),
-            }
-        } else if sys_object_id.starts_with("1.3.6.1.4.1.11.") {
-            DeviceInfo {
-                device_type: "hp".to_string(),
-                description: "HP Device".to_string(),
-            }
-        } else if sys_object_id.starts_with("1.3.6.1.4.1.2636.") {
-            DeviceInfo {
-                device_type: "juniper".to_string(),
-                description: "Juniper Device".to_string(),
-            }
-        } else if sys_object_id.starts_with("1.3.6.1.4.1.2011.") {
-            DeviceInfo {
-                device_type: "huawei".to_string(),
-                description: "Huawei Device".to_string(),
-            }
-        } else {
-            DeviceInfo {
-                device_type: "generic".to_string(),
-                description: "Unknown Device".to_string(),
-            }
+    /// Определяет устройство по всем доступным сигналам.
+    /// Производитель и тип: правило sysObjectID, затем sysDescr, ENTITY-MIB и IANA PEN.
+    /// Если sysObjectID указывает на агент (Net-SNMP), sysDescr и ENTITY-MIB важнее правила.
+    /// Модель: ENTITY-MIB точнее всего, затем правило sysObjectID и sysDescr.
+    /// Версия ПО: из sysDescr, затем entPhysicalSoftwareRev
+    pub fn fingerprint(signals: &DeviceSignals) -> DeviceInfo {
+        let registry = vendor_registry::global();
+        let by_oid = signals
+            .sys_object_id
+            .as_deref()
+            .map(|oid| registry.lookup(oid))
+            .unwrap_or_default();
+        let descr_matched = signals
+            .sys_descr
+            .as_deref()
+            .and_then(|descr| registry.match_sys_descr(descr));
+        let entity = signals.entity.clone().unwrap_or_default();
+
+        let mut confidence = 0.0;
+        let mut used = Vec::new();
+        // Правило агента говорит только о ПО SNMP, вклад как у номера IANA
+        if by_oid.matched_prefix.is_some() && !by_oid.agent {
+            confidence += CONFIDENCE_OID_RULE;
+            used.push("sysObjectID".to_string());
+        } else if by_oid.matched_prefix.is_some() || by_oid.enterprise_name.is_some() {
+            confidence += CONFIDENCE_OID_ENTERPRISE;
+            used.push("sysObjectID".to_string());
+        }
+        if descr_matched.is_some() {
+            confidence += CONFIDENCE_SYS_DESCR;
+            used.push("sysDescr".to_string());
+        }
+        let by_descr = descr_matched.unwrap_or_default();
+        if entity.model.is_some() || entity.serial_number.is_some() {
+            confidence += CONFIDENCE_ENTITY;
+            used.push("ENTITY-MIB".to_string());
         }
-    }
-}
 
-/// Простой OID resolver для текущего устройства
-struct DeviceTypeHolder {
-    current_device_type: Option<String>,
-}
+        let (vendor, family, device_type) = if by_oid.agent {
+            (
+                by_descr
+                    .vendor
+                    .or(entity.manufacturer)
+                    .or(by_oid.vendor)
+                    .or(by_oid.enterprise_name),
+                by_descr.family.or(by_oid.family),
+                by_descr.device_type.or(by_oid.device_type),
+            )
+        } else {
+            (
+                by_oid
+                    .vendor
+                    .or(by_descr.vendor)
+                    .or(entity.manufacturer)
+                    .or(by_oid.enterprise_name),
+                by_oid.family.or(by_descr.family),
+                by_oid.device_type.or(by_descr.device_type),
+            )
+        };
+        let model = entity.model.or(by_oid.model).or(by_descr.model);
+        let os = by_descr.os.or(by_oid.os);
 
-impl DeviceTypeHolder {
-    fn new() -> Self {
-        Self {
-            current_device_type: None,
-        }
-    }
+        let description = [&vendor, &family, &model, &os]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(" ");
 
-    fn set_device_type(&mut self, device_type: String) {
-        self.current_device_type = Some(device_type);
+        DeviceInfo {
+            device_type: device_type.unwrap_or_else(|| "generic".to_string()),
+            description: if description.is_empty() {
+                "Unknown Device".to_string()
+            } else {
+                description
+            },
+            vendor,
+            family,
+            model,
+            os,
+            version: by_descr.version.or(entity.software_rev),
+            firmware: entity.firmware_rev,
+            serial_number: entity.serial_number,
+            sys_object_id: signals.sys_object_id.clone(),
+            sys_descr: signals.sys_descr.clone(),
+            enterprise: by_oid.enterprise,
+            confidence: (confidence.min(1.0) * 100.0).round() / 100.0,
+            signals: used,
+        }
     }
 }
 
-/// Глобальный держатель типа устройства
-static GLOBAL_DEVICE_TYPE: LazyLock<std::sync::Mutex<DeviceTypeHolder>> =
-    LazyLock::new(|| std::sync::Mutex::new(DeviceTypeHolder::new()));
-
 /// Парсит строку OID в объект Oid
-pub fn parse_oid(oid_str: &str) -> Result<Oid> {
+pub fn parse_oid(oid_str: &str) -> Result<Oid<'_>> {
     let parts: Result<Vec<u64>, _> = oid_str
         .trim()
         .split('.')
@@ -84,9 +164,3 @@ pub fn parse_oid(oid_str: &str) -> Result<Oid> {
     Oid::from(&parts)
         .map_err(|e| anyhow::anyhow!("Не удалось создать Oid из '{}': {:?}", oid_str, e))
 }
-
-/// Устанавливает глобальный тип устройства
-pub fn set_global_device_type(device_type: String) {
-    let mut holder = GLOBAL_DEVICE_TYPE.lock().unwrap();
-    holder.set_device_type(device_type);
-}
\ No newline at end of file
@@ -5,15 +5,54 @@ pub mod clients_enum;
 pub mod device_profiles;
 pub mod v2c;
 pub mod v3;
+pub mod vendor_registry;
 
 pub use clients_enum::SnmpClient;
-pub use device_profiles::{DeviceDetector, parse_oid, set_global_device_type};
+pub use device_profiles::{DeviceDetector, DeviceInfo, DeviceSignals, EntityInfo, parse_oid};
 pub use v2c::SnmpClientV2c;
 pub use v3::SnmpClientV3;
 
 pub use snmp2::v3::{AuthProtocol, Cipher};
 
+/// Значение varbind'а строкой "ТИП: значение" (как Debug у snmp2::Value).
+/// Непечатаемые OCTET STRING (MAC, DateAndTime) отдаются как "HEX STRING: 00 1A ..",
+/// чтобы байты можно было восстановить при отображении
+pub fn value_to_string(value: &Value<'_>) -> String {
+    match value {
+        Value::OctetString(bytes) => match std::str::from_utf8(bytes) {
+            Ok(text) if !text.chars().any(|c| c.is_control() && !matches!(c, '\t' | '\r' | '\n')) => {
+                format!("OCTET STRING: {}", text)
+            }
+            _ => format!(
+                "HEX STRING: {}",
+                bytes
+                    .iter()
+                    .map(|b| format!("{:02X}", b))
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            ),
+        },
+        other => format!("{:?}", other),
+    }
+}
+
 impl SnmpClient {
+    /// Адрес агента: ключ для кэша возможностей и состояния устройства
+    pub fn target(&self) -> &str {
+        match self {
+            SnmpClient::V2c(client) => &client.target,
+            SnmpClient::V3(client) => &client.target,
+        }
+    }
+
+    /// Версия протокола: "SNMPv2c" или "SNMPv3"
+    pub fn version(&self) -> &'static str {
+        match self {
+            SnmpClient::V2c(_) => "SNMPv2c",
+            SnmpClient::V3(_) => "SNMPv3",
+        }
+    }
+
     pub async fn get(&mut self, oid: &Oid<'_>) -> Result<Value<'_>> {
         match self {
             SnmpClient::V2c(client) => client.get(oid).await,
@@ -21,21 +60,117 @@ impl SnmpClient {
         }
     }
 
-    pub async fn walk(&mut self, root_oid: &Oid<'_>) -> Result<Vec<(Oid<'static>, String)>> {
+    /// GET нескольких OID одним запросом: OID и значение строкой "ТИП: значение"
+    pub async fn get_many(&mut self, oids: &[&Oid<'_>]) -> Result<Vec<(Oid<'static>, String)>> {
         match self {
-            SnmpClient::V2c(client) => client.walk(root_oid).await,
-            SnmpClient::V3(client) => client.walk(root_oid).await,
+            SnmpClient::V2c(client) => client.get_many(oids).await,
+            SnmpClient::V3(client) => client.get_many(oids).await,
         }
     }
 
+    /// Обходит поддерево, но не больше max_items записей (None - без ограничений).
+    /// Забирает на одну запись больше лимита, чтобы отличить обрезанную таблицу от полной
     pub async fn walk_limited(
         &mut self,
         root_oid: &Oid<'_>,
-        max_items: usize,
-    ) -> Result<Vec<(Oid<'static>, String)>> {
-        let all_items = self.walk(root_oid).await?;
-        Ok(all_items.into_iter().take(max_items).collect())
+        max_items: Option<usize>,
+        max_repetitions: u32,
+    ) -> Result<WalkResult> {
+        let fetch_limit = max_items.map(|max| max + 1);
+        let mut items = match self {
+            SnmpClient::V2c(client) => {
+                client
+                    .walk_bulk(root_oid, max_repetitions, fetch_limit)
+                    .await?
+            }
+            SnmpClient::V3(client) => {
+                client
+                    .walk_bulk(root_oid, max_repetitions, fetch_limit)
+                    .await?
+            }
+        };
+
+        let truncated = match max_items {
+            Some(max) if items.len() > max => {
+                items.truncate(max);
+                true
+            }
+            _ => false,
+        };
+
+        Ok(WalkResult { items, truncated })
     }
+
+    /// Обходит таблицу по колонкам, из каждой колонки не больше max_rows строк
+    /// (None - без ограничений). GETBULK идет по колонкам, поэтому лимит на число
+    /// varbind'ов оставил бы только первые колонки
+    pub async fn walk_table(
+        &mut self,
+        entry_oid: &str,
+        max_rows: Option<usize>,
+        max_repetitions: u32,
+    ) -> Result<WalkResult> {
+        let entry = parse_oid(entry_oid)?;
+        let Some(max_rows) = max_rows else {
+            return self.walk_limited(&entry, None, max_repetitions).await;
+        };
+
+        let prefix = format!("{}.", entry_oid.trim_matches('.'));
+        let mut items = Vec::new();
+        let mut truncated = false;
+        let mut start = entry.to_owned();
+        loop {
+            let batch = match self {
+                SnmpClient::V2c(client) => {
+                    client
+                        .walk_bulk_within(&start, &entry, max_repetitions, Some(max_rows + 1))
+                        .await?
+                }
+                SnmpClient::V3(client) => {
+                    client
+                        .walk_bulk_within(&start, &entry, max_repetitions, Some(max_rows + 1))
+                        .await?
+                }
+            };
+            // Колонка первого varbind'а; остальные колонки пачки заберет следующий обход
+            let Some(column) = batch.first().and_then(|(oid, _)| {
+                let oid = oid.to_string();
+                oid.strip_prefix(&prefix)?
+                    .split('.')
+                    .next()?
+                    .parse::<u64>()
+                    .ok()
+            }) else {
+                break;
+            };
+
+            let column_prefix = format!("{}{}.", prefix, column);
+            let rows: Vec<_> = batch
+                .into_iter()
+                .take_while(|(oid, _)| oid.to_string().starts_with(&column_prefix))
+                .collect();
+            // endOfMibView приходит с OID запроса, строк в нем нет
+            if rows.is_empty() {
+                break;
+            }
+            if rows.len() > max_rows {
+                truncated = true;
+            }
+            items.extend(rows.into_iter().take(max_rows));
+
+            let next = format!("{}{}", prefix, column + 1);
+            start = parse_oid(&next)?.to_owned();
+        }
+
+        Ok(WalkResult { items, truncated })
+    }
+}
+
+/// Результат обхода поддерева с лимитом
+pub struct WalkResult {
+    pub items: Vec<(Oid<'static>, String)>,
+    /// true, если обход остановлен по лимиту и в таблице есть еще записи
+    pub truncated: bool,
 }
 
 // TODO: создать фабрику для поддержки выбора версии (v2c/v3) по конфигурации
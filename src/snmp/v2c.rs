@@ -2,7 +2,10 @@ use anyhow::{Context, Result};
 use snmp2::{AsyncSession, Oid, Value};
 
 pub struct SnmpClientV2c {
-    pub(crate) session: AsyncSession,
+    /// В куче: сессия с буферами занимает больше 100 КБ и иначе раздувает future опроса
+    pub(crate) session: Box<AsyncSession>,
+    /// Адрес агента, как его передали при создании
+    pub(crate) target: String,
 }
 
 impl SnmpClientV2c {
@@ -11,11 +14,14 @@ impl SnmpClientV2c {
             .await
             .context("Не удалось создать SNMP сессию")?;
 
-        Ok(Self { session })
+        Ok(Self {
+            session: Box::new(session),
+            target: target.to_string(),
+        })
     }
 
     pub async fn get(&mut self, oid: &Oid<'_>) -> Result<Value<'_>> {
-        let resp = self
+        let mut resp = self
             .session
             .get(oid)
             .await
@@ -23,21 +29,50 @@ impl SnmpClientV2c {
 
         let (_, value) = resp
             .varbinds
-            .into_iter()
             .next()
             .ok_or_else(|| anyhow::anyhow!("SNMP ответ пустой"))?;
 
         Ok(value)
     }
 
-    pub async fn walk(&mut self, start_oid: &Oid<'_>) -> Result<Vec<(Oid<'static>, String)>> {
-        self.walk_bulk(start_oid, 10).await
+    /// GET нескольких OID одним запросом, значения в порядке запроса
+    pub async fn get_many(&mut self, oids: &[&Oid<'_>]) -> Result<Vec<(Oid<'static>, String)>> {
+        let resp = self
+            .session
+            .get_many(oids)
+            .await
+            .context("SNMP GET запрос не удался")?;
+        if resp.error_status != 0 {
+            anyhow::bail!(
+                "агент вернул ошибку {} (varbind {})",
+                resp.error_status,
+                resp.error_index
+            );
+        }
+
+        Ok(resp
+            .varbinds
+            .map(|(oid, value)| (oid.to_owned(), super::value_to_string(&value)))
+            .collect())
     }
 
     pub async fn walk_bulk(
         &mut self,
         start_oid: &Oid<'_>,
         max_repetitions: u32,
+        max_items: Option<usize>,
+    ) -> Result<Vec<(Oid<'static>, String)>> {
+        self.walk_bulk_within(start_oid, start_oid, max_repetitions, max_items)
+            .await
+    }
+
+    /// Обход с start_oid (не включая его), пока OID внутри поддерева bound
+    pub async fn walk_bulk_within(
+        &mut self,
+        start_oid: &Oid<'_>,
+        bound: &Oid<'_>,
+        max_repetitions: u32,
+        max_items: Option<usize>,
     ) -> Result<Vec<(Oid<'static>, String)>> {
         let mut results: Vec<(Oid<'static>, String)> = Vec::new();
         let mut current_oid = start_oid.to_owned();
@@ -55,14 +90,14 @@ impl SnmpClientV2c {
 
             // Обрабатываем каждый элемент из ответа и конвертируем в строку
             for (oid, value) in resp.varbinds {
-                if !oid.starts_with(start_oid) {
+                if !oid.starts_with(bound) {
                     // Добавляем собранные элементы перед возвратом
                     results.extend(items);
                     return Ok(results);
                 }
 
                 // Конвертируем Value в строку для возможности клонирования
-                let value_str = format!("{:?}", value);
+                let value_str = super::value_to_string(&value);
                 items.push((oid.to_owned(), value_str));
                 current_oid = oid.to_owned();
                 found_any = true;
@@ -74,6 +109,11 @@ impl SnmpClientV2c {
 
             // Добавляем все собранные элементы
             results.extend(items);
+
+            // Дальше не идем, если уже набрали нужное количество
+            if max_items.is_some_and(|max| results.len() >= max) {
+                break;
+            }
         }
 
         Ok(results)
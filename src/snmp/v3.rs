@@ -2,52 +2,13 @@ use anyhow::{Context, Result};
 use snmp2::{AsyncSession, Oid, Value, v3};
 
 pub struct SnmpClientV3 {
-    session: AsyncSession,
+    /// В куче: сессия с буферами занимает больше 100 КБ и иначе раздувает future опроса
+    session: Box<AsyncSession>,
+    /// Адрес агента, как его передали при создании
+    pub(crate) target: String,
 }
 
 impl SnmpClientV3 {
-    // TODO ПРОТЕСТИТЬ!
-    /// Конструктор для noAuthNoPriv (без аутентификации и шифрования)
-    pub async fn new_no_auth_no_priv(target: &str, username: &[u8]) -> Result<Self> {
-        let security = v3::Security::new(username, b"");
-
-        let mut session = AsyncSession::new_v3(target, 0, security)
-            .await
-            .context("Failed to create SNMPv3 session")?;
-
-        // Инициализация для получения engine_id от агента
-        session
-            .init()
-            .await
-            .context("Failed to initialize session")?;
-
-        Ok(Self { session })
-    }
-
-    // TODO ПРОТЕСТИТЬ!
-    /// Конструктор для authNoPriv (с аутентификацией, без шифрования)
-    pub async fn new_auth_no_priv(
-        target: &str,
-        username: &[u8],
-        auth_password: &[u8],
-        auth_protocol: v3::AuthProtocol,
-    ) -> Result<Self> {
-        let security = v3::Security::new(username, auth_password)
-            .with_auth_protocol(auth_protocol)
-            .with_auth(v3::Auth::AuthNoPriv);
-
-        let mut session = AsyncSession::new_v3(target, 0, security)
-            .await
-            .context("Failed to create SNMPv3 session")?;
-
-        session
-            .init()
-            .await
-            .context("Failed to initialize session")?;
-
-        Ok(Self { session })
-    }
-
     // тестировался!
     /// Конструктор для authPriv (с аутентификацией и шифрованием)
     pub async fn new_auth_priv(
@@ -65,20 +26,25 @@ impl SnmpClientV3 {
                 privacy_password: privacy_password.to_vec(),
             });
 
-        let mut session = AsyncSession::new_v3(target, 0, security)
-            .await
-            .context("Failed to create SNMPv3 session")?;
+        let mut session = Box::new(
+            AsyncSession::new_v3(target, 0, security)
+                .await
+                .context("Failed to create SNMPv3 session")?,
+        );
 
         session
             .init()
             .await
             .context("Failed to initialize session")?;
 
-        Ok(Self { session })
+        Ok(Self {
+            session,
+            target: target.to_string(),
+        })
     }
 
     pub async fn get(&mut self, oid: &Oid<'_>) -> Result<Value<'_>> {
-        let resp = self
+        let mut resp = self
             .session
             .get(oid)
             .await
@@ -86,21 +52,51 @@ impl SnmpClientV3 {
 
         let (_, value) = resp
             .varbinds
-            .into_iter()
             .next()
             .ok_or_else(|| anyhow::anyhow!("SNMPv3 ответ пустой"))?;
 
         Ok(value)
     }
-    // тупа копипаст из v2c но пока так =)
-    pub async fn walk(&mut self, start_oid: &Oid<'_>) -> Result<Vec<(Oid<'static>, String)>> {
-        self.walk_bulk(start_oid, 10).await
+
+    /// GET нескольких OID одним запросом, значения в порядке запроса
+    pub async fn get_many(&mut self, oids: &[&Oid<'_>]) -> Result<Vec<(Oid<'static>, String)>> {
+        let resp = self
+            .session
+            .get_many(oids)
+            .await
+            .context("SNMPv3 GET запрос не удался")?;
+        if resp.error_status != 0 {
+            anyhow::bail!(
+                "агент вернул ошибку {} (varbind {})",
+                resp.error_status,
+                resp.error_index
+            );
+        }
+
+        Ok(resp
+            .varbinds
+            .map(|(oid, value)| (oid.to_owned(), super::value_to_string(&value)))
+            .collect())
     }
     // тупа копипаст из v2c но пока так =)
     pub async fn walk_bulk(
         &mut self,
         start_oid: &Oid<'_>,
         max_repetitions: u32,
+        max_items: Option<usize>,
+    ) -> Result<Vec<(Oid<'static>, String)>> {
+        self.walk_bulk_within(start_oid, start_oid, max_repetitions, max_items)
+            .await
+    }
+
+    // тупа копипаст из v2c но пока так =)
+    /// Обход с start_oid (не включая его), пока OID внутри поддерева bound
+    pub async fn walk_bulk_within(
+        &mut self,
+        start_oid: &Oid<'_>,
+        bound: &Oid<'_>,
+        max_repetitions: u32,
+        max_items: Option<usize>,
     ) -> Result<Vec<(Oid<'static>, String)>> {
         let mut results: Vec<(Oid<'static>, String)> = Vec::new();
         let mut current_oid = start_oid.to_owned();
@@ -118,14 +114,14 @@ impl SnmpClientV3 {
 
             // Обрабатываем каждый элемент из ответа и конвертируем в строку
             for (oid, value) in resp.varbinds {
-                if !oid.starts_with(start_oid) {
+                if !oid.starts_with(bound) {
                     // Добавляем собранные элементы перед возвратом
                     results.extend(items);
                     return Ok(results);
                 }
 
                 // Конвертируем Value в строку для возможности клонирования
-                let value_str = format!("{:?}", value);
+                let value_str = super::value_to_string(&value);
                 items.push((oid.to_owned(), value_str));
                 current_oid = oid.to_owned();
                 found_any = true;
@@ -137,6 +133,11 @@ impl SnmpClientV3 {
 
             // Добавляем все собранные элементы
             results.extend(items);
+
+            // Дальше не идем, если уже набрали нужное количество
+            if max_items.is_some_and(|max| results.len() >= max) {
+                break;
+            }
         }
 
         Ok(results)
@@ -0,0 +1,328 @@
+use anyhow::{Context, Result, anyhow, bail};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::{Arc, LazyLock, RwLock};
+
+use crate::config::settings::VendorSettings;
+use crate::mib::{self, format_oid, parse_numeric_oid};
+
+/// Префикс OID частных предприятий: 1.3.6.1.4.1.N
+const ENTERPRISES: &[u32] = &[1, 3, 6, 1, 4, 1];
+
+/// Правило из файла реестра: enterprise N (= 1.3.6.1.4.1.N) или произвольный префикс OID
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RuleRaw {
+    enterprise: Option<u32>,
+    oid: Option<String>,
+    vendor: Option<String>,
+    family: Option<String>,
+    model: Option<String>,
+    os: Option<String>,
+    device_type: Option<String>,
+    /// Префикс агента (Net-SNMP), а не устройства: sysDescr и ENTITY-MIB важнее
+    #[serde(default)]
+    agent: bool,
+}
+
+/// Правило по sysDescr: регулярное выражение, именованные группы model и version
+/// берутся из совпадения
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SysDescrRuleRaw {
+    pattern: String,
+    vendor: Option<String>,
+    family: Option<String>,
+    model: Option<String>,
+    os: Option<String>,
+    device_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct RegistryFile {
+    #[serde(default)]
+    vendors: Vec<RuleRaw>,
+    #[serde(default)]
+    sysdescr: Vec<SysDescrRuleRaw>,
+}
+
+/// Поля правила, которые переносятся на совпавший sysObjectID
+#[derive(Debug, Clone, Default)]
+struct VendorRule {
+    vendor: Option<String>,
+    family: Option<String>,
+    model: Option<String>,
+    os: Option<String>,
+    device_type: Option<String>,
+    agent: bool,
+}
+
+#[derive(Debug)]
+struct SysDescrRule {
+    pattern: Regex,
+    rule: VendorRule,
+}
+
+/// Результат сопоставления sysObjectID с реестром
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct VendorMatch {
+    /// Номер предприятия IANA (1.3.6.1.4.1.N)
+    pub enterprise: Option<u32>,
+    /// Организация по списку IANA PEN
+    pub enterprise_name: Option<String>,
+    pub vendor: Option<String>,
+    pub family: Option<String>,
+    pub model: Option<String>,
+    pub os: Option<String>,
+    pub device_type: Option<String>,
+    /// Самый длинный совпавший префикс из реестра
+    pub matched_prefix: Option<String>,
+    /// Совпало правило агента: тип и ОС лучше брать из sysDescr
+    pub agent: bool,
+}
+
+/// Результат сопоставления sysDescr с правилами реестра
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct SysDescrMatch {
+    pub vendor: Option<String>,
+    pub family: Option<String>,
+    pub model: Option<String>,
+    pub os: Option<String>,
+    pub version: Option<String>,
+    pub device_type: Option<String>,
+}
+
+/// Реестр производителей: правила по префиксам sysObjectID и список IANA PEN
+#[derive(Debug, Default)]
+pub struct VendorRegistry {
+    rules: BTreeMap<Vec<u32>, VendorRule>,
+    /// Правила по sysDescr в порядке файла, срабатывает первое совпавшее
+    sysdescr: Vec<SysDescrRule>,
+    enterprises: HashMap<u32, String>,
+}
+
+impl VendorRegistry {
+    pub fn empty() -> Self {
+        Self::default()
+    }
+
+    /// Загружает правила (YAML) и список IANA PEN: первый из pen_paths, который
+    /// удалось прочитать (полный список, затем выборка из репозитория). Без списка - не ошибка
+    pub fn load(registry_path: impl AsRef<Path>, pen_paths: &[&str]) -> Result<Self> {
+        let registry_path = registry_path.as_ref();
+        let content = std::fs::read_to_string(registry_path)
+            .with_context(|| format!("Не удалось прочитать {}", registry_path.display()))?;
+        let mut registry = Self::from_yaml(&content)
+            .with_context(|| format!("Реестр производителей {}", registry_path.display()))?;
+
+        for pen_path in pen_paths {
+            match std::fs::read_to_string(pen_path) {
+                Ok(content) => {
+                    registry.enterprises = Self::parse_enterprise_numbers(&content);
+                    tracing::debug!("Список IANA PEN: {}", pen_path);
+                    return Ok(registry);
+                }
+                Err(e) => tracing::debug!("Список IANA PEN {} не прочитан: {}", pen_path, e),
+            }
+        }
+        tracing::warn!("Список IANA PEN не загружен: {}", pen_paths.join(", "));
+        Ok(registry)
+    }
+
+    /// Разбирает правила реестра из YAML
+    pub fn from_yaml(content: &str) -> Result<Self> {
+        let file: RegistryFile = serde_yml::from_str(content)?;
+        let tree = mib::global();
+        let mut rules = BTreeMap::new();
+        let mut errors = Vec::new();
+
+        for (position, raw) in file.vendors.into_iter().enumerate() {
+            let prefix = match (raw.enterprise, raw.oid.as_deref()) {
+                (Some(number), None) => Ok([ENTERPRISES, &[number]].concat()),
+                // OID может быть и именем из MIB: CISCO-PRODUCTS-MIB::cat37xxStack
+                (None, Some(oid)) => tree.parse_name(oid),
+                (Some(_), Some(_)) => Err(anyhow!("заданы и enterprise, и oid")),
+                (None, None) => Err(anyhow!("не задан ни enterprise, ни oid")),
+            };
+
+            match prefix {
+                Ok(prefix) => {
+                    let rule = VendorRule {
+                        vendor: raw.vendor,
+                        family: raw.family,
+                        model: raw.model,
+                        os: raw.os,
+                        device_type: raw.device_type,
+                        agent: raw.agent,
+                    };
+                    if rules.insert(prefix.clone(), rule).is_some() {
+                        errors.push(format!(
+                            "правило {}: префикс {} задан повторно",
+                            position + 1,
+                            format_oid(&prefix)
+                        ));
+                    }
+                }
+                Err(e) => errors.push(format!("правило {}: {}", position + 1, e)),
+            }
+        }
+
+        let mut sysdescr = Vec::new();
+        for (position, raw) in file.sysdescr.into_iter().enumerate() {
+            match Regex::new(&raw.pattern) {
+                Ok(pattern) => sysdescr.push(SysDescrRule {
+                    pattern,
+                    rule: VendorRule {
+                        vendor: raw.vendor,
+                        family: raw.family,
+                        model: raw.model,
+                        os: raw.os,
+                        device_type: raw.device_type,
+                        agent: false,
+                    },
+                }),
+                Err(e) => errors.push(format!("sysdescr {}: {}", position + 1, e)),
+            }
+        }
+
+        if !errors.is_empty() {
+            bail!("Ошибки в правилах:\n  {}", errors.join("\n  "));
+        }
+
+        Ok(Self {
+            rules,
+            sysdescr,
+            enterprises: HashMap::new(),
+        })
+    }
+
+    /// Разбирает enterprise-numbers.txt в формате IANA: номер в начале строки,
+    /// на следующей строке с отступом - организация, дальше контакт и e-mail
+    pub fn parse_enterprise_numbers(content: &str) -> HashMap<u32, String> {
+        let mut enterprises = HashMap::new();
+        let mut current = None;
+
+        for line in content.lines() {
+            if let Ok(number) = line.trim_end().parse::<u32>() {
+                current = Some(number);
+                continue;
+            }
+            let organization = line.trim();
+            if line.starts_with(char::is_whitespace) && !organization.is_empty()
+                && let Some(number) = current.take()
+            {
+                enterprises.insert(number, organization.to_string());
+            }
+        }
+
+        enterprises
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.rules.len() + self.sysdescr.len()
+    }
+
+    pub fn enterprise_count(&self) -> usize {
+        self.enterprises.len()
+    }
+
+    /// Организация по номеру IANA PEN
+    pub fn enterprise_name(&self, number: u32) -> Option<&str> {
+        self.enterprises.get(&number).map(String::as_str)
+    }
+
+    /// Сопоставляет sysObjectID с реестром. Правила применяются от короткого префикса
+    /// к длинному, так что самое длинное совпадение переопределяет поля более общих.
+    /// vendor - только из правил, имя по IANA PEN отдается отдельно в enterprise_name
+    pub fn lookup(&self, sys_object_id: &str) -> VendorMatch {
+        let Some(oid) = parse_numeric_oid(sys_object_id) else {
+            return VendorMatch::default();
+        };
+
+        let enterprise = oid
+            .strip_prefix(ENTERPRISES)
+            .and_then(|rest| rest.first())
+            .copied();
+        let mut result = VendorMatch {
+            enterprise,
+            enterprise_name: enterprise
+                .and_then(|number| self.enterprise_name(number))
+                .map(String::from),
+            ..VendorMatch::default()
+        };
+
+        for len in 1..=oid.len() {
+            let Some(rule) = self.rules.get(&oid[..len]) else {
+                continue;
+            };
+            result.vendor = rule.vendor.clone().or(result.vendor);
+            result.family = rule.family.clone().or(result.family);
+            result.model = rule.model.clone().or(result.model);
+            result.os = rule.os.clone().or(result.os);
+            result.device_type = rule.device_type.clone().or(result.device_type);
+            result.agent |= rule.agent;
+            result.matched_prefix = Some(format_oid(&oid[..len]));
+        }
+
+        result
+    }
+
+    /// Первое правило sysDescr, под которое подходит описание.
+    /// Группы (?P<model>..) и (?P<version>..) переопределяют model и задают версию ПО
+    pub fn match_sys_descr(&self, sys_descr: &str) -> Option<SysDescrMatch> {
+        self.sysdescr.iter().find_map(|rule| {
+            let captures = rule.pattern.captures(sys_descr)?;
+            let group = |name: &str| {
+                captures
+                    .name(name)
+                    .map(|m| m.as_str().trim().to_string())
+                    .filter(|value| !value.is_empty())
+            };
+
+            Some(SysDescrMatch {
+                vendor: rule.rule.vendor.clone(),
+                family: rule.rule.family.clone(),
+                model: group("model").or_else(|| rule.rule.model.clone()),
+                os: rule.rule.os.clone(),
+                version: group("version"),
+                device_type: rule.rule.device_type.clone(),
+            })
+        })
+    }
+}
+
+/// Реестр производителей, общий для всех опросов
+static GLOBAL_REGISTRY: LazyLock<RwLock<Arc<VendorRegistry>>> =
+    LazyLock::new(|| RwLock::new(Arc::new(VendorRegistry::empty())));
+
+/// Текущий реестр (пустой, пока не вызван install)
+pub fn global() -> Arc<VendorRegistry> {
+    GLOBAL_REGISTRY.read().unwrap().clone()
+}
+
+/// Заменяет глобальный реестр
+pub fn install(registry: VendorRegistry) {
+    *GLOBAL_REGISTRY.write().unwrap() = Arc::new(registry);
+}
+
+/// Загружает реестр из файлов настроек и делает его глобальным. При ошибке остается
+/// пустой реестр
+pub fn load_and_install(settings: &VendorSettings) {
+    let pen_paths = [
+        settings.enterprise_numbers.as_str(),
+        settings.enterprise_numbers_fallback.as_str(),
+    ];
+    match VendorRegistry::load(&settings.registry, &pen_paths) {
+        Ok(registry) => {
+            tracing::info!(
+                "Реестр производителей: {} правил, {} организаций IANA",
+                registry.rule_count(),
+                registry.enterprise_count()
+            );
+            install(registry);
+        }
+        Err(e) => tracing::warn!("{:#}", e),
+    }
+}